@@ -1,12 +1,16 @@
 use futures::future;
 use futures::{Future, Stream};
 use gotham::handler::{HandlerFuture, IntoHandlerError};
+use gotham::helpers::http::response::create_empty_response;
 use gotham::state::{FromState, State};
 use hyper::body::Chunk;
-use hyper::{Body, Response, StatusCode};
+use hyper::{Body, HeaderMap, Response, StatusCode};
+use ring::constant_time::verify_slices_are_equal;
 
 use crate::api::error::ApiError;
-use crate::api::router::{trace_create_response, trace_state_and_body, WalletContainer};
+use crate::api::router::{
+    trace_create_response, trace_state_and_body, CheckoutToken, WalletContainer,
+};
 use common::Result;
 use wallet::types::{BlockFees, Slate};
 
@@ -90,3 +94,51 @@ fn handle_receive_invoice(state: &State, body: &Chunk) -> Result<Response<Body>>
         serde_json::to_string(&slate)?,
     ))
 }
+
+pub fn receive_and_finalize_tx(mut state: State) -> Box<HandlerFuture> {
+    let future = Body::take_from(&mut state)
+        .concat2()
+        .then(|body| match body {
+            Ok(body) => match handle_receive_and_finalize_tx(&state, &body) {
+                Ok(res) => future::ok((state, res)),
+                Err(e) => future::err((state, ApiError::new(e).into_handler_error())),
+            },
+            Err(e) => future::err((state, e.into_handler_error())),
+        });
+
+    Box::new(future)
+}
+
+/// Does whatever the slate still needs from us in one request: a new slate is received,
+/// or paid when it is an invoice, and a slate that comes back signed is finalized and
+/// posted. Either way the updated slate is returned.
+fn handle_receive_and_finalize_tx(state: &State, body: &Chunk) -> Result<Response<Body>> {
+    trace_state_and_body(state, body);
+    let CheckoutToken(ref token) = *CheckoutToken::borrow_from(&state);
+    let authorized = HeaderMap::borrow_from(&state)
+        .get("X-Checkout-Token")
+        .and_then(|x| x.to_str().ok())
+        .map(|x| verify_slices_are_equal(token.as_bytes(), x.as_bytes()).is_ok())
+        .unwrap_or(false);
+    if !authorized {
+        return Ok(create_empty_response(&state, StatusCode::UNAUTHORIZED));
+    }
+
+    let mut slate: Slate = serde_json::from_slice(&body)?;
+    let mut wallet = WalletContainer::borrow_from(&state).lock()?;
+    if slate.num_participants > slate.participant_data.len() {
+        if slate.tx.inputs().len() == 0 {
+            wallet.process_receiver_initiated_slate(&mut slate)?;
+        } else {
+            wallet.process_sender_initiated_slate(None, &mut slate)?;
+        }
+    } else {
+        wallet.finalize_slate(&mut slate, None)?;
+    }
+    Ok(trace_create_response(
+        &state,
+        StatusCode::OK,
+        mime::APPLICATION_JSON,
+        serde_json::to_string(&slate)?,
+    ))
+}
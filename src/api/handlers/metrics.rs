@@ -0,0 +1,148 @@
+use failure::Error;
+use gotham::handler::{IntoHandlerError, IntoResponse};
+use gotham::state::{FromState, State};
+use hyper::{Body, Response, StatusCode};
+use std::fmt::Write;
+
+use crate::api::error::ApiError;
+use crate::api::router::{trace_create_response, trace_state, MetricsListener, WalletContainer};
+use crate::wallet::types::TxLogEntryType;
+
+pub fn metrics(state: State) -> (State, Response<Body>) {
+    let res = match handle_metrics(&state) {
+        Ok(res) => res,
+        Err(e) => ApiError::new(e).into_handler_error().into_response(&state),
+    };
+    (state, res)
+}
+
+fn handle_metrics(state: &State) -> Result<Response<Body>, Error> {
+    trace_state(state);
+    let container = WalletContainer::borrow_from(&state);
+    let mut body = String::new();
+
+    {
+        let wallet = container.lock()?;
+        let unlocked = !wallet.is_locked();
+        gauge(
+            &mut body,
+            "wallet713_wallet_unlocked",
+            "Whether the wallet is unlocked, the wallet metrics are only reported when it is",
+            unlocked as u64,
+        );
+        if unlocked {
+            // the stored state only, a scrape should not wait on the node
+            let info = wallet.retrieve_summary_info(false)?;
+            writeln!(
+                body,
+                "# HELP wallet713_balance_nanogrins The wallet balance by state"
+            )?;
+            writeln!(body, "# TYPE wallet713_balance_nanogrins gauge")?;
+            for (kind, amount) in &[
+                ("total", info.total),
+                ("spendable", info.amount_currently_spendable),
+                ("awaiting_confirmation", info.amount_awaiting_confirmation),
+                ("immature", info.amount_immature),
+                ("locked", info.amount_locked),
+            ] {
+                writeln!(
+                    body,
+                    "wallet713_balance_nanogrins{{state=\"{}\"}} {}",
+                    kind, amount
+                )?;
+            }
+
+            let (_, txs) = wallet.retrieve_txs(false, None, None)?;
+            let pending = txs
+                .iter()
+                .filter(|tx| !tx.confirmed)
+                .filter(|tx| match tx.tx_type {
+                    TxLogEntryType::TxSentCancelled | TxLogEntryType::TxReceivedCancelled => false,
+                    _ => true,
+                })
+                .count();
+            gauge(
+                &mut body,
+                "wallet713_pending_txs",
+                "Transactions that are neither confirmed nor cancelled",
+                pending as u64,
+            );
+        }
+    }
+
+    let publisher = MetricsListener::borrow_from(&state).0.lock().clone();
+    gauge(
+        &mut body,
+        "wallet713_grinbox_connected",
+        "Whether the grinbox listener is running",
+        publisher.as_ref().map(|p| p.is_running()).unwrap_or(false) as u64,
+    );
+    if let Some(publisher) = publisher {
+        let stats = publisher.stats();
+        writeln!(body, "# HELP wallet713_grinbox_messages_total Slates exchanged with the grinbox relay since the listener started")?;
+        writeln!(body, "# TYPE wallet713_grinbox_messages_total counter")?;
+        for (kind, count) in &[
+            ("sent", stats.sent),
+            ("received", stats.received),
+            ("failed", stats.failed),
+        ] {
+            writeln!(
+                body,
+                "wallet713_grinbox_messages_total{{kind=\"{}\"}} {}",
+                kind, count
+            )?;
+        }
+        writeln!(body, "# HELP wallet713_grinbox_reconnects_total Times the grinbox connection was re-established")?;
+        writeln!(body, "# TYPE wallet713_grinbox_reconnects_total counter")?;
+        writeln!(
+            body,
+            "wallet713_grinbox_reconnects_total {}",
+            stats.reconnects
+        )?;
+    }
+
+    Ok(trace_create_response(
+        &state,
+        StatusCode::OK,
+        mime::TEXT_PLAIN,
+        body,
+    ))
+}
+
+/// The wallet, node and listener status as json, answered with 503 while the wallet isn't
+/// healthy so a probe can check the status code alone
+pub fn status(state: State) -> (State, Response<Body>) {
+    let res = match handle_status(&state) {
+        Ok(res) => res,
+        Err(e) => ApiError::new(e).into_handler_error().into_response(&state),
+    };
+    (state, res)
+}
+
+fn handle_status(state: &State) -> Result<Response<Body>, Error> {
+    trace_state(state);
+    let container = WalletContainer::borrow_from(&state);
+    let grinbox_running = MetricsListener::borrow_from(&state)
+        .0
+        .lock()
+        .as_ref()
+        .map(|p| p.is_running());
+    let status = container.lock()?.status_json(grinbox_running, false);
+    let code = match status["healthy"].as_bool() {
+        Some(true) => StatusCode::OK,
+        _ => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    Ok(trace_create_response(
+        &state,
+        code,
+        mime::APPLICATION_JSON,
+        serde_json::to_string(&status)?,
+    ))
+}
+
+fn gauge(body: &mut String, name: &str, help: &str, value: u64) {
+    body.push_str(&format!(
+        "# HELP {} {}\n# TYPE {} gauge\n{} {}\n",
+        name, help, name, name, value
+    ));
+}
@@ -1,2 +1,3 @@
 pub mod foreign;
+pub mod metrics;
 pub mod owner;
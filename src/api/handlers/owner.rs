@@ -187,7 +187,7 @@ pub fn handle_finalize_tx(state: &State, body: &Chunk) -> Result<Response<Body>,
     trace_state_and_body(state, body);
     let mut slate: Slate = serde_json::from_slice(&body)?;
     let container = WalletContainer::borrow_from(&state);
-    let wallet = container.lock()?;
+    let mut wallet = container.lock()?;
 
     wallet.finalize_slate(&mut slate, None)?;
 
@@ -293,12 +293,13 @@ pub fn handle_issue_iou_tx(state: &State, body: &Chunk) -> Result<Response<Body>
     trace_state_and_body(state, body);
     let body: IssueSendBody = serde_json::from_slice(&body)?;
     let container = WalletContainer::borrow_from(&state);
-    let wallet = container.lock()?;
+    let mut wallet = container.lock()?;
     let selection_strategy = match body.selection_strategy_is_use_all {
         true => "all",
         false => "",
     };
 
+    // api callers pick the confirmations explicitly, spending unconfirmed change is up to them
     let res = match body.method {
         IssueSendMethod::None => {
             let slate = wallet.initiate_send_tx(
@@ -308,7 +309,13 @@ pub fn handle_issue_iou_tx(state: &State, body: &Chunk) -> Result<Response<Body>
                 selection_strategy,
                 body.num_change_outputs,
                 body.max_outputs,
+                None,
+                None,
                 body.message,
+                None,
+                false,
+                false,
+                true,
             )?;
             serde_json::to_string(&slate)?
         }
@@ -326,7 +333,13 @@ pub fn handle_issue_iou_tx(state: &State, body: &Chunk) -> Result<Response<Body>
                 selection_strategy,
                 body.num_change_outputs,
                 body.max_outputs,
+                None,
+                None,
                 body.message,
+                None,
+                false,
+                false,
+                true,
             )?;
             publisher.post_slate(&slate, &address)?;
             serde_json::to_string(&slate)?
@@ -345,7 +358,13 @@ pub fn handle_issue_iou_tx(state: &State, body: &Chunk) -> Result<Response<Body>
                 selection_strategy,
                 body.num_change_outputs,
                 body.max_outputs,
+                None,
+                None,
                 body.message,
+                None,
+                false,
+                false,
+                true,
             )?;
             publisher.post_slate(&slate, &address)?;
             serde_json::to_string(&slate)?
@@ -362,7 +381,13 @@ pub fn handle_issue_iou_tx(state: &State, body: &Chunk) -> Result<Response<Body>
                 selection_strategy,
                 body.num_change_outputs,
                 body.max_outputs,
+                None,
+                None,
                 body.message,
+                None,
+                false,
+                false,
+                true,
             )?;
             let mut slate: Slate = grin_api::client::post(url.as_str(), None, &slate)?;
             wallet.finalize_slate(&mut slate, None)?;
@@ -383,7 +408,13 @@ pub fn handle_issue_iou_tx(state: &State, body: &Chunk) -> Result<Response<Body>
                 selection_strategy,
                 body.num_change_outputs,
                 body.max_outputs,
+                None,
+                None,
                 body.message,
+                None,
+                false,
+                false,
+                true,
             )?;
             let json = serde_json::to_string(&slate).unwrap();
             file.write_all(json.as_bytes())?;
@@ -12,7 +12,7 @@ use mime::Mime;
 use std::panic::RefUnwindSafe;
 
 use crate::api::auth::BasicAuthMiddleware;
-use crate::api::handlers::{foreign, owner};
+use crate::api::handlers::{foreign, metrics, owner};
 use crate::broker::{GrinboxPublisher, GrinboxSubscriber, KeybasePublisher, KeybaseSubscriber};
 use crate::wallet::types::{Arc, Mutex, MutexGuard};
 use crate::wallet::Wallet;
@@ -27,6 +27,17 @@ pub struct WalletContainer {
 
 impl RefUnwindSafe for WalletContainer {}
 
+/// The grinbox listener the metrics report on. The REPL replaces it as listeners are
+/// started and stopped, so the endpoint follows the current one
+#[derive(Clone, StateData)]
+pub struct MetricsListener(pub Arc<Mutex<Option<GrinboxPublisher>>>);
+
+impl RefUnwindSafe for MetricsListener {}
+
+/// The token a web backend has to present to use the checkout route
+#[derive(Clone, StateData)]
+pub struct CheckoutToken(pub String);
+
 impl WalletContainer {
     fn new(
         wallet: Arc<Mutex<Wallet>>,
@@ -157,9 +168,13 @@ pub fn build_foreign_api_router(
     grinbox_broker: Option<(GrinboxPublisher, GrinboxSubscriber)>,
     keybase_broker: Option<(KeybasePublisher, KeybaseSubscriber)>,
     foreign_api_secret: Option<String>,
+    checkout_api_token: Option<String>,
 ) -> Router {
     let grinbox_publisher = grinbox_broker.map(|(p, _)| p);
     let keybase_publisher = keybase_broker.map(|(p, _)| p);
+    // an empty token would authorize any request with an empty header
+    let checkout_api_token = checkout_api_token.filter(|token| !token.trim().is_empty());
+    let has_checkout = checkout_api_token.is_some();
 
     let (chain, pipelines) = single_pipeline(
         new_pipeline()
@@ -169,11 +184,43 @@ pub fn build_foreign_api_router(
                 grinbox_publisher,
                 keybase_publisher,
             )))
+            .add(StateMiddleware::new(CheckoutToken(
+                checkout_api_token.unwrap_or_default(),
+            )))
             .build(),
     );
 
     build_router(chain, pipelines, |route| {
         build_foreign_api(route);
+        // only served when a token is configured, it moves funds without a prompt
+        if has_checkout {
+            route
+                .post("/v1/wallet/foreign/receive_and_finalize_tx")
+                .to(foreign::receive_and_finalize_tx);
+        }
+    })
+}
+
+/// A router serving the wallet and listener metrics in the Prometheus text format,
+/// behind basic auth with the metrics secret.
+pub fn build_metrics_router(
+    wallet: Arc<Mutex<Wallet>>,
+    grinbox_publisher: Arc<Mutex<Option<GrinboxPublisher>>>,
+    metrics_api_secret: String,
+) -> Router {
+    let (chain, pipelines) = single_pipeline(
+        new_pipeline()
+            .add(BasicAuthMiddleware::new(Some(metrics_api_secret)))
+            .add(StateMiddleware::new(WalletContainer::new(
+                wallet, None, None,
+            )))
+            .add(StateMiddleware::new(MetricsListener(grinbox_publisher)))
+            .build(),
+    );
+
+    build_router(chain, pipelines, |route| {
+        route.get("/metrics").to(metrics::metrics);
+        route.get("/status").to(metrics::status);
     })
 }
 
@@ -1,23 +1,38 @@
+use chrono::{DateTime, Utc};
+use std::io::ErrorKind as IoErrorKind;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
 use ws::util::Token;
 use ws::{
-    connect, CloseCode, Error as WsError, ErrorKind as WsErrorKind, Handler, Handshake, Message,
-    Result as WsResult, Sender,
+    Builder, CloseCode, Error as WsError, ErrorKind as WsErrorKind, Handler, Handshake, Message,
+    Result as WsResult, Sender, Settings,
 };
 
 use grin_wallet::Slate;
+use uuid::Uuid;
 
 use crate::wallet::types::{TxProof, TxProofErrorKind};
-use common::crypto::{sign_challenge, Hex, SecretKey};
+use common::crypto::{sign_challenge, verify_signature, Hex, SecretKey, Signature};
 use common::message::EncryptedMessage;
 use common::{Arc, ErrorKind, Mutex, Result};
 use contacts::{Address, GrinboxAddress, DEFAULT_GRINBOX_PORT};
 
-use super::protocol::{ProtocolRequest, ProtocolResponse};
+use super::protocol::{ProtocolError, ProtocolRequest, ProtocolResponse, WalletMessage};
 use super::types::{CloseReason, Publisher, Subscriber, SubscriptionHandler};
 
 const KEEPALIVE_TOKEN: Token = Token(1);
+const CONNECT_TIMEOUT_TOKEN: Token = Token(2);
 const KEEPALIVE_INTERVAL_MS: u64 = 30_000;
+const MAX_CLOCK_SKEW_SECS: i64 = 60;
+/// How long a post waits for the relay to acknowledge it. Relays that never do are
+/// trusted to have delivered the slate, as before
+const POST_ACK_TIMEOUT_SECS: u64 = 3;
+/// Room for the header of a websocket frame on top of its payload
+const MAX_FRAME_HEADER_BYTES: usize = 14;
+/// Size of the fragments relays split their messages into, the ws default
+const RELAY_FRAGMENT_BYTES: usize = 65_535;
 
 #[derive(Clone)]
 pub struct GrinboxPublisher {
@@ -31,15 +46,41 @@ impl GrinboxPublisher {
         address: &GrinboxAddress,
         secret_key: &SecretKey,
         protocol_unsecure: bool,
+        max_message_bytes: usize,
+        timeout_secs: u64,
     ) -> Result<Self> {
         Ok(Self {
             address: address.clone(),
-            broker: GrinboxBroker::new(protocol_unsecure)?,
+            broker: GrinboxBroker::new(protocol_unsecure, max_message_bytes, timeout_secs)?,
             secret_key: secret_key.clone(),
         })
     }
 }
 
+impl GrinboxPublisher {
+    /// The message counters of this listener since it was started
+    pub fn stats(&self) -> GrinboxStats {
+        self.broker.stats.lock().clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.broker.is_running()
+    }
+
+    /// Checks that the relay resolves and accepts connections, telling which of the two
+    /// failed, as the listener itself only retries in the background
+    pub fn probe(&self) -> Result<()> {
+        self.broker.probe(&self.address)
+    }
+
+    /// Checks that the wallet behind `to` is listening and returns how long it took
+    /// to answer
+    pub fn ping(&self, to: &Address) -> Result<Duration> {
+        let to = GrinboxAddress::from_str(&to.to_string())?;
+        self.broker.ping(&to, &self.address, &self.secret_key)
+    }
+}
+
 impl Publisher for GrinboxPublisher {
     fn post_slate(&self, slate: &Slate, to: &Address) -> Result<()> {
         let to = GrinboxAddress::from_str(&to.to_string())?;
@@ -85,6 +126,46 @@ impl Subscriber for GrinboxSubscriber {
 struct GrinboxBroker {
     inner: Arc<Mutex<Option<Sender>>>,
     protocol_unsecure: bool,
+    max_message_bytes: usize,
+    timeout_secs: u64,
+    stats: Arc<Mutex<GrinboxStats>>,
+    session: Arc<Mutex<RelaySession>>,
+}
+
+/// Counts of the messages a grinbox listener exchanged with the relay
+#[derive(Clone, Debug, Default)]
+pub struct GrinboxStats {
+    /// Slates posted to the relay
+    pub sent: u64,
+    /// Slates received and handed over to the wallet
+    pub received: u64,
+    /// Slates that could not be posted, or were received but could not be read
+    pub failed: u64,
+    /// Times the connection to the relay was re-established after dropping
+    pub reconnects: u64,
+}
+
+/// What a listener knows of its session with the relay
+struct RelaySession {
+    /// Challenge of the current connection, which requests are signed with
+    challenge: Option<String>,
+    /// Whether a post is waiting for the relay to acknowledge it
+    awaiting_post: bool,
+    /// Whether the relay took the last post, once it answered
+    posted: Option<bool>,
+    /// Nonce of the last pong received
+    pong: Option<String>,
+}
+
+impl RelaySession {
+    fn new() -> Self {
+        Self {
+            challenge: None,
+            awaiting_post: false,
+            posted: None,
+            pong: None,
+        }
+    }
 }
 
 struct ConnectionMetadata {
@@ -102,54 +183,173 @@ impl ConnectionMetadata {
 }
 
 impl GrinboxBroker {
-    fn new(protocol_unsecure: bool) -> Result<Self> {
+    fn new(protocol_unsecure: bool, max_message_bytes: usize, timeout_secs: u64) -> Result<Self> {
         Ok(Self {
             inner: Arc::new(Mutex::new(None)),
             protocol_unsecure,
+            max_message_bytes,
+            timeout_secs,
+            stats: Arc::new(Mutex::new(GrinboxStats::default())),
+            session: Arc::new(Mutex::new(RelaySession::new())),
         })
     }
 
+    fn url(&self, address: &GrinboxAddress) -> String {
+        let scheme = match self.protocol_unsecure {
+            true => "ws",
+            false => "wss",
+        };
+        format!(
+            "{}://{}:{}",
+            scheme,
+            address.domain,
+            address.port.unwrap_or(DEFAULT_GRINBOX_PORT)
+        )
+    }
+
+    fn probe(&self, address: &GrinboxAddress) -> Result<()> {
+        let port = address.port.unwrap_or(DEFAULT_GRINBOX_PORT);
+        let dns_failure = || ErrorKind::GrinboxDnsFailure(address.domain.clone());
+        let addrs: Vec<SocketAddr> = (address.domain.as_str(), port)
+            .to_socket_addrs()
+            .map_err(|_| dns_failure())?
+            .collect();
+        if addrs.is_empty() {
+            return Err(dns_failure().into());
+        }
+        // the relay is up if any of its addresses answers, report the last failure
+        let mut last_error = None;
+        for addr in addrs.iter() {
+            match TcpStream::connect_timeout(addr, Duration::from_secs(self.timeout_secs)) {
+                Ok(_) => return Ok(()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        let error = match last_error.map(|e| e.kind()) {
+            Some(IoErrorKind::TimedOut) => ErrorKind::Timeout(self.timeout_secs),
+            _ => ErrorKind::GrinboxConnectionRefused(self.url(address)),
+        };
+        Err(error.into())
+    }
+
+    fn wait_for<T, F>(&self, deadline: Instant, f: F) -> Result<T>
+    where
+        F: Fn(&RelaySession) -> Option<T>,
+    {
+        loop {
+            if let Some(value) = f(&self.session.lock()) {
+                return Ok(value);
+            }
+            if Instant::now() >= deadline {
+                Err(ErrorKind::Timeout(self.timeout_secs))?;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
     fn post_slate(
         &self,
         slate: &Slate,
         to: &GrinboxAddress,
         from: &GrinboxAddress,
         secret_key: &SecretKey,
+    ) -> Result<()> {
+        let result = self.post(
+            &serde_json::to_string(&slate).unwrap(),
+            to,
+            from,
+            secret_key,
+        );
+        let mut stats = self.stats.lock();
+        match result {
+            Ok(_) => stats.sent += 1,
+            Err(_) => stats.failed += 1,
+        }
+        result
+    }
+
+    fn ping(
+        &self,
+        to: &GrinboxAddress,
+        from: &GrinboxAddress,
+        secret_key: &SecretKey,
+    ) -> Result<Duration> {
+        let nonce = Uuid::new_v4().to_string();
+        let ping = WalletMessage::Ping {
+            nonce: nonce.clone(),
+        };
+        self.session.lock().pong = None;
+        let started = Instant::now();
+        self.post(&serde_json::to_string(&ping).unwrap(), to, from, secret_key)?;
+        let deadline = started + Duration::from_secs(self.timeout_secs);
+        self.wait_for(deadline, |session| match session.pong {
+            Some(ref pong) if *pong == nonce => Some(()),
+            _ => None,
+        })
+        .map_err(|_| ErrorKind::PingTimeout(to.stripped(), self.timeout_secs))?;
+        Ok(started.elapsed())
+    }
+
+    /// Posts `content` encrypted for `to`, and waits for the relay to take it
+    fn post(
+        &self,
+        content: &str,
+        to: &GrinboxAddress,
+        from: &GrinboxAddress,
+        secret_key: &SecretKey,
     ) -> Result<()> {
         if !self.is_running() {
             return Err(ErrorKind::ClosedListener("grinbox".to_string()).into());
         }
 
+        let request = GrinboxBroker::post_request(content, to, from, secret_key)?;
+        {
+            let mut session = self.session.lock();
+            session.awaiting_post = true;
+            session.posted = None;
+        }
+        let mut result: Result<()> = if let Some(ref sender) = *self.inner.lock() {
+            sender
+                .send(serde_json::to_string(&request).unwrap())
+                .map_err(|_| ErrorKind::GenericError("failed posting slate!".to_string()).into())
+        } else {
+            Err(ErrorKind::GenericError("failed posting slate!".to_string()).into())
+        };
+        if result.is_ok() {
+            let deadline = Instant::now() + Duration::from_secs(POST_ACK_TIMEOUT_SECS);
+            let delivered = self
+                .wait_for(deadline, |session| session.posted)
+                .unwrap_or(true);
+            if !delivered {
+                result = Err(ErrorKind::RecipientOffline(to.stripped()).into());
+            }
+        }
+        self.session.lock().awaiting_post = false;
+        result
+    }
+
+    fn post_request(
+        content: &str,
+        to: &GrinboxAddress,
+        from: &GrinboxAddress,
+        secret_key: &SecretKey,
+    ) -> Result<ProtocolRequest> {
         let pkey = to.public_key()?;
         let skey = secret_key.clone();
-        let message = EncryptedMessage::new(
-            serde_json::to_string(&slate).unwrap(),
-            &to,
-            &pkey,
-            &skey,
-        )
-            .map_err(|_| {
-                WsError::new(WsErrorKind::Protocol, "could not encrypt slate!")
-            })?;
+        let message = EncryptedMessage::new(content.to_string(), &to, &pkey, &skey)
+            .map_err(|_| WsError::new(WsErrorKind::Protocol, "could not encrypt slate!"))?;
         let slate_str = serde_json::to_string(&message).unwrap();
 
         let mut challenge = String::new();
         challenge.push_str(&slate_str);
 
         let signature = GrinboxClient::generate_signature(&challenge, secret_key);
-        let request = ProtocolRequest::PostSlate {
+        Ok(ProtocolRequest::PostSlate {
             from: from.stripped(),
             to: to.stripped(),
             str: slate_str,
             signature,
-        };
-
-        if let Some(ref sender) = *self.inner.lock() {
-            sender.send(serde_json::to_string(&request).unwrap())
-                .map_err(|_| ErrorKind::GenericError("failed posting slate!".to_string()).into())
-        } else {
-            Err(ErrorKind::GenericError("failed posting slate!".to_string()).into())
-        }
+        })
     }
 
     fn subscribe(
@@ -159,25 +359,15 @@ impl GrinboxBroker {
         handler: Box<SubscriptionHandler + Send>,
     ) -> Result<()> {
         let handler = Arc::new(Mutex::new(handler));
-        let url = {
-            let cloned_address = address.clone();
-            match self.protocol_unsecure {
-                true => format!(
-                    "ws://{}:{}",
-                    cloned_address.domain,
-                    cloned_address.port.unwrap_or(DEFAULT_GRINBOX_PORT)
-                ),
-                false => format!(
-                    "wss://{}:{}",
-                    cloned_address.domain,
-                    cloned_address.port.unwrap_or(DEFAULT_GRINBOX_PORT)
-                ),
-            }
-        };
+        let url = self.url(address);
         let secret_key = secret_key.clone();
+        let max_message_bytes = self.max_message_bytes;
+        let timeout_secs = self.timeout_secs;
         let cloned_address = address.clone();
         let cloned_inner = self.inner.clone();
         let cloned_handler = handler.clone();
+        let stats = self.stats.clone();
+        let session = self.session.clone();
         thread::spawn(move || {
             let connection_meta_data = Arc::new(Mutex::new(ConnectionMetadata::new()));
             loop {
@@ -185,19 +375,34 @@ impl GrinboxBroker {
                 let cloned_handler = cloned_handler.clone();
                 let cloned_cloned_inner = cloned_inner.clone();
                 let cloned_connection_meta_data = connection_meta_data.clone();
-                let result = connect(url.clone(), move |sender| {
+                let cloned_url = url.clone();
+                let cloned_stats = stats.clone();
+                let cloned_session = session.clone();
+                let result = connect(&url, max_message_bytes, move |sender| {
                     {
                         let mut guard = cloned_cloned_inner.lock();
                         *guard = Some(sender.clone());
                     }
 
+                    // give up on the connection attempt if the relay doesn't answer in time
+                    sender
+                        .timeout(timeout_secs * 1000, CONNECT_TIMEOUT_TOKEN)
+                        .is_ok();
+
                     let client = GrinboxClient {
                         sender,
+                        url: cloned_url.clone(),
                         handler: cloned_handler.clone(),
                         challenge: None,
                         address: cloned_address.clone(),
                         secret_key,
+                        max_message_bytes,
+                        timeout_secs,
+                        is_open: false,
+                        clock_skew: None,
                         connection_meta_data: cloned_connection_meta_data.clone(),
+                        stats: cloned_stats.clone(),
+                        session: cloned_session.clone(),
                     };
                     client
                 });
@@ -243,13 +448,43 @@ impl GrinboxBroker {
     }
 }
 
+/// Connects to `url` like `ws::connect`, but with the input buffer capped at
+/// `max_message_bytes`, so a frame over the limit closes the connection instead of
+/// being buffered in full. Fragmented messages are capped to as many fragments as
+/// a relay splits `max_message_bytes` into.
+fn connect<F, H>(url: &str, max_message_bytes: usize, factory: F) -> WsResult<()>
+where
+    F: FnMut(Sender) -> H,
+    H: Handler,
+{
+    let settings = Settings {
+        in_buffer_capacity: max_message_bytes + MAX_FRAME_HEADER_BYTES,
+        in_buffer_grow: false,
+        fragments_capacity: max_message_bytes / RELAY_FRAGMENT_BYTES + 1,
+        fragments_grow: false,
+        ..Settings::default()
+    };
+    let url = Url::parse(url).map_err(|e| WsError::new(WsErrorKind::Internal, e.to_string()))?;
+    let mut ws = Builder::new().with_settings(settings).build(factory)?;
+    ws.connect(url)?;
+    ws.run()?;
+    Ok(())
+}
+
 struct GrinboxClient {
     sender: Sender,
+    url: String,
     handler: Arc<Mutex<Box<SubscriptionHandler + Send>>>,
     challenge: Option<String>,
     address: GrinboxAddress,
     secret_key: SecretKey,
+    max_message_bytes: usize,
+    timeout_secs: u64,
+    is_open: bool,
+    clock_skew: Option<i64>,
     connection_meta_data: Arc<Mutex<ConnectionMetadata>>,
+    stats: Arc<Mutex<GrinboxStats>>,
+    session: Arc<Mutex<RelaySession>>,
 }
 
 impl GrinboxClient {
@@ -269,18 +504,84 @@ impl GrinboxClient {
         Ok(())
     }
 
+    /// Seconds our clock is ahead (positive) or behind (negative) the relay,
+    /// based on the `Date` header of the handshake response
+    fn clock_skew(shake: &Handshake) -> Option<i64> {
+        let date = shake.response.header("Date")?;
+        let date = std::str::from_utf8(date).ok()?;
+        let relay_time = DateTime::parse_from_rfc2822(date).ok()?;
+        Some(Utc::now().timestamp() - relay_time.timestamp())
+    }
+
+    fn has_clock_skew(&self) -> bool {
+        match self.clock_skew {
+            Some(skew) => skew.abs() > MAX_CLOCK_SKEW_SECS,
+            None => false,
+        }
+    }
+
     fn send(&self, request: &ProtocolRequest) -> Result<()> {
         let request = serde_json::to_string(&request).unwrap();
         self.sender.send(request)?;
         Ok(())
     }
+
+    /// Reads a post that holds a message of another wallet rather than a slate
+    fn wallet_message(
+        &self,
+        from: &str,
+        message: &str,
+        challenge: &str,
+        signature: &str,
+    ) -> Option<(GrinboxAddress, WalletMessage)> {
+        let address = GrinboxAddress::from_str(from).ok()?;
+        let public_key = address.public_key().ok()?;
+        let signature = Signature::from_hex(signature).ok()?;
+        verify_signature(
+            &format!("{}{}", message, challenge),
+            &signature,
+            &public_key,
+        )
+        .ok()?;
+        let encrypted_message: EncryptedMessage = serde_json::from_str(message).ok()?;
+        let key = encrypted_message.key(&public_key, &self.secret_key).ok()?;
+        let decrypted_message = encrypted_message.decrypt_with_key(&key).ok()?;
+        let wallet_message = serde_json::from_str(&decrypted_message).ok()?;
+        Some((address, wallet_message))
+    }
+
+    fn on_wallet_message(&self, from: &GrinboxAddress, message: WalletMessage) {
+        match message {
+            WalletMessage::Ping { nonce } => {
+                let pong = serde_json::to_string(&WalletMessage::Pong { nonce }).unwrap();
+                let request =
+                    GrinboxBroker::post_request(&pong, from, &self.address, &self.secret_key);
+                if let Err(e) = request.and_then(|request| self.send(&request)) {
+                    error!("could not answer ping from {}: {}", from.stripped(), e);
+                }
+            }
+            WalletMessage::Pong { nonce } => self.session.lock().pong = Some(nonce),
+        }
+    }
 }
 
 impl Handler for GrinboxClient {
-    fn on_open(&mut self, _shake: Handshake) -> WsResult<()> {
+    fn on_open(&mut self, shake: Handshake) -> WsResult<()> {
+        self.is_open = true;
+        self.session.lock().challenge = None;
+        self.clock_skew = GrinboxClient::clock_skew(&shake);
+        if self.has_clock_skew() {
+            cli_message!(
+                "WARNING: your clock is {} seconds off from the grinbox relay. \
+                 subscribing may fail, please check your system clock.",
+                self.clock_skew.unwrap()
+            );
+        }
+
         let mut guard = self.connection_meta_data.lock();
 
         if guard.connected_at_least_once {
+            self.stats.lock().reconnects += 1;
             self.handler.lock().on_reestablished();
         } else {
             self.handler.lock().on_open();
@@ -299,6 +600,14 @@ impl Handler for GrinboxClient {
                 self.sender.ping(vec![])?;
                 self.sender.timeout(KEEPALIVE_INTERVAL_MS, KEEPALIVE_TOKEN)
             }
+            CONNECT_TIMEOUT_TOKEN if self.is_open => Ok(()),
+            CONNECT_TIMEOUT_TOKEN => {
+                cli_message!("{}", ErrorKind::Timeout(self.timeout_secs));
+                Err(WsError::new(
+                    WsErrorKind::Internal,
+                    "timed out connecting to grinbox!",
+                ))
+            }
             _ => Err(WsError::new(
                 WsErrorKind::Internal,
                 "Invalid timeout token encountered!",
@@ -307,10 +616,23 @@ impl Handler for GrinboxClient {
     }
 
     fn on_message(&mut self, msg: Message) -> WsResult<()> {
+        // a message assembled from fragments can still be over the limit
+        if msg.len() > self.max_message_bytes {
+            cli_message!(
+                "rejecting grinbox message of {} bytes (limit is {} bytes)",
+                msg.len(),
+                self.max_message_bytes
+            );
+            return Ok(());
+        }
+
         let response = match serde_json::from_str::<ProtocolResponse>(&msg.to_string()) {
             Ok(x) => x,
             Err(_) => {
-                cli_message!("could not parse response");
+                cli_message!(
+                    "{}",
+                    ErrorKind::GrinboxProtocolError(String::from("could not parse response"))
+                );
                 return Ok(());
             }
         };
@@ -318,6 +640,7 @@ impl Handler for GrinboxClient {
         match response {
             ProtocolResponse::Challenge { str } => {
                 self.challenge = Some(str.clone());
+                self.session.lock().challenge = Some(str.clone());
                 self.subscribe(&str).map_err(|_| {
                     WsError::new(WsErrorKind::Protocol, "error attempting to subscribe!")
                 })?;
@@ -328,6 +651,12 @@ impl Handler for GrinboxClient {
                 challenge,
                 signature,
             } => {
+                if let Some((from, message)) =
+                    self.wallet_message(&from, &str, &challenge, &signature)
+                {
+                    self.on_wallet_message(&from, message);
+                    return Ok(());
+                }
                 let (mut slate, mut tx_proof) = match TxProof::from_response(
                     from,
                     str,
@@ -337,54 +666,92 @@ impl Handler for GrinboxClient {
                     Some(&self.address),
                 ) {
                     Ok(x) => x,
-                    Err(TxProofErrorKind::ParseAddress) => {
-                        cli_message!("could not parse address!");
-                        return Ok(());
-                    }
-                    Err(TxProofErrorKind::ParsePublicKey) => {
-                        cli_message!("could not parse public key!");
-                        return Ok(());
-                    }
-                    Err(TxProofErrorKind::ParseSignature) => {
-                        cli_message!("could not parse signature!");
-                        return Ok(());
-                    }
-                    Err(TxProofErrorKind::VerifySignature) => {
-                        cli_message!("invalid slate signature!");
-                        return Ok(());
-                    }
-                    Err(TxProofErrorKind::ParseEncryptedMessage) => {
-                        cli_message!("could not parse encrypted slate!");
-                        return Ok(());
-                    }
-                    Err(TxProofErrorKind::VerifyDestination) => {
-                        cli_message!("could not verify destination!");
-                        return Ok(());
-                    }
-                    Err(TxProofErrorKind::DecryptionKey) => {
-                        cli_message!("could not determine decryption key!");
-                        return Ok(());
-                    }
-                    Err(TxProofErrorKind::DecryptMessage) => {
-                        cli_message!("could not decrypt slate!");
-                        return Ok(());
-                    }
-                    Err(TxProofErrorKind::ParseSlate) => {
-                        cli_message!("could not parse decrypted slate!");
+                    Err(e) => {
+                        self.stats.lock().failed += 1;
+                        match e {
+                            TxProofErrorKind::ParseAddress => {
+                                cli_message!("could not parse address!")
+                            }
+                            TxProofErrorKind::ParsePublicKey => {
+                                cli_message!("could not parse public key!")
+                            }
+                            TxProofErrorKind::ParseSignature => {
+                                cli_message!("could not parse signature!")
+                            }
+                            TxProofErrorKind::VerifySignature => {
+                                cli_message!("invalid slate signature!")
+                            }
+                            TxProofErrorKind::ParseEncryptedMessage => {
+                                cli_message!("could not parse encrypted slate!")
+                            }
+                            TxProofErrorKind::VerifyDestination => {
+                                cli_message!("could not verify destination!")
+                            }
+                            TxProofErrorKind::DecryptionKey => {
+                                cli_message!("could not determine decryption key!")
+                            }
+                            TxProofErrorKind::DecryptMessage => {
+                                cli_message!("could not decrypt slate!")
+                            }
+                            TxProofErrorKind::ParseSlate => {
+                                cli_message!("could not parse decrypted slate!")
+                            }
+                        }
                         return Ok(());
                     }
                 };
 
+                self.stats.lock().received += 1;
                 let address = tx_proof.address.clone();
                 self.handler
                     .lock()
                     .on_slate(&address, &mut slate, Some(&mut tx_proof));
             }
+            ProtocolResponse::Error {
+                kind: ProtocolError::InvalidSignature,
+                description: _,
+            }
+            | ProtocolResponse::Error {
+                kind: ProtocolError::InvalidChallenge,
+                description: _,
+            } if self.has_clock_skew() => {
+                cli_message!("{}", ErrorKind::ClockSkew(self.clock_skew.unwrap()));
+            }
+            ProtocolResponse::Error {
+                kind: ProtocolError::InvalidSignature,
+                description: _,
+            }
+            | ProtocolResponse::Error {
+                kind: ProtocolError::InvalidChallenge,
+                description: _,
+            }
+            | ProtocolResponse::Error {
+                kind: ProtocolError::TooManySubscriptions,
+                description: _,
+            } => {
+                cli_message!("{}", ErrorKind::GrinboxAuthFailed(response.to_string()));
+            }
+            ProtocolResponse::Error {
+                kind: ProtocolError::RecipientOffline,
+                description: _,
+            } => {
+                // reported by the post that is waiting on it
+                let mut session = self.session.lock();
+                if session.awaiting_post {
+                    session.posted = Some(false);
+                }
+            }
             ProtocolResponse::Error {
                 kind: _,
                 description: _,
             } => {
-                cli_message!("{}", response);
+                cli_message!("{}", ErrorKind::GrinboxProtocolError(response.to_string()));
+            }
+            ProtocolResponse::Ok => {
+                let mut session = self.session.lock();
+                if session.awaiting_post {
+                    session.posted = Some(true);
+                }
             }
             _ => {}
         }
@@ -399,6 +766,37 @@ impl Handler for GrinboxClient {
             }
         }
 
+        // the buffer limits of `connect` tripped, the connection closes and is retried
+        if let WsErrorKind::Capacity = err.kind {
+            cli_message!(
+                "rejecting grinbox message over {} bytes: {}",
+                self.max_message_bytes,
+                err.details
+            );
+        }
+
+        // only report the first failed attempt, we keep retrying in the background
+        let first_attempt = {
+            let guard = self.connection_meta_data.lock();
+            guard.retries == 0 && !guard.connected_at_least_once
+        };
+        if first_attempt {
+            match err.kind {
+                WsErrorKind::Io(_) => {
+                    cli_message!("{}", ErrorKind::GrinboxConnectionRefused(self.url.clone()))
+                }
+                WsErrorKind::Ssl(ref e) => cli_message!(
+                    "{}",
+                    ErrorKind::GrinboxTlsError(self.url.clone(), e.to_string())
+                ),
+                WsErrorKind::Protocol => cli_message!(
+                    "{}",
+                    ErrorKind::GrinboxProtocolError(err.details.to_string())
+                ),
+                _ => {}
+            }
+        }
+
         error!("{:?}", err);
     }
 }
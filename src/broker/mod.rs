@@ -3,6 +3,6 @@ mod keybase;
 mod protocol;
 mod types;
 
-pub use self::grinbox::{GrinboxPublisher, GrinboxSubscriber};
+pub use self::grinbox::{GrinboxPublisher, GrinboxStats, GrinboxSubscriber};
 pub use self::keybase::{KeybasePublisher, KeybaseSubscriber, TOPIC_SLATE_NEW};
 pub use self::types::{CloseReason, Publisher, Subscriber, SubscriptionHandler};
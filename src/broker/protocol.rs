@@ -8,6 +8,8 @@ pub enum ProtocolError {
     InvalidSignature,
     InvalidChallenge,
     TooManySubscriptions,
+    /// The recipient of a slate isn't subscribed and the relay doesn't store messages
+    RecipientOffline,
 }
 
 impl Display for ProtocolError {
@@ -18,6 +20,7 @@ impl Display for ProtocolError {
             ProtocolError::InvalidSignature => write!(f, "{}", "invalid signature!"),
             ProtocolError::InvalidChallenge => write!(f, "{}", "invalid challenge!"),
             ProtocolError::TooManySubscriptions => write!(f, "{}", "too many subscriptions!"),
+            ProtocolError::RecipientOffline => write!(f, "{}", "recipient offline!"),
         }
     }
 }
@@ -115,3 +118,17 @@ impl Display for ProtocolResponse {
         }
     }
 }
+
+/// Messages between wallets that are not slates. Relays only forward posts, so these
+/// travel encrypted and signed inside a `PostSlate` like slates do.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+pub enum WalletMessage {
+    /// Asks the other wallet to answer with the same nonce, to check it is reachable
+    Ping {
+        nonce: String,
+    },
+    Pong {
+        nonce: String,
+    },
+}
@@ -0,0 +1,3610 @@
+use std::borrow::Cow::{self, Borrowed, Owned};
+use std::fs::File;
+use std::io::{BufRead, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+use colored::*;
+use futures::Future;
+use grin_api::client;
+use grin_core::global::{is_mainnet, set_mining_mode, ChainTypes};
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Client, Request};
+use hyper_rustls::HttpsConnector;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::config::OutputStreamType;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::{Highlighter, MatchingBracketHighlighter};
+use rustyline::hint::Hinter;
+use rustyline::{CompletionType, Config, EditMode, Editor, Helper};
+use tokio::runtime::Runtime;
+use tokio::timer::Timeout;
+use url::Url;
+use uuid::Uuid;
+
+use broker;
+use common;
+use contacts;
+use wallet;
+
+use api::router::{build_foreign_api_router, build_metrics_router, build_owner_api_router};
+use cli::{Parser, Spinner};
+use common::config::Wallet713Config;
+use common::crypto::{public_key_from_secret_key, PassphraseEncrypted};
+use common::time;
+use common::{format_error, password, ErrorKind, Result, RuntimeMode};
+use wallet::{check_writable, remove_debris, DataLock, Wallet};
+
+use crate::wallet::types::{
+    Arc, ExportedOutput, FeeOverrides, Mutex, ProofBundle, SendResult, SigningKey, TxLogEntry,
+    TxLogEntryType, TxProof, TxState, WatchData,
+};
+
+use contacts::{Address, AddressBook, AddressType, Backend, Contact, GrinboxAddress};
+
+const CLI_HISTORY_PATH: &str = ".history";
+
+fn do_config(
+    args: &ArgMatches,
+    chain: &Option<ChainTypes>,
+    silent: bool,
+    new_address_index: Option<u32>,
+    config_path: Option<&str>,
+) -> Result<Wallet713Config> {
+    let mut config;
+    let mut any_matches = false;
+    let exists = Wallet713Config::exists(config_path, &chain)?;
+    if exists {
+        config = Wallet713Config::from_file(config_path, &chain)?;
+    } else {
+        config = Wallet713Config::default(&chain)?;
+    }
+
+    if let Some(data_path) = args.value_of("data-path") {
+        config.wallet713_data_path = data_path.to_string();
+        any_matches = true;
+    }
+
+    if let Some(domain) = args.value_of("domain") {
+        config.grinbox_domain = domain.to_string();
+        any_matches = true;
+    }
+
+    if let Some(port) = args.value_of("port") {
+        let port = u16::from_str_radix(port, 10).map_err(|_| ErrorKind::NumberParsingError)?;
+        config.grinbox_port = Some(port);
+        any_matches = true;
+    }
+
+    if let Some(node_uri) = args.value_of("node-uri") {
+        config.grin_node_uri = Some(node_uri.to_string());
+        any_matches = true;
+    }
+
+    if let Some(node_secret) = args.value_of("node-secret") {
+        config.grin_node_secret = Some(node_secret.to_string());
+        any_matches = true;
+    }
+
+    if new_address_index.is_some() {
+        config.grinbox_address_index = new_address_index;
+        any_matches = true;
+    }
+
+    config.to_file(config_path)?;
+
+    if !any_matches && !silent {
+        cli_message!("{}", config);
+    }
+
+    Ok(config)
+}
+
+/// Settings `reload` applies to the running wallet, any other change needs a restart
+const LIVE_SETTINGS: &[&str] = &[
+    "config_file",
+    "grin_node_uris",
+    "grin_node_secret",
+    "grin_node_secret_file",
+    "timeout",
+    "use_color",
+    "language",
+    "spend_confirmations",
+    "max_selectable_outputs",
+    "pending_slate_ttl_hours",
+    "send_countdown_seconds",
+    "restore_max_retries",
+    "price_api_url",
+];
+
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+/// Bumped whenever a confirmation watcher starts, which stops the one of an earlier listener
+static WATCHER_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// How often the reload watcher checks for a SIGHUP
+const RELOAD_POLL_INTERVAL_MS: u64 = 500;
+/// How long a webhook gets to answer a notification
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// Makes SIGHUP `reload` the config, as service managers expect. The reload runs on a
+/// watcher thread, so it applies while the prompt waits for input.
+#[cfg(unix)]
+fn install_reload_handler(config: Arc<Mutex<Wallet713Config>>, wallet: Arc<Mutex<Wallet>>) {
+    extern "C" fn on_hangup(_: libc::c_int) {
+        // only flag it here, reloading is not safe to do inside a signal handler
+        RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+    }
+    unsafe {
+        libc::signal(libc::SIGHUP, on_hangup as libc::sighandler_t);
+    }
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_millis(RELOAD_POLL_INTERVAL_MS));
+        if RELOAD_REQUESTED.swap(false, Ordering::SeqCst) {
+            // waits for a running command to finish, which holds the config
+            if let Err(e) = reload_config(&mut config.lock(), wallet.clone()) {
+                cli_message!("{}", format_error(&e));
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn install_reload_handler(_config: Arc<Mutex<Wallet713Config>>, _wallet: Arc<Mutex<Wallet>>) {}
+
+fn reload_config(config: &mut Wallet713Config, wallet: Arc<Mutex<Wallet>>) -> Result<()> {
+    let config_path = config.config_home.clone();
+    let mut new_config =
+        Wallet713Config::from_file(config_path.as_ref().map(|p| p.as_str()), &config.chain)?;
+    // keep what was chosen at startup rather than read from the file
+    new_config.chain = config.chain.clone();
+    new_config.data_path_override = config.data_path_override.clone();
+    new_config.color_override = config.color_override.clone();
+    new_config.timeout_override = config.timeout_override;
+    new_config.grinbox_address_key = config.grinbox_address_key.clone();
+
+    let problems = new_config.check();
+    if !problems.is_empty() {
+        for problem in problems.iter() {
+            cli_message!("- {}", problem);
+        }
+        return Err(ErrorKind::InvalidConfig(problems.len()).into());
+    }
+    FeeOverrides::new(
+        new_config.fee_per_weight,
+        new_config.min_fee,
+        new_config.max_fee,
+    )
+    .validate()?;
+
+    let old_settings = config.effective_settings();
+    let restart_needed: Vec<&str> = new_config
+        .effective_settings()
+        .into_iter()
+        .zip(old_settings.into_iter())
+        .filter(|(new, old)| new.1 != old.1 && !LIVE_SETTINGS.contains(&new.0))
+        .map(|(new, _)| new.0)
+        .collect();
+
+    unsafe {
+        common::set_color_mode(new_config.color_mode())?;
+        common::locale::set_language(new_config.language())?;
+    }
+    wallet.lock().reload_config(&new_config)?;
+    *config = new_config;
+
+    cli_message!("configuration reloaded");
+    if !restart_needed.is_empty() {
+        cli_message!(
+            "{}: restart wallet713 to apply the changes to {}",
+            "WARNING".bright_yellow(),
+            restart_needed.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn show_config(config: &Wallet713Config) {
+    for (name, value) in config.effective_settings() {
+        cli_message!("{}={}", name.bright_blue(), value);
+    }
+}
+
+fn check_config(config: &Wallet713Config) {
+    let mut problems = config.check();
+    if let Err(e) =
+        FeeOverrides::new(config.fee_per_weight, config.min_fee, config.max_fee).validate()
+    {
+        problems.push(e.to_string());
+    }
+    match config.get_data_path() {
+        Ok(data_path) => {
+            if let Err(e) = check_writable(&data_path.to_string_lossy()) {
+                problems.push(e.to_string());
+            }
+        }
+        Err(e) => problems.push(e.to_string()),
+    }
+
+    if problems.is_empty() {
+        cli_message!("{}", "configuration is valid".bright_green());
+    } else {
+        for problem in &problems {
+            cli_message!("{} {}", "-".bright_red(), problem);
+        }
+        cli_message!(
+            "found {} problem(s) in the configuration",
+            problems.len().to_string().bright_red()
+        );
+    }
+}
+
+fn do_contacts(args: &ArgMatches, address_book: Arc<Mutex<AddressBook>>) -> Result<()> {
+    let mut address_book = address_book.lock();
+    if let Some(add_args) = args.subcommand_matches("add") {
+        let name = add_args.value_of("name").expect("missing argument: name");
+        let address = add_args
+            .value_of("address")
+            .expect("missing argument: address");
+
+        // try parse as a general address and fallback to grinbox address
+        let contact_address = Address::parse(address);
+        let contact_address: Result<Box<Address>> = match contact_address {
+            Ok(address) => Ok(address),
+            Err(e) => {
+                Ok(Box::new(GrinboxAddress::from_str(address).map_err(|_| e)?) as Box<Address>)
+            }
+        };
+
+        let contact = Contact::new(name, contact_address?)?;
+        address_book.add_contact(&contact)?;
+    } else if let Some(set_args) = args.subcommand_matches("set") {
+        let name = set_args.value_of("name").unwrap();
+        let mut contact = address_book.get_contact(name)?;
+        if set_args.is_present("clear") {
+            contact.set_transport(None)?;
+            contact.set_strategy(None)?;
+            contact.set_min_confirmations(None)?;
+        }
+        if let Some(transport) = set_args.value_of("transport") {
+            contact.set_transport(Some(transport))?;
+        }
+        if let Some(strategy) = set_args.value_of("strategy") {
+            contact.set_strategy(Some(strategy))?;
+        }
+        if let Some(min_confirmations) = set_args.value_of("min-confirmations") {
+            let min_confirmations = u64::from_str_radix(min_confirmations, 10)
+                .map_err(|_| ErrorKind::NumberParsingError)?;
+            contact.set_min_confirmations(Some(min_confirmations))?;
+        }
+        address_book.update_contact(&contact)?;
+    } else if let Some(add_args) = args.subcommand_matches("remove") {
+        let name = add_args.value_of("name").unwrap();
+        address_book.remove_contact(name)?;
+    } else if let Some(group_args) = args.subcommand_matches("group") {
+        if let Some(create_args) = group_args.subcommand_matches("create") {
+            let name = create_args.value_of("name").unwrap();
+            address_book.create_group(name)?;
+        } else if let Some(add_args) = group_args.subcommand_matches("add") {
+            let group = add_args.value_of("group").unwrap();
+            let contact = add_args.value_of("contact").unwrap();
+            let weight = add_args.value_of("weight").unwrap_or("1");
+            let weight =
+                u64::from_str_radix(weight, 10).map_err(|_| ErrorKind::NumberParsingError)?;
+            if weight == 0 {
+                return Err(ErrorKind::NumberParsingError.into());
+            }
+            address_book.add_to_group(group, contact, weight)?;
+        } else {
+            let groups: Vec<()> = address_book
+                .groups()
+                .map(|group| {
+                    cli_message!("@{}", group);
+                    ()
+                })
+                .collect();
+
+            if groups.len() == 0 {
+                cli_message!(
+                    "you have no contact groups. consider using `contacts group create` to add a new group."
+                );
+            }
+        }
+    } else {
+        let contacts: Vec<()> = address_book
+            .contacts()
+            .map(|contact| {
+                let mut defaults = vec![];
+                if let Some(transport) = contact.get_transport() {
+                    defaults.push(format!("transport: {}", transport));
+                }
+                if let Some(strategy) = contact.get_strategy() {
+                    defaults.push(format!("strategy: {}", strategy));
+                }
+                if let Some(min_confirmations) = contact.get_min_confirmations() {
+                    defaults.push(format!("min confirmations: {}", min_confirmations));
+                }
+                match defaults.is_empty() {
+                    true => cli_message!("@{} = {}", contact.get_name(), contact.get_address()),
+                    false => cli_message!(
+                        "@{} = {} ({})",
+                        contact.get_name(),
+                        contact.get_address(),
+                        defaults.join(", ")
+                    ),
+                }
+                ()
+            })
+            .collect();
+
+        if contacts.len() == 0 {
+            cli_message!(
+                "your contact list is empty. consider using `contacts add` to add a new contact."
+            );
+        }
+    }
+    Ok(())
+}
+
+const WELCOME_HEADER: &str = r#"
+Welcome to wallet713
+"#;
+
+fn welcome(args: &ArgMatches, runtime_mode: &RuntimeMode) -> Result<Wallet713Config> {
+    let chain: Option<ChainTypes> = match args.is_present("floonet") {
+        true => Some(ChainTypes::Floonet),
+        false => Some(ChainTypes::Mainnet),
+    };
+
+    unsafe {
+        common::set_runtime_mode(runtime_mode);
+    };
+
+    let mut config = do_config(args, &chain, true, None, args.value_of("config-path"))?;
+    // `--floonet` wins over the chain the config says it is for
+    if args.is_present("floonet") {
+        config.chain = chain;
+    }
+    set_mining_mode(config.chain.clone().unwrap_or(ChainTypes::Mainnet));
+
+    config.color_override = args.value_of("color").map(|color| color.to_string());
+    unsafe {
+        common::set_color_mode(config.color_mode())?;
+        common::set_amount_units(args.value_of("units").unwrap_or("grin"))?;
+        common::locale::set_language(config.language())?;
+    }
+
+    Ok(config)
+}
+
+use broker::{
+    CloseReason, GrinboxPublisher, GrinboxSubscriber, KeybasePublisher, KeybaseSubscriber,
+    Publisher, Subscriber, SubscriptionHandler,
+};
+use grin_wallet::Slate;
+use std::borrow::Borrow;
+use std::collections::HashSet;
+
+struct Controller {
+    name: String,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+    publisher: Box<Publisher + Send>,
+    receive_webhook_url: Option<String>,
+    manual: bool,
+}
+
+impl Controller {
+    pub fn new(
+        name: &str,
+        wallet: Arc<Mutex<Wallet>>,
+        address_book: Arc<Mutex<AddressBook>>,
+        publisher: Box<Publisher + Send>,
+        receive_webhook_url: Option<String>,
+        manual: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            name: name.to_string(),
+            wallet,
+            address_book,
+            publisher,
+            receive_webhook_url,
+            manual,
+        })
+    }
+
+    /// Notifies the configured webhook about a processed slate. The request is made on
+    /// its own thread so a slow endpoint never holds up the listener, and failures are
+    /// only logged.
+    fn notify_webhook(&self, event: &str, from: &Address, slate: &Slate) {
+        let url = match self.receive_webhook_url {
+            Some(ref url) => url.clone(),
+            None => return,
+        };
+        let payload = json!({
+            "event": event,
+            "tx_slate_id": slate.id.to_string(),
+            "amount": slate.amount,
+            "from": from.stripped(),
+        });
+        post_webhook(url, payload);
+    }
+
+    fn queue_incoming_slate(&self, from: &Address, slate: &Slate, tx_proof: Option<&TxProof>) {
+        match self
+            .wallet
+            .lock()
+            .queue_slate(&from.to_string(), slate, tx_proof)
+        {
+            Ok(_) => cli_message!(
+                "{}",
+                tr!(
+                    "slate_queued",
+                    slate.id.to_string().bright_green(),
+                    slate.id,
+                    slate.id
+                )
+            ),
+            Err(e) => cli_message!("{}", format_error(&e)),
+        }
+    }
+}
+
+/// Processes a received slate, returning whether it was finalized. A slate that is not
+/// finalized has to be sent back to where it came from.
+fn process_incoming_slate(
+    wallet: &Arc<Mutex<Wallet>>,
+    address: Option<String>,
+    slate: &mut Slate,
+    tx_proof: Option<&mut TxProof>,
+) -> Result<bool> {
+    if slate.num_participants > slate.participant_data.len() {
+        //TODO: this needs to be changed to properly figure out if this slate is an invoice or a send
+        if slate.tx.inputs().len() == 0 {
+            wallet.lock().process_receiver_initiated_slate(slate)?;
+        } else {
+            wallet
+                .lock()
+                .process_sender_initiated_slate(address, slate)?;
+        }
+        Ok(false)
+    } else {
+        wallet.lock().finalize_slate(slate, tx_proof)?;
+        Ok(true)
+    }
+}
+
+impl SubscriptionHandler for Controller {
+    fn on_open(&self) {
+        cli_message!("{}", tr!("listener_started", self.name.bright_green()));
+    }
+
+    fn on_slate(&self, from: &Address, slate: &mut Slate, tx_proof: Option<&mut TxProof>) {
+        let mut display_from = from.stripped();
+        if let Ok(contact) = self
+            .address_book
+            .lock()
+            .get_contact_by_address(&from.to_string())
+        {
+            display_from = contact.get_name().to_string();
+        }
+
+        if slate.num_participants > slate.participant_data.len() {
+            cli_message!(
+                "{}",
+                tr!(
+                    "slate_received",
+                    slate.id.to_string().bright_green(),
+                    display_from.bright_green(),
+                    common::amount_to_string(slate.amount, false).bright_green(),
+                    common::amount_units()
+                )
+            );
+            for message in slate
+                .participant_data
+                .iter()
+                .filter_map(|p| p.message.as_ref())
+            {
+                cli_message!(
+                    "{}",
+                    tr!("slate_message", display_from.bright_green(), message)
+                );
+            }
+        } else {
+            cli_message!(
+                "{}",
+                tr!(
+                    "slate_received_back",
+                    slate.id.to_string().bright_green(),
+                    display_from.bright_green(),
+                    common::amount_to_string(slate.amount, false).bright_green(),
+                    common::amount_units()
+                )
+            );
+        };
+
+        if from.address_type() == AddressType::Grinbox {
+            GrinboxAddress::from_str(&from.to_string()).expect("invalid grinbox address");
+        }
+
+        if self.manual {
+            self.queue_incoming_slate(from, slate, tx_proof.as_ref().map(|p| &**p));
+            return;
+        }
+
+        let result = process_incoming_slate(&self.wallet, Some(from.to_string()), slate, tx_proof)
+            .and_then(|is_finalized| {
+                if !is_finalized {
+                    self.publisher
+                        .post_slate(slate, from)
+                        .map_err(|e| {
+                            cli_message!("{}", format_error(&e));
+                            e
+                        })
+                        .expect("failed posting slate!");
+                    cli_message!(
+                        "{}",
+                        tr!(
+                            "slate_sent_back",
+                            slate.id.to_string().bright_green(),
+                            display_from.bright_green()
+                        )
+                    );
+                    self.notify_webhook("received", from, slate);
+                } else {
+                    cli_message!(
+                        "{}",
+                        tr!("slate_finalized", slate.id.to_string().bright_green())
+                    );
+                    self.notify_webhook("finalized", from, slate);
+                }
+                Ok(())
+            });
+
+        match result {
+            Ok(()) => {}
+            Err(e) => cli_message!("{}", format_error(&e)),
+        }
+    }
+
+    fn on_close(&self, reason: CloseReason) {
+        match reason {
+            CloseReason::Normal => {
+                cli_message!("{}", tr!("listener_stopped", self.name.bright_green()))
+            }
+            CloseReason::Abnormal(_) => cli_message!(
+                "{}",
+                tr!(
+                    "listener_stopped_unexpectedly",
+                    tr!("label_error").bright_red(),
+                    self.name.bright_green()
+                )
+            ),
+        }
+    }
+
+    fn on_dropped(&self) {
+        cli_message!(
+            "{}",
+            tr!(
+                "listener_dropped",
+                tr!("label_warning").bright_yellow(),
+                self.name.bright_green()
+            )
+        )
+    }
+
+    fn on_reestablished(&self) {
+        cli_message!(
+            "{}",
+            tr!(
+                "listener_reestablished",
+                tr!("label_info").bright_blue(),
+                self.name.bright_green()
+            )
+        )
+    }
+}
+
+fn start_grinbox_listener(
+    config: &Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+    manual: bool,
+    retry: bool,
+) -> Result<(GrinboxPublisher, GrinboxSubscriber)> {
+    // make sure wallet is not locked, if it is try to unlock with no passphrase
+    {
+        let mut wallet = wallet.lock();
+        if wallet.is_locked() {
+            wallet.unlock(config, "default", "")?;
+        }
+    }
+
+    cli_message!("{}", tr!("listener_starting", "grinbox"));
+    let grinbox_address = config.get_grinbox_address()?;
+    let grinbox_secret_key = config.get_grinbox_secret_key()?;
+
+    let grinbox_publisher = GrinboxPublisher::new(
+        &grinbox_address,
+        &grinbox_secret_key,
+        config.grinbox_protocol_unsecure(),
+        config.max_message_bytes(),
+        config.timeout(),
+    )?;
+
+    let grinbox_subscriber = GrinboxSubscriber::new(&grinbox_publisher)?;
+
+    // tell a relay outage from a config mistake before going to the background
+    if let Err(e) = grinbox_publisher.probe() {
+        if !retry {
+            return Err(e);
+        }
+        cli_message!("{}", format_error(&e));
+        cli_message!("retrying to connect to the grinbox relay in the background...");
+    }
+
+    let cloned_publisher = grinbox_publisher.clone();
+    let mut cloned_subscriber = grinbox_subscriber.clone();
+    let receive_webhook_url = config.receive_webhook_url.clone();
+    start_confirmation_watcher(
+        config,
+        wallet.clone(),
+        address_book.clone(),
+        grinbox_publisher.clone(),
+        grinbox_subscriber.clone(),
+    );
+    std::thread::spawn(move || {
+        let controller = Controller::new(
+            &grinbox_address.stripped(),
+            wallet.clone(),
+            address_book.clone(),
+            Box::new(cloned_publisher),
+            receive_webhook_url,
+            manual,
+        )
+        .expect("could not start grinbox controller!");
+        cloned_subscriber
+            .start(Box::new(controller))
+            .expect("something went wrong!");
+    });
+    Ok((grinbox_publisher, grinbox_subscriber))
+}
+
+/// Delivers a webhook notification on its own thread, only logging failures.
+fn post_webhook(url: String, payload: serde_json::Value) {
+    std::thread::spawn(move || {
+        if let Err(e) = send_webhook(&url, &payload) {
+            error!("failed delivering webhook to {}: {}", url, e);
+        }
+    });
+}
+
+/// Posts `payload` to `url`, giving up if the request isn't answered within
+/// `WEBHOOK_TIMEOUT_SECS`
+fn send_webhook(url: &str, payload: &serde_json::Value) -> Result<()> {
+    let request = Request::post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_string(payload)?))?;
+    let client = Client::builder().build::<_, Body>(HttpsConnector::new(1));
+    let task = client
+        .request(request)
+        .map_err(|e| ErrorKind::GenericError(e.to_string()))
+        .and_then(|response| match response.status().is_success() {
+            true => Ok(()),
+            false => Err(ErrorKind::GenericError(format!(
+                "webhook answered with {}",
+                response.status()
+            ))),
+        });
+    let task =
+        Timeout::new(task, std::time::Duration::from_secs(WEBHOOK_TIMEOUT_SECS)).map_err(|e| {
+            e.into_inner().unwrap_or(ErrorKind::GenericError(format!(
+                "no answer within {} seconds",
+                WEBHOOK_TIMEOUT_SECS
+            )))
+        });
+    let mut runtime = Runtime::new()?;
+    runtime.block_on(task)?;
+    Ok(())
+}
+
+/// Prints that a transaction was confirmed and sends the `confirmed` event to the
+/// webhook, with the other party in `to` for sends and in `from` for receives
+fn report_confirmed(tx: &TxLogEntry, webhook_url: &Option<String>) {
+    let address = tx.address.clone().unwrap_or(String::new());
+    let (amount, party) = match tx.tx_type {
+        TxLogEntryType::TxSent => (
+            (tx.amount_debited - tx.amount_credited).saturating_sub(tx.fee.unwrap_or(0)),
+            "to",
+        ),
+        _ => (tx.amount_credited, "from"),
+    };
+    cli_message!(
+        "{}",
+        tr!(
+            "tx_confirmed",
+            tx.id.to_string().bright_green(),
+            common::amount_to_string(amount, false).bright_green(),
+            common::amount_units()
+        )
+    );
+    if let Some(ref url) = webhook_url {
+        let mut payload = json!({
+            "event": "confirmed",
+            "tx_slate_id": tx.tx_slate_id.map(|id| id.to_string()).unwrap_or(String::new()),
+            "amount": amount,
+        });
+        payload[party] = json!(address);
+        post_webhook(url.clone(), payload);
+    }
+}
+
+/// Periodically refreshes the wallet from the node while the listener is running and
+/// notifies about every transaction that got confirmed since the last check.
+/// With `auto_fee_bump`, grinbox sends that stay unconfirmed for too long are rebuilt
+/// with a higher fee.
+fn start_confirmation_watcher(
+    config: &Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+    publisher: GrinboxPublisher,
+    subscriber: GrinboxSubscriber,
+) {
+    let interval = std::time::Duration::from_secs(config.confirmation_poll_interval());
+    let webhook_url = config.receive_webhook_url.clone();
+    let fee_bump_after_minutes = match config.auto_fee_bump() {
+        true => Some(config.fee_bump_after_minutes()),
+        false => None,
+    };
+    let generation = WATCHER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    std::thread::spawn(move || {
+        // keyed by slate id, tx ids are only unique within an account
+        let mut pending: Option<HashSet<Uuid>> = None;
+        // sends whose fee can't be bumped, so they are not retried on every check
+        let mut not_bumped: HashSet<Uuid> = HashSet::new();
+        loop {
+            std::thread::sleep(interval);
+            if !subscriber.is_running() || WATCHER_GENERATION.load(Ordering::SeqCst) != generation {
+                break;
+            }
+
+            let txs = match wallet.lock().retrieve_txs(true, None, None) {
+                Ok((_, txs)) => txs,
+                Err(_) => continue,
+            };
+            let tracked: Vec<TxLogEntry> = txs
+                .into_iter()
+                .filter(|tx| {
+                    tx.tx_type == TxLogEntryType::TxSent || tx.tx_type == TxLogEntryType::TxReceived
+                })
+                .collect();
+
+            // the first check only records what is pending, so old transactions are not
+            // reported as newly confirmed
+            let mut below_threshold = HashSet::new();
+            if let Some(ref pending) = pending {
+                for tx in tracked.iter().filter(|tx| {
+                    tx.confirmed
+                        && tx
+                            .tx_slate_id
+                            .map(|id| pending.contains(&id))
+                            .unwrap_or(false)
+                }) {
+                    let min_confirmations = tx
+                        .address
+                        .as_ref()
+                        .and_then(|address| {
+                            address_book.lock().get_contact_by_address(address).ok()
+                        })
+                        .and_then(|contact| contact.get_min_confirmations());
+                    if let Some(min_confirmations) = min_confirmations {
+                        // no unspent outputs left means they were spent, so it is deep enough
+                        let confirmations = wallet.lock().tx_confirmations(tx.id);
+                        if let Ok(Some(confirmations)) = confirmations {
+                            if confirmations < min_confirmations {
+                                below_threshold.extend(tx.tx_slate_id);
+                                continue;
+                            }
+                        }
+                    }
+                    report_confirmed(tx, &webhook_url);
+                }
+            }
+
+            pending = Some(
+                tracked
+                    .iter()
+                    .filter(|tx| !tx.confirmed)
+                    .filter_map(|tx| tx.tx_slate_id)
+                    .chain(below_threshold)
+                    .collect(),
+            );
+
+            for tx in wallet.lock().expired_sends().unwrap_or(vec![]) {
+                match wallet.lock().cancel(tx.id) {
+                    Ok(()) => cli_message!(
+                        "transaction [{}] was not finalized before it expired and was cancelled",
+                        tx.id.to_string().bright_green()
+                    ),
+                    Err(e) => cli_message!(
+                        "could not cancel expired transaction [{}]: {}",
+                        tx.id.to_string().bright_green(),
+                        format_error(&e)
+                    ),
+                }
+            }
+
+            // the wallet was just refreshed, so the stuck sends are known to be unconfirmed
+            if let Some(minutes) = fee_bump_after_minutes {
+                let stuck = wallet.lock().stuck_sends(minutes).unwrap_or(vec![]);
+                for tx in stuck.iter() {
+                    let slate_id = match tx.tx_slate_id {
+                        Some(slate_id) if !not_bumped.contains(&slate_id) => slate_id,
+                        _ => continue,
+                    };
+                    if !bump_stuck_send(&wallet, &publisher, tx) {
+                        not_bumped.insert(slate_id);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Rebuilds a grinbox send that is stuck unconfirmed with a higher fee and sends the new
+/// slate to the recipient. Returns false if the send couldn't be bumped
+fn bump_stuck_send(
+    wallet: &Arc<Mutex<Wallet>>,
+    publisher: &GrinboxPublisher,
+    tx: &TxLogEntry,
+) -> bool {
+    let to = match tx.address.as_ref().and_then(|a| Address::parse(a).ok()) {
+        Some(to) => to,
+        None => return false,
+    };
+    if to.address_type() != AddressType::Grinbox {
+        return false;
+    }
+    let slate = match wallet.lock().bump_fee(tx.id) {
+        Ok(slate) => slate,
+        Err(e) => {
+            cli_message!(
+                "could not bump the fee of stuck transaction [{}]: {}",
+                tx.id.to_string().bright_green(),
+                format_error(&e)
+            );
+            return false;
+        }
+    };
+    cli_message!(
+        "transaction [{}] was stuck unconfirmed, rebuilt it with a fee of {} {}",
+        tx.id.to_string().bright_green(),
+        common::amount_to_string(slate.fee, false).bright_green(),
+        common::amount_units()
+    );
+    match publisher.post_slate(&slate, to.borrow()) {
+        Ok(()) => cli_message!(
+            "slate [{}] for the higher fee sent to [{}]",
+            slate.id.to_string().bright_green(),
+            to.stripped().bright_green()
+        ),
+        Err(e) => cli_message!(
+            "could not send slate [{}] for the higher fee, use `txs resend` to retry: {}",
+            slate.id.to_string().bright_green(),
+            format_error(&e)
+        ),
+    }
+    true
+}
+
+fn start_keybase_listener(
+    config: &Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+    manual: bool,
+) -> Result<(KeybasePublisher, KeybaseSubscriber)> {
+    // make sure wallet is not locked, if it is try to unlock with no passphrase
+    {
+        let mut wallet = wallet.lock();
+        if wallet.is_locked() {
+            wallet.unlock(config, "default", "")?;
+        }
+    }
+
+    cli_message!("{}", tr!("listener_starting", "keybase"));
+    let keybase_subscriber = KeybaseSubscriber::new()?;
+    let keybase_publisher = KeybasePublisher::new(config.default_keybase_ttl.clone())?;
+
+    let mut cloned_subscriber = keybase_subscriber.clone();
+    let cloned_publisher = keybase_publisher.clone();
+    let receive_webhook_url = config.receive_webhook_url.clone();
+    std::thread::spawn(move || {
+        let controller = Controller::new(
+            "keybase",
+            wallet.clone(),
+            address_book.clone(),
+            Box::new(cloned_publisher),
+            receive_webhook_url,
+            manual,
+        )
+        .expect("could not start keybase controller!");
+        cloned_subscriber
+            .start(Box::new(controller))
+            .expect("something went wrong!");
+    });
+    Ok((keybase_publisher, keybase_subscriber))
+}
+
+/// Serves the foreign api, whose `receive_tx` endpoint hands received slates to the
+/// wallet. The address is bound once up front, so a port in use is reported here
+/// rather than failing on the server thread.
+fn start_http_listener(
+    address: &str,
+    config: &Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    keybase_broker: &Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<()> {
+    let socket_address = address
+        .parse::<SocketAddr>()
+        .map_err(|_| ErrorKind::InvalidListenAddress(address.to_string()))?;
+    TcpListener::bind(socket_address)
+        .map_err(|e| ErrorKind::HttpListenerBind(address.to_string(), e.to_string()))?;
+
+    if config.foreign_api_secret.is_none() {
+        cli_message!(
+            "{}: no api secret for foreign api, it is recommended to set one.",
+            "WARNING".bright_yellow()
+        );
+    }
+    let router = build_foreign_api_router(
+        wallet,
+        grinbox_broker.clone(),
+        keybase_broker.clone(),
+        config.foreign_api_secret.clone(),
+        config.checkout_api_token.clone(),
+    );
+    std::thread::spawn(move || {
+        gotham::start(socket_address, router);
+    });
+    cli_message!(
+        "listening for http slates on [{}]",
+        format!("http://{}/v1/wallet/foreign/receive_tx", socket_address).bright_green()
+    );
+    Ok(())
+}
+
+/// Removes the debris a crashed instance leaves behind, refusing to touch the data path
+/// while another instance runs
+fn clean(config: &Wallet713Config, data_path: &str) -> Result<()> {
+    let mut removed = vec![];
+    removed.extend(DataLock::remove_stale(data_path)?);
+    {
+        // keep a new instance from starting while we clean up
+        let _data_lock = DataLock::acquire(data_path, false)?;
+        removed.extend(remove_debris(data_path)?);
+        removed.extend(config.remove_temp_file()?);
+    }
+
+    for path in &removed {
+        println!("removed {}", path.display());
+    }
+    match removed.len() {
+        0 => cli_message!("nothing to clean in {}", data_path),
+        count => cli_message!(
+            "removed [{}] file(s) from {}",
+            count.to_string().bright_green(),
+            data_path
+        ),
+    }
+    Ok(())
+}
+
+struct EditorHelper(FilenameCompleter, MatchingBracketHighlighter);
+
+impl Completer for EditorHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+    ) -> std::result::Result<(usize, Vec<Pair>), ReadlineError> {
+        self.0.complete(line, pos)
+    }
+}
+
+impl Hinter for EditorHelper {
+    fn hint(&self, _line: &str, _pos: usize) -> Option<String> {
+        None
+    }
+}
+
+impl Highlighter for EditorHelper {
+    fn highlight_prompt<'p>(&self, prompt: &'p str) -> Cow<'p, str> {
+        if prompt == unsafe { common::prompt() } {
+            Owned(unsafe { common::colored_prompt() })
+        } else {
+            Borrowed(prompt)
+        }
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        if !unsafe { common::use_color() } {
+            return Borrowed(hint);
+        }
+        Owned("\x1b[1m".to_owned() + hint + "\x1b[m")
+    }
+
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        self.1.highlight(line, pos)
+    }
+
+    fn highlight_char(&self, line: &str, pos: usize) -> bool {
+        self.1.highlight_char(line, pos)
+    }
+}
+
+impl Helper for EditorHelper {}
+
+/// The top-level command line of the `wallet713` binary
+fn cli_app() -> App<'static, 'static> {
+    App::new("wallet713")
+        .version(crate_version!())
+        .arg(Arg::from_usage("[config-path] -c, --config=<config-path> 'the path to the config file'"))
+        .arg(Arg::from_usage("[log-config-path] -l, --log-config-path=<log-config-path> 'the path to the log config file'"))
+        .arg(Arg::from_usage("[account] -a, --account=<account> 'the account to use'"))
+        .arg(Arg::from_usage("[passphrase] -p, --passphrase=<passphrase> 'the passphrase to use'").min_values(0))
+        .arg(Arg::from_usage("[daemon] -d, --daemon 'run daemon'"))
+        .arg(Arg::from_usage("[floonet] -f, --floonet 'use floonet'"))
+        .arg(Arg::from_usage("[timeout] --timeout=<secs> 'the timeout for node and grinbox requests, in seconds'"))
+        .arg(Arg::from_usage("[data-path-override] --data-path=<data-path> 'the wallet data path to use for this run, without changing the config'"))
+        .arg(Arg::from_usage("[color] --color=<when> 'when to use colored output (auto/always/never), overriding the config'"))
+        .arg(Arg::from_usage("[units] --units=<units> 'the units to print amounts in: grin, or nano for integer nanogrins. Default: grin'"))
+        .arg(Arg::from_usage("[force-unlock] --force-unlock 'take over the wallet data from an instance that did not exit cleanly'"))
+        .subcommand(SubCommand::with_name("clean").about("removes the lock and temporary files a crashed instance left in the data path, then exits"))
+        .subcommand(
+            SubCommand::with_name("sign-slate")
+                .about("signs a slate with a key file exported from a wallet, then exits. Needs no wallet")
+                .arg(Arg::from_usage("-i, --in=<file> 'the slate to sign'"))
+                .arg(Arg::from_usage("-k, --key=<file> 'the file holding the signing key'"))
+                .arg(Arg::from_usage("-o, --out=<file> 'the file to store the signed slate in'")),
+        )
+        .subcommand(
+            SubCommand::with_name("send")
+                .about("sends to the `<amount> <address>` lines read from stdin, then exits")
+                .arg(Arg::from_usage("<amount> 'must be -, to read the recipients from stdin'")),
+        )
+}
+
+/// Runs the wallet713 command line, as the `wallet713` binary does
+pub fn run() {
+    let matches = cli_app().get_matches();
+
+    if let Some(args) = matches.subcommand_matches("sign-slate") {
+        let home_dir = dirs::home_dir()
+            .map(|p| p.to_str().unwrap().to_string())
+            .unwrap_or("~".to_string());
+        if let Err(e) = sign_slate_file(args, &home_dir) {
+            println!("{}", format_error(&e));
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    let runtime_mode = match matches.is_present("daemon") {
+        true => RuntimeMode::Daemon,
+        false => RuntimeMode::Cli,
+    };
+
+    let mut config: Wallet713Config = welcome(&matches, &runtime_mode).unwrap_or_else(|e| {
+        println!("{}", format_error(&e));
+        std::process::exit(1);
+    });
+
+    if let Some(timeout) = matches.value_of("timeout") {
+        match timeout.parse::<u64>() {
+            Ok(timeout) => config.timeout_override = Some(timeout),
+            Err(_) => {
+                println!("{}", format_error(&ErrorKind::NumberParsingError.into()));
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // named apart from the `config --data-path` that `do_config` saves to the file
+    config.data_path_override = matches
+        .value_of("data-path-override")
+        .map(|data_path| data_path.to_string());
+
+    let data_path_buf = config.get_data_path().unwrap();
+    let data_path = data_path_buf.to_str().unwrap();
+
+    if matches.subcommand_matches("clean").is_some() {
+        let code = match clean(&config, data_path) {
+            Ok(_) => 0,
+            Err(e) => {
+                println!("{}", format_error(&e));
+                1
+            }
+        };
+        std::process::exit(code);
+    }
+
+    let data_lock = match DataLock::acquire(data_path, matches.is_present("force-unlock")) {
+        Ok(data_lock) => Arc::new(data_lock),
+        Err(e) => {
+            println!("{}", format_error(&e));
+            std::process::exit(1);
+        }
+    };
+
+    // ctrl-c stops a running restore after the current batch, and exits otherwise
+    let handler_lock = data_lock.clone();
+    ctrlc::set_handler(move || {
+        if !wallet::interrupt_restore() {
+            handler_lock.release();
+            std::process::exit(130);
+        }
+    })
+    .expect("could not set the ctrl-c handler");
+
+    if let Err(e) =
+        FeeOverrides::new(config.fee_per_weight, config.min_fee, config.max_fee).validate()
+    {
+        cli_message!("{}", format_error(&e));
+    }
+
+    let address_book_backend =
+        Backend::new(data_path).expect("could not create address book backend!");
+    let address_book = AddressBook::new(Box::new(address_book_backend))
+        .expect("could not create an address book!");
+    let address_book = Arc::new(Mutex::new(address_book));
+
+    let mut wallet = Wallet::new(config.max_auto_accept_invoice);
+    wallet.set_progress(Some(spinner));
+    let wallet = Arc::new(Mutex::new(wallet));
+
+    let mut grinbox_broker: Option<(GrinboxPublisher, GrinboxSubscriber)> = None;
+    let mut keybase_broker: Option<(KeybasePublisher, KeybaseSubscriber)> = None;
+
+    let account = matches
+        .value_of("account")
+        .unwrap_or(config.default_account())
+        .to_string();
+    let passphrase = match startup_passphrase(&matches, &config) {
+        Ok(passphrase) => passphrase,
+        Err(e) => {
+            println!("{}", format_error(&e));
+            data_lock.release();
+            std::process::exit(1);
+        }
+    };
+    let has_wallet = if let Some(passphrase) = passphrase {
+        let result = wallet.lock().unlock(&config, &account, &passphrase);
+        if let Err(ref err) = result {
+            println!("{}", format_error(err));
+            data_lock.release();
+            std::process::exit(1);
+        }
+        result.is_ok()
+    } else {
+        wallet.lock().unlock(&config, &account, "").is_ok()
+    };
+
+    cli_message!("{}", WELCOME_HEADER.bright_yellow().bold());
+    let network = match is_mainnet() {
+        true => "mainnet",
+        false => "floonet",
+    };
+    cli_message!("{}: {}", "Network".bright_yellow(), network.bright_green());
+    if has_wallet {
+        let der = derive_address_key(&mut config, wallet.clone(), &mut grinbox_broker);
+        if der.is_err() {
+            cli_message!("{}", format_error(&der.unwrap_err()));
+        }
+    } else {
+        cli_message!("{}", tr!("unlock_or_init").bright_blue().bold());
+    }
+    cli_message!("{}\n", tr!("welcome_footer").bright_blue());
+
+    if let Some(auto_start) = config.grinbox_listener_auto_start {
+        if auto_start {
+            let mut is_safe = false;
+            let result = do_command(
+                "listen -g --retry",
+                &mut config,
+                wallet.clone(),
+                address_book.clone(),
+                &mut keybase_broker,
+                &mut grinbox_broker,
+                &mut is_safe,
+            );
+            if let Err(err) = result {
+                cli_message!("{}", format_error(&err));
+            }
+        }
+    }
+
+    if let Some(auto_start) = config.keybase_listener_auto_start {
+        if auto_start {
+            let mut is_safe = false;
+            let result = do_command(
+                "listen -k",
+                &mut config,
+                wallet.clone(),
+                address_book.clone(),
+                &mut keybase_broker,
+                &mut grinbox_broker,
+                &mut is_safe,
+            );
+            if let Err(err) = result {
+                cli_message!("{}", format_error(&err));
+            }
+        }
+    }
+
+    if config.auto_listen() {
+        let is_running = match grinbox_broker {
+            Some((_, ref subscriber)) => subscriber.is_running(),
+            _ => false,
+        };
+        if !has_wallet {
+            cli_message!(
+                "{}: auto_listen needs an unlocked wallet, pass -p, set {} or set passphrase_file",
+                "WARNING".bright_yellow(),
+                PASSPHRASE_ENV
+            );
+        } else if !is_running {
+            cli_message!(
+                "{}: listening on grinbox, as set by auto_listen",
+                "Auto-listen".bright_yellow()
+            );
+            let mut is_safe = false;
+            let result = do_command(
+                "listen --retry",
+                &mut config,
+                wallet.clone(),
+                address_book.clone(),
+                &mut keybase_broker,
+                &mut grinbox_broker,
+                &mut is_safe,
+            );
+            if let Err(err) = result {
+                cli_message!("{}", format_error(&err));
+            }
+        }
+    }
+
+    if let Some(args) = matches.subcommand_matches("send") {
+        let result = send_from_stdin(
+            args,
+            &mut config,
+            wallet.clone(),
+            address_book.clone(),
+            &mut keybase_broker,
+            &mut grinbox_broker,
+        );
+        let code = match result {
+            Ok(_) => 0,
+            Err(e) => {
+                println!("{}", format_error(&e));
+                1
+            }
+        };
+        data_lock.release();
+        std::process::exit(code);
+    }
+
+    if config.owner_api() || config.foreign_api() {
+        let _owner_handle = match config.owner_api {
+            Some(true) => {
+                cli_message!(
+                    "starting listener for owner api on [{}]",
+                    config.owner_api_address().bright_green()
+                );
+                if config.owner_api_secret.is_none() {
+                    cli_message!(
+                        "{}: no api secret for owner api, it is recommended to set one.",
+                        "WARNING".bright_yellow()
+                    );
+                }
+                let router = build_owner_api_router(
+                    wallet.clone(),
+                    grinbox_broker.clone(),
+                    keybase_broker.clone(),
+                    config.owner_api_secret.clone(),
+                    config.owner_api_include_foreign,
+                );
+                let address = config.owner_api_address();
+                Some(std::thread::spawn(move || {
+                    gotham::start(address, router);
+                }))
+            }
+            _ => None,
+        };
+
+        let _foreign_handle = match config.foreign_api {
+            Some(true) => {
+                cli_message!(
+                    "starting listener for foreign api on [{}]",
+                    config.foreign_api_address().bright_green()
+                );
+                if config.foreign_api_secret.is_none() {
+                    cli_message!(
+                        "{}: no api secret for foreign api, it is recommended to set one.",
+                        "WARNING".bright_yellow()
+                    );
+                }
+                let router = build_foreign_api_router(
+                    wallet.clone(),
+                    grinbox_broker.clone(),
+                    keybase_broker.clone(),
+                    config.foreign_api_secret.clone(),
+                    config.checkout_api_token.clone(),
+                );
+                let address = config.foreign_api_address();
+                Some(std::thread::spawn(move || {
+                    gotham::start(address, router);
+                }))
+            }
+            _ => None,
+        };
+    }
+
+    let metrics_listener = Arc::new(Mutex::new(
+        grinbox_broker
+            .as_ref()
+            .map(|(publisher, _)| publisher.clone()),
+    ));
+    if let Some(ref address) = config.metrics_bind {
+        match config.metrics_api_secret {
+            Some(ref secret) if !secret.trim().is_empty() => {
+                cli_message!(
+                    "starting metrics endpoint on [{}]",
+                    format!("http://{}/metrics", address).bright_green()
+                );
+                let router =
+                    build_metrics_router(wallet.clone(), metrics_listener.clone(), secret.clone());
+                let address = address.clone();
+                std::thread::spawn(move || {
+                    gotham::start(address, router);
+                });
+            }
+            _ => cli_message!(
+                "{}: not starting the metrics endpoint, set a metrics_api_secret for it",
+                "WARNING".bright_yellow()
+            ),
+        }
+    }
+
+    let editor_config = Config::builder()
+        .history_ignore_space(true)
+        .completion_type(CompletionType::List)
+        .edit_mode(EditMode::Emacs)
+        .output_stream(OutputStreamType::Stdout)
+        .build();
+    let mut rl = Editor::with_config(editor_config);
+    rl.set_helper(Some(EditorHelper(
+        FilenameCompleter::new(),
+        MatchingBracketHighlighter::new(),
+    )));
+
+    let wallet713_home_path_buf = Wallet713Config::default_home_path(&config.chain).unwrap();
+    let wallet713_home_path = wallet713_home_path_buf.to_str().unwrap();
+
+    if let Some(path) = Path::new(wallet713_home_path)
+        .join(CLI_HISTORY_PATH)
+        .to_str()
+    {
+        rl.load_history(path).is_ok();
+    }
+
+    let config = Arc::new(Mutex::new(config));
+    install_reload_handler(config.clone(), wallet.clone());
+
+    loop {
+        unsafe {
+            common::set_prompt(render_prompt(&config.lock(), wallet.clone()));
+        }
+        let command = rl.readline(&unsafe { common::prompt() });
+        match command {
+            Ok(command) => {
+                let command = command.trim();
+
+                if command == "exit" {
+                    break;
+                }
+
+                let mut out_is_safe = false;
+                let result = do_command(
+                    &command,
+                    &mut config.lock(),
+                    wallet.clone(),
+                    address_book.clone(),
+                    &mut keybase_broker,
+                    &mut grinbox_broker,
+                    &mut out_is_safe,
+                );
+
+                if let Err(err) = result {
+                    cli_message!("{}", format_error(&err));
+                }
+                // the command may have started or stopped the grinbox listener
+                *metrics_listener.lock() = grinbox_broker
+                    .as_ref()
+                    .map(|(publisher, _)| publisher.clone());
+
+                if out_is_safe {
+                    rl.add_history_entry(command);
+                }
+            }
+            Err(_) => {
+                break;
+            }
+        }
+    }
+
+    if let Some(path) = Path::new(wallet713_home_path)
+        .join(CLI_HISTORY_PATH)
+        .to_str()
+    {
+        rl.save_history(path).is_ok();
+    }
+    data_lock.release();
+}
+
+/// Runs the one-shot `send -`, listening on grinbox first so the slates can be delivered
+fn send_from_stdin(
+    args: &ArgMatches,
+    config: &mut Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+    keybase_broker: &mut Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &mut Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<()> {
+    if args.value_of("amount") != Some("-") {
+        return Err(ErrorKind::MissingDestination.into());
+    }
+    let is_running = match grinbox_broker {
+        Some((_, ref subscriber)) => subscriber.is_running(),
+        _ => false,
+    };
+    let mut is_safe = false;
+    if !is_running {
+        do_command(
+            "listen -g",
+            config,
+            wallet.clone(),
+            address_book.clone(),
+            keybase_broker,
+            grinbox_broker,
+            &mut is_safe,
+        )?;
+    }
+    do_command(
+        "send -",
+        config,
+        wallet,
+        address_book,
+        keybase_broker,
+        grinbox_broker,
+        &mut is_safe,
+    )
+}
+
+fn derive_address_key(
+    config: &mut Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    grinbox_broker: &mut Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<()> {
+    if grinbox_broker.is_some() {
+        return Err(ErrorKind::HasListener.into());
+    }
+    let index = config.grinbox_address_index();
+    let key = wallet.lock().derive_address_key(index)?;
+    config.grinbox_address_key = Some(key);
+    // the first address seen is the one `config verify-key` expects from then on
+    if config.grinbox_expected_address.is_none() {
+        config.grinbox_expected_address = Some(config.get_grinbox_address()?.public_key);
+        let config_home = config.config_home.clone();
+        config.to_file(config_home.as_ref().map(|x| &**x))?;
+    }
+    show_address(config, false)?;
+    Ok(())
+}
+
+/// Derives the grinbox key from the seed again and checks that the address stored as
+/// expected in the config, and the one given, belong to it
+fn verify_address_key(
+    config: &Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    expected: Option<&str>,
+) -> Result<()> {
+    let index = config.grinbox_address_index();
+    let key = wallet.lock().derive_address_key(index)?;
+    let derived = GrinboxAddress::new(
+        public_key_from_secret_key(&key)?,
+        Some(config.grinbox_domain.clone()),
+        config.grinbox_port,
+    );
+    let expected = config
+        .grinbox_expected_address
+        .iter()
+        .map(|address| address.as_str())
+        .chain(expected);
+    for expected in expected {
+        let expected = GrinboxAddress::from_str(expected)?;
+        if expected.public_key != derived.public_key {
+            return Err(
+                ErrorKind::AddressKeyMismatch(expected.stripped(), derived.stripped()).into(),
+            );
+        }
+    }
+    cli_message!(
+        "address [{}] matches the key derived from your seed with index [{}]",
+        derived.stripped().bright_green(),
+        index.to_string().bright_blue()
+    );
+    Ok(())
+}
+
+/// Renders the `prompt` template from the config, replacing `{account}`, `{address}`
+/// and `{network}` with their current values.
+fn render_prompt(config: &Wallet713Config, wallet: Arc<Mutex<Wallet>>) -> Option<String> {
+    let template = config.prompt.as_ref()?;
+    let account = {
+        let wallet = wallet.lock();
+        match wallet.is_locked() {
+            true => String::from("locked"),
+            false => wallet.active_account().to_string(),
+        }
+    };
+    let address = match config.get_grinbox_address() {
+        Ok(address) => format!(
+            "{}..",
+            address.stripped().chars().take(6).collect::<String>()
+        ),
+        Err(_) => String::from("none"),
+    };
+    let network = match is_mainnet() {
+        true => "mainnet",
+        false => "floonet",
+    };
+    Some(
+        template
+            .replace("{account}", &account)
+            .replace("{address}", &address)
+            .replace("{network}", network),
+    )
+}
+
+fn show_address(config: &Wallet713Config, include_index: bool) -> Result<()> {
+    cli_message!(
+        "{}: {}",
+        "Your grinbox address".bright_yellow(),
+        config.get_grinbox_address()?.stripped().bright_green()
+    );
+    if include_index {
+        cli_message!(
+            "Derived with index [{}]",
+            config.grinbox_address_index().to_string().bright_blue()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(all(
+    feature = "clipboard",
+    any(windows, target_os = "macos", target_os = "linux")
+))]
+thread_local! {
+    /// The clipboard we copied to. On Linux the contents are served by the
+    /// context, so it is kept for as long as the wallet runs.
+    static CLIPBOARD: std::cell::RefCell<Option<clipboard::ClipboardContext>> =
+        std::cell::RefCell::new(None);
+}
+
+/// Puts `text` on the system clipboard, false when there is none, as in a headless
+/// session or over ssh
+#[cfg(all(
+    feature = "clipboard",
+    any(windows, target_os = "macos", target_os = "linux")
+))]
+fn copy_to_clipboard(text: &str) -> bool {
+    use clipboard::{ClipboardContext, ClipboardProvider};
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+        if clipboard.is_none() {
+            let context: std::result::Result<ClipboardContext, _> = ClipboardProvider::new();
+            *clipboard = context.ok();
+        }
+        match clipboard.as_mut() {
+            Some(context) => context.set_contents(text.to_string()).is_ok(),
+            None => false,
+        }
+    })
+}
+
+#[cfg(not(all(
+    feature = "clipboard",
+    any(windows, target_os = "macos", target_os = "linux")
+)))]
+fn copy_to_clipboard(_text: &str) -> bool {
+    false
+}
+
+/// The environment variable holding the passphrase to unlock the wallet with at startup
+const PASSPHRASE_ENV: &str = "WALLET713_PASSPHRASE";
+
+/// The passphrase to unlock the wallet with at startup: the one given with `-p`,
+/// otherwise the one in `WALLET713_PASSPHRASE`, otherwise the content of the
+/// config's `passphrase_file`. `None` unlocks the wallet without a passphrase.
+fn startup_passphrase(args: &ArgMatches, config: &Wallet713Config) -> Result<Option<String>> {
+    if args.is_present("passphrase") {
+        return Ok(Some(password_prompt(args.value_of("passphrase"))));
+    }
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(Some(passphrase));
+    }
+    match config.passphrase_file {
+        Some(ref path) => {
+            let mut content = String::new();
+            File::open(path)
+                .and_then(|mut file| file.read_to_string(&mut content))
+                .map_err(|_| ErrorKind::PassphraseFile(path.clone()))?;
+            Ok(Some(
+                content
+                    .trim_end_matches(|c| c == '\n' || c == '\r')
+                    .to_string(),
+            ))
+        }
+        None => Ok(None),
+    }
+}
+
+fn password_prompt(opt: Option<&str>) -> String {
+    opt.map(String::from).unwrap_or_else(|| {
+        rpassword::prompt_password_stdout("passphrase: ").unwrap_or(String::from(""))
+    })
+}
+
+/// The passphrase of an encrypted slate file, asked for unless it was given on the
+/// command line. When encrypting it is asked for twice, to catch a typo
+fn slate_passphrase(opt: Option<&str>, encrypting: bool) -> Result<String> {
+    if let Some(passphrase) = opt {
+        return Ok(passphrase.to_string());
+    }
+    let passphrase = rpassword::prompt_password_stdout("slate passphrase: ")?;
+    if encrypting {
+        let repeated = rpassword::prompt_password_stdout("repeat passphrase: ")?;
+        if passphrase != repeated {
+            return Err(ErrorKind::PasswordsDontMatch.into());
+        }
+    }
+    Ok(passphrase)
+}
+
+/// The content of a slate file, encrypted with `passphrase` if one is given
+fn encode_slate_file(slate: &Slate, passphrase: Option<&str>) -> Result<String> {
+    let json = serde_json::to_string(slate)?;
+    match passphrase {
+        Some(passphrase) => {
+            let sealed = PassphraseEncrypted::seal(json.as_bytes(), passphrase)?;
+            Ok(serde_json::to_string(&sealed)?)
+        }
+        None => Ok(json),
+    }
+}
+
+/// Reads the slate from the content of `file`, which has to be opened with a passphrase
+/// if it was written encrypted
+fn decode_slate_file(content: &str, file: &str, passphrase: Option<&str>) -> Result<Slate> {
+    let json = match serde_json::from_str::<PassphraseEncrypted>(content) {
+        Ok(sealed) => {
+            let passphrase =
+                passphrase.ok_or_else(|| ErrorKind::SlateFileEncrypted(file.to_string()))?;
+            let json = sealed
+                .open(passphrase)
+                .map_err(|_| ErrorKind::WrongSlatePassphrase)?;
+            String::from_utf8(json).map_err(|_| ErrorKind::WrongSlatePassphrase)?
+        }
+        Err(_) => content.to_string(),
+    };
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Signs the slate file given with `--in` using the key file given with `--key`. Needs
+/// no wallet, so it also runs on a machine that only has the key. The key's nonce is
+/// dropped from its file before the signed slate is written
+fn sign_slate_file(args: &ArgMatches, home_dir: &str) -> Result<()> {
+    let input = args.value_of("in").unwrap();
+    let mut file = File::open(input.replace("~", home_dir))?;
+    let mut slate = String::new();
+    file.read_to_string(&mut slate)?;
+    let mut slate: Slate = serde_json::from_str(&slate)?;
+
+    let key_file = args.value_of("key").unwrap();
+    let key_path = key_file.replace("~", home_dir);
+    let mut file = File::open(&key_path)?;
+    let mut key = String::new();
+    file.read_to_string(&mut key)?;
+    let mut key: SigningKey = serde_json::from_str(&key)?;
+
+    key.sign(&mut slate)?;
+    let mut file = File::create(&key_path)?;
+    file.write_all(serde_json::to_string(&key).unwrap().as_bytes())?;
+
+    let output = args.value_of("out").unwrap();
+    let mut file = File::create(output.replace("~", home_dir))?;
+    file.write_all(serde_json::to_string(&slate).unwrap().as_bytes())?;
+    cli_message!("{} signed, the signed slate is in {}.", input, output);
+    Ok(())
+}
+
+/// Asks a yes or no question on the terminal, defaulting to no
+fn confirm_prompt(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush().is_ok();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    let answer = answer.trim().to_lowercase();
+    answer == "y" || answer == "yes"
+}
+
+fn proof_ok(
+    sender: Option<String>,
+    receiver: String,
+    amount: u64,
+    outputs: Vec<String>,
+    kernel: String,
+) {
+    let sender_message = sender
+        .as_ref()
+        .map(|s| format!(" from [{}]", s.bright_green()))
+        .unwrap_or(String::new());
+
+    println!(
+        "this file proves that [{}] {} was sent to [{}]{}",
+        common::amount_to_string(amount, false).bright_green(),
+        common::amount_units(),
+        receiver.bright_green(),
+        sender_message
+    );
+
+    if sender.is_none() {
+        println!(
+            "{}: this proof does not prove which address sent the funds, only which received it",
+            "WARNING".bright_yellow()
+        );
+    }
+
+    println!("\noutputs:");
+    for output in outputs {
+        println!("   {}", output.bright_magenta());
+    }
+    println!("kernel:");
+    println!("   {}", kernel.bright_magenta());
+    println!("\n{}: this proof should only be considered valid if the kernel is actually on-chain with sufficient confirmations", "WARNING".bright_yellow());
+    println!("please use a grin block explorer to verify this is the case. for example:");
+    let prefix = match is_mainnet() {
+        true => "",
+        false => "floonet.",
+    };
+    cli_message!("   https://{}grinscan.net/kernel/{}", prefix, kernel);
+}
+
+/// Warns if the node runs a different chain than the one our grinbox address is for,
+/// as slates would then never make it on chain
+fn check_network(wallet: Arc<Mutex<Wallet>>) {
+    let address_chain = match is_mainnet() {
+        true => ChainTypes::Mainnet,
+        false => ChainTypes::Floonet,
+    };
+    // the node is queried without holding the wallet lock
+    let node_api = wallet.lock().node_api();
+    if let Some(node_chain) = node_api.and_then(|node_api| node_api.chain_type()) {
+        if node_chain != address_chain {
+            cli_message!(
+                "{}: your grinbox address is for {} but the node is running {}! check `grin_node_uri` in your config.",
+                "WARNING".bright_yellow(),
+                address_chain.shortname().bright_green(),
+                node_chain.shortname().bright_green()
+            );
+        }
+    }
+}
+
+fn resolve_address(
+    to: &str,
+    address_book: Arc<Mutex<AddressBook>>,
+) -> Result<(Box<Address>, String)> {
+    let mut to = to.to_string();
+    let mut display_to = None;
+    if to.starts_with("@") {
+        let contact = address_book.lock().get_contact(&to[1..])?;
+        to = contact.get_address().to_string();
+        display_to = Some(contact.get_name().to_string());
+    }
+
+    // try parse as a general address and fallback to grinbox address
+    let address = Address::parse(&to);
+    let address: Result<Box<Address>> = match address {
+        Ok(address) => Ok(address),
+        Err(e) => Ok(Box::new(GrinboxAddress::from_str(&to).map_err(|_| e)?) as Box<Address>),
+    };
+    let to = address?;
+    let display_to = display_to.unwrap_or(to.stripped());
+    Ok((to, display_to))
+}
+
+fn ensure_transport(
+    to: &Box<Address>,
+    keybase_broker: &Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<()> {
+    match to.address_type() {
+        AddressType::Keybase if keybase_broker.is_none() => {
+            Err(ErrorKind::ClosedListener("keybase".to_string()))?
+        }
+        AddressType::Grinbox if grinbox_broker.is_none() => {
+            Err(ErrorKind::ClosedListener("grinbox".to_string()))?
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Delivers a slate to an address. Returns the response slate for transports that
+/// respond synchronously (https), `None` otherwise.
+fn deliver_slate(
+    slate: &Slate,
+    to: &Box<Address>,
+    keybase_broker: &Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<Option<Slate>> {
+    ensure_transport(to, keybase_broker, grinbox_broker)?;
+    match to.address_type() {
+        AddressType::Keybase => {
+            if let Some((publisher, _)) = keybase_broker {
+                let mut keybase_address = contacts::KeybaseAddress::from_str(&to.to_string())?;
+                keybase_address.topic = Some(broker::TOPIC_SLATE_NEW.to_string());
+                publisher.post_slate(slate, keybase_address.borrow())?;
+            }
+            Ok(None)
+        }
+        AddressType::Grinbox => {
+            if let Some((publisher, _)) = grinbox_broker {
+                publisher.post_slate(slate, to.borrow())?;
+            }
+            Ok(None)
+        }
+        AddressType::Https => {
+            let url = Url::parse(&format!("{}/v1/wallet/foreign/receive_tx", to.to_string()))?;
+            let response: Slate =
+                client::post(url.as_str(), None, slate).map_err(|_| ErrorKind::HttpRequest)?;
+            Ok(Some(response))
+        }
+    }
+}
+
+/// Sends a processed slate back to the listener it was received from. Unlike
+/// `deliver_slate`, a keybase reply keeps the topic the sender is waiting on.
+fn post_reply(
+    slate: &Slate,
+    to: &Box<Address>,
+    keybase_broker: &Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<()> {
+    ensure_transport(to, keybase_broker, grinbox_broker)?;
+    match to.address_type() {
+        AddressType::Keybase => {
+            if let Some((publisher, _)) = keybase_broker {
+                publisher.post_slate(slate, to.borrow())?;
+            }
+        }
+        AddressType::Grinbox => {
+            if let Some((publisher, _)) = grinbox_broker {
+                publisher.post_slate(slate, to.borrow())?;
+            }
+        }
+        AddressType::Https => Err(ErrorKind::HttpRequest)?,
+    }
+    Ok(())
+}
+
+/// Reads `<amount> <address>` lines from stdin until an empty line or the end of input.
+/// Every line is validated before anything is sent, and nothing is returned if any of
+/// them is invalid.
+fn read_recipients(
+    address_book: Arc<Mutex<AddressBook>>,
+    keybase_broker: &Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<Vec<(String, u64)>> {
+    let stdin = std::io::stdin();
+    let mut recipients = vec![];
+    let mut invalid_lines = 0;
+    for (index, line) in stdin.lock().lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+
+        let result: Result<(String, u64)> =
+            Parser::split(line).and_then(|fields| match fields.len() {
+                2 => common::amount_from_string(&fields[0]).and_then(|amount| {
+                    let (to, _) = resolve_address(&fields[1], address_book.clone())?;
+                    ensure_transport(&to, keybase_broker, grinbox_broker)?;
+                    Ok((fields[1].clone(), amount))
+                }),
+                _ => Err(ErrorKind::GenericError(format!(
+                    "expected `<amount> <address>`, got `{}`",
+                    line
+                ))
+                .into()),
+            });
+
+        match result {
+            Ok(recipient) => recipients.push(recipient),
+            Err(e) => {
+                invalid_lines += 1;
+                cli_message!("line {}: {}", index + 1, format_error(&e));
+            }
+        }
+    }
+
+    if invalid_lines > 0 {
+        Err(ErrorKind::GenericError(format!(
+            "{} invalid line(s), nothing was sent",
+            invalid_lines
+        )))?;
+    }
+    Ok(recipients)
+}
+
+fn send_to(
+    to: &str,
+    amount: u64,
+    confirmations: u64,
+    strategy: &str,
+    change_outputs: usize,
+    max_outputs: usize,
+    use_outputs: Option<Vec<String>>,
+    lock_height: Option<u64>,
+    message: Option<String>,
+    change_account: Option<&str>,
+    fluff: bool,
+    allow_high_fee: bool,
+    allow_unconfirmed_change: bool,
+    expires_in: Option<u64>,
+    countdown: u64,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+    keybase_broker: &Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<SendResult> {
+    let (to, display_to) = resolve_address(to, address_book)?;
+
+    // fail before locking any outputs if we have no way to deliver the slate
+    ensure_transport(&to, keybase_broker, grinbox_broker)?;
+    if countdown > 0 {
+        cli_message!(
+            "sending [{}] {} to [{}]",
+            common::amount_to_string(amount, false).bright_green(),
+            common::amount_units(),
+            display_to.bright_green()
+        );
+        if !send_countdown(countdown) {
+            return Err(ErrorKind::SendCancelled.into());
+        }
+    }
+    let (slate, result) = wallet.lock().send(
+        Some(to.to_string()),
+        amount,
+        confirmations,
+        strategy,
+        change_outputs,
+        max_outputs,
+        use_outputs,
+        lock_height,
+        message,
+        change_account,
+        fluff,
+        allow_high_fee,
+        allow_unconfirmed_change,
+        expires_in,
+    )?;
+    let response = match deliver_slate(&slate, &to, keybase_broker, grinbox_broker) {
+        Ok(response) => response,
+        Err(e) => {
+            let offline = match e.downcast_ref::<ErrorKind>() {
+                Some(ErrorKind::RecipientOffline(..)) => true,
+                _ => false,
+            };
+            if !offline || !atty::is(atty::Stream::Stdin) {
+                return Err(e);
+            }
+            // the slate can still reach the recipient some other way
+            let file = format!("{}.tx", slate.id);
+            let question = format!(
+                "recipient [{}] appears to be offline and the grinbox relay did not keep the slate. write it to {} instead?",
+                display_to.bright_green(),
+                file
+            );
+            if !confirm_prompt(&question) {
+                return Err(e);
+            }
+            let mut output = File::create(&file)?;
+            output.write_all(serde_json::to_string(&slate)?.as_bytes())?;
+            wallet.lock().log_tx_state(&slate.id, TxState::Sent)?;
+            cli_message!(
+                "slate written to {}. send it to the recipient and `finalize` the response they return.",
+                file
+            );
+            return Ok(result);
+        }
+    };
+    wallet.lock().log_tx_state(&slate.id, TxState::Sent)?;
+
+    cli_message!(
+        "{}",
+        tr!(
+            "slate_sent",
+            slate.id.to_string().bright_green(),
+            common::amount_to_string(slate.amount, false).bright_green(),
+            common::amount_units(),
+            display_to.bright_green()
+        )
+    );
+
+    if let Some(mut slate) = response {
+        wallet.lock().finalize_slate(&mut slate, None)?;
+        cli_message!(
+            "{}",
+            tr!("slate_finalized", slate.id.to_string().bright_green())
+        );
+    }
+    Ok(result)
+}
+
+/// Counts down before a send goes out, returns false if a key was pressed to stop it
+#[cfg(unix)]
+fn send_countdown(seconds: u64) -> bool {
+    let fd = libc::STDIN_FILENO;
+    let mut original: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+        return confirm_prompt("send?");
+    }
+    // read single key presses without waiting for enter or echoing them
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 1;
+    raw.c_cc[libc::VTIME] = 0;
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) };
+
+    let mut cancelled = false;
+    for remaining in (1..=seconds).rev() {
+        print!("\rsending in {}... press any key to cancel ", remaining);
+        std::io::stdout().flush().is_ok();
+        let mut poll_fd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut poll_fd, 1, 1000) } > 0 {
+            let mut keys = [0u8; 16];
+            unsafe { libc::read(fd, keys.as_mut_ptr() as *mut libc::c_void, keys.len()) };
+            cancelled = true;
+            break;
+        }
+    }
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+    println!();
+    !cancelled
+}
+
+#[cfg(not(unix))]
+fn send_countdown(_seconds: u64) -> bool {
+    confirm_prompt("send?")
+}
+
+/// Resolves a transaction given either by its numeric id or by its slate id
+fn resolve_tx_id(id: &str, wallet: &Arc<Mutex<Wallet>>) -> Result<u32> {
+    if let Ok(id) = id.parse::<u32>() {
+        return Ok(id);
+    }
+    match Uuid::parse_str(id) {
+        Ok(slate_id) => wallet.lock().tx_id_by_slate_id(&slate_id),
+        Err(_) => Err(ErrorKind::InvalidTxId(id.to_string()))?,
+    }
+}
+
+/// Prints the wallet, node and listener status as a json object for health checks
+fn print_status_json(
+    wallet: &Wallet,
+    keybase_broker: &Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &Option<(GrinboxPublisher, GrinboxSubscriber)>,
+) -> Result<()> {
+    let grinbox_running = match grinbox_broker {
+        Some((_, subscriber)) => Some(subscriber.is_running()),
+        None => None,
+    };
+    let keybase_running = match keybase_broker {
+        Some((_, subscriber)) => subscriber.is_running(),
+        None => false,
+    };
+    let status = wallet.status_json(grinbox_running, keybase_running);
+    println!("{}", serde_json::to_string(&status)?);
+    Ok(())
+}
+
+/// Shows a spinner while the wallet waits on the node, unless output is for a script
+fn spinner(message: &str) -> Box<dyn Send> {
+    Box::new(Spinner::start(message))
+}
+
+/// Keeps progress messages off stdout while alive, so a `--json` command prints
+/// nothing but its json. The messages still go to the log.
+struct JsonOutput {
+    was_cli: bool,
+}
+
+impl JsonOutput {
+    fn start() -> Self {
+        unsafe {
+            let was_cli = common::is_cli();
+            common::set_runtime_mode(&RuntimeMode::Daemon);
+            Self { was_cli }
+        }
+    }
+}
+
+impl Drop for JsonOutput {
+    fn drop(&mut self) {
+        if self.was_cli {
+            unsafe { common::set_runtime_mode(&RuntimeMode::Cli) };
+        }
+    }
+}
+
+fn print_send_result(result: &SendResult) -> Result<()> {
+    println!("{}", serde_json::to_string(result)?);
+    Ok(())
+}
+
+fn do_command(
+    command: &str,
+    config: &mut Wallet713Config,
+    wallet: Arc<Mutex<Wallet>>,
+    address_book: Arc<Mutex<AddressBook>>,
+    keybase_broker: &mut Option<(KeybasePublisher, KeybaseSubscriber)>,
+    grinbox_broker: &mut Option<(GrinboxPublisher, GrinboxSubscriber)>,
+    out_is_safe: &mut bool,
+) -> Result<()> {
+    *out_is_safe = true;
+    let home_dir = dirs::home_dir()
+        .map(|p| p.to_str().unwrap().to_string())
+        .unwrap_or("~".to_string());
+
+    let words = Parser::split(command)?;
+    let words: Vec<&str> = words.iter().map(|word| word.as_str()).collect();
+    if words.len() > 1 && words[0] == "help" {
+        cli_message!("{}", Parser::help(&words[1..])?);
+        return Ok(());
+    }
+
+    let matches = Parser::parse(command)?;
+    match matches.subcommand_name() {
+        Some("config") => {
+            let args = matches.subcommand_matches("config").unwrap();
+            match args.subcommand_name() {
+                Some("show") => {
+                    show_config(config);
+                    return Ok(());
+                }
+                Some("check") => {
+                    check_config(config);
+                    return Ok(());
+                }
+                Some("verify-key") => {
+                    let args = args.subcommand_matches("verify-key").unwrap();
+                    verify_address_key(config, wallet, args.value_of("address"))?;
+                    return Ok(());
+                }
+                _ => {}
+            }
+
+            let new_address_index = match args.is_present("generate-address") {
+                false => None,
+                true => Some({
+                    let index = match args.value_of("generate-address-index") {
+                        Some(index) => u32::from_str_radix(index, 10)
+                            .map_err(|_| ErrorKind::NumberParsingError)?,
+                        None => config.grinbox_address_index() + 1,
+                    };
+                    config.grinbox_address_index = Some(index);
+                    index
+                }),
+            };
+
+            // keep the overrides given for this run only
+            let data_path_override = config.data_path_override.clone();
+            let color_override = config.color_override.clone();
+            let timeout_override = config.timeout_override;
+            *config = do_config(
+                args,
+                &config.chain,
+                false,
+                new_address_index,
+                config.config_home.as_ref().map(|x| &**x),
+            )?;
+            config.data_path_override = data_path_override;
+            config.color_override = color_override;
+            config.timeout_override = timeout_override;
+
+            if new_address_index.is_some() {
+                // the new address is the expected one from now on
+                config.grinbox_expected_address = None;
+                derive_address_key(config, wallet, grinbox_broker)?;
+                cli_message!(
+                    "Derived with index [{}]",
+                    config.grinbox_address_index().to_string().bright_blue()
+                );
+            }
+        }
+        Some("address") => {
+            let args = matches.subcommand_matches("address").unwrap();
+            show_address(config, true)?;
+            if args.is_present("copy") {
+                match copy_to_clipboard(&config.get_grinbox_address()?.stripped()) {
+                    true => cli_message!("address copied to the clipboard."),
+                    false => cli_message!("no clipboard available, copy the address above."),
+                }
+            }
+        }
+        Some("init") => {
+            *out_is_safe = false;
+            if keybase_broker.is_some() || grinbox_broker.is_some() {
+                return Err(ErrorKind::HasListener.into());
+            }
+            let args = matches.subcommand_matches("init").unwrap();
+            if let Some(input) = args.value_of("watch") {
+                let path = Path::new(&input.replace("~", &home_dir)).to_path_buf();
+                if !path.exists() {
+                    return Err(ErrorKind::FileNotFound(input.to_string()).into());
+                }
+                let mut file = File::open(path)?;
+                let mut watch_data = String::new();
+                file.read_to_string(&mut watch_data)?;
+                let watch_data: WatchData = serde_json::from_str(&watch_data)?;
+                let mut w = wallet.lock();
+                w.init_watch(config, &watch_data)?;
+                cli_message!(
+                    "watch-only wallet initialized for account [{}]",
+                    watch_data.account.bright_green()
+                );
+                w.watch_info(config)?;
+                return Ok(());
+            }
+            let passphrase = match args.is_present("passphrase") {
+                true => password_prompt(args.value_of("passphrase")),
+                false => "".to_string(),
+            };
+            *out_is_safe = args.value_of("passphrase").is_none();
+            let force = args.is_present("force");
+            let account = args.value_of("account").unwrap_or("default");
+            let seed_words = args.value_of("words").unwrap_or("24");
+            let seed_words =
+                usize::from_str_radix(seed_words, 10).map_err(|_| ErrorKind::NumberParsingError)?;
+
+            // an empty passphrase is warned about once the wallet is created
+            if !passphrase.is_empty() && !args.is_present("no-password-check") {
+                if let Some(reason) = password::passphrase_weakness(&passphrase) {
+                    if args.is_present("strict") {
+                        return Err(ErrorKind::WeakPassphrase(reason.to_string()).into());
+                    }
+                    cli_message!(
+                        "{}: the passphrase is weak, {}.",
+                        "WARNING".bright_yellow(),
+                        reason
+                    );
+                }
+            }
+
+            {
+                wallet.lock().init(
+                    config,
+                    account,
+                    passphrase.as_str(),
+                    true,
+                    force,
+                    seed_words,
+                )?;
+            }
+            if account != config.default_account() {
+                config.default_account = Some(account.to_string());
+                let config_home = config.config_home.clone();
+                config.to_file(config_home.as_ref().map(|x| &**x))?;
+            }
+            derive_address_key(config, wallet, grinbox_broker)?;
+            if passphrase.is_empty() {
+                cli_message!("{}: wallet with no passphrase.", "WARNING".bright_yellow());
+            }
+            return Ok(());
+        }
+        Some("lock") => {
+            if keybase_broker.is_some() || grinbox_broker.is_some() {
+                return Err(ErrorKind::HasListener.into());
+            }
+            wallet.lock().lock();
+        }
+        Some("unlock") => {
+            let args = matches.subcommand_matches("unlock").unwrap();
+            let account = args
+                .value_of("account")
+                .unwrap_or(config.default_account())
+                .to_string();
+            let passphrase = match args.is_present("passphrase") {
+                true => password_prompt(args.value_of("passphrase")),
+                false => "".to_string(),
+            };
+            *out_is_safe = args.value_of("passphrase").is_none();
+
+            {
+                let mut w = wallet.lock();
+                if !w.is_locked() {
+                    return Err(ErrorKind::WalletAlreadyUnlocked.into());
+                }
+                w.unlock(config, &account, passphrase.as_str())?;
+            }
+
+            derive_address_key(config, wallet, grinbox_broker)?;
+            return Ok(());
+        }
+        Some("accounts") => {
+            wallet.lock().list_accounts()?;
+        }
+        Some("xpub") => {
+            wallet.lock().show_public_keys()?;
+        }
+        Some("account") => {
+            let args = matches.subcommand_matches("account").unwrap();
+            *out_is_safe = args.value_of("passphrase").is_none();
+
+            let create_args = args.subcommand_matches("create");
+            let switch_args = args.subcommand_matches("switch");
+            if let Some(args) = create_args {
+                wallet
+                    .lock()
+                    .create_account(args.value_of("name").unwrap())?;
+            } else if let Some(args) = switch_args {
+                let account = args.value_of("name").unwrap();
+                let passphrase = match args.is_present("passphrase") {
+                    true => password_prompt(args.value_of("passphrase")),
+                    false => "".to_string(),
+                };
+                wallet.lock().unlock(config, account, passphrase.as_str())?;
+                if account != config.default_account() {
+                    config.default_account = Some(account.to_string());
+                    let config_home = config.config_home.clone();
+                    config.to_file(config_home.as_ref().map(|x| &**x))?;
+                }
+            }
+
+            return Ok(());
+        }
+        Some("listen") => {
+            let grinbox = matches
+                .subcommand_matches("listen")
+                .unwrap()
+                .is_present("grinbox");
+            let keybase = matches
+                .subcommand_matches("listen")
+                .unwrap()
+                .is_present("keybase");
+            let manual = matches
+                .subcommand_matches("listen")
+                .unwrap()
+                .is_present("manual");
+            let http = matches
+                .subcommand_matches("listen")
+                .unwrap()
+                .value_of("http");
+            let retry = matches
+                .subcommand_matches("listen")
+                .unwrap()
+                .is_present("retry");
+            if let Some(address) = http {
+                start_http_listener(
+                    address,
+                    config,
+                    wallet.clone(),
+                    keybase_broker,
+                    grinbox_broker,
+                )?;
+            }
+            if grinbox || (!keybase && http.is_none()) {
+                let is_running = match grinbox_broker {
+                    Some((_, subscriber)) => subscriber.is_running(),
+                    _ => false,
+                };
+                if is_running {
+                    Err(ErrorKind::AlreadyListening("grinbox".to_string()))?
+                } else {
+                    let (publisher, subscriber) = start_grinbox_listener(
+                        config,
+                        wallet.clone(),
+                        address_book.clone(),
+                        manual,
+                        retry,
+                    )?;
+                    *grinbox_broker = Some((publisher, subscriber));
+                    check_network(wallet.clone());
+                }
+            }
+            if keybase {
+                let is_running = match keybase_broker {
+                    Some((_, subscriber)) => subscriber.is_running(),
+                    _ => false,
+                };
+                if is_running {
+                    Err(ErrorKind::AlreadyListening("keybase".to_string()))?
+                } else {
+                    let (publisher, subscriber) = start_keybase_listener(
+                        config,
+                        wallet.clone(),
+                        address_book.clone(),
+                        manual,
+                    )?;
+                    *keybase_broker = Some((publisher, subscriber));
+                }
+            }
+        }
+        Some("stop") => {
+            let grinbox = matches
+                .subcommand_matches("stop")
+                .unwrap()
+                .is_present("grinbox");
+            let keybase = matches
+                .subcommand_matches("stop")
+                .unwrap()
+                .is_present("keybase");
+            if grinbox || !keybase {
+                let is_running = match grinbox_broker {
+                    Some((_, subscriber)) => subscriber.is_running(),
+                    _ => false,
+                };
+                if is_running {
+                    cli_message!("{}", tr!("listener_stopping", "grinbox"));
+                    if let Some((_, subscriber)) = grinbox_broker {
+                        subscriber.stop();
+                    };
+                    *grinbox_broker = None;
+                } else {
+                    Err(ErrorKind::ClosedListener("grinbox".to_string()))?
+                }
+            }
+            if keybase {
+                let is_running = match keybase_broker {
+                    Some((_, subscriber)) => subscriber.is_running(),
+                    _ => false,
+                };
+                if is_running {
+                    cli_message!("{}", tr!("listener_stopping", "keybase"));
+                    if let Some((_, subscriber)) = keybase_broker {
+                        subscriber.stop();
+                    };
+                    *keybase_broker = None;
+                } else {
+                    Err(ErrorKind::ClosedListener("keybase".to_string()))?
+                }
+            }
+        }
+        Some("status") => {
+            let args = matches.subcommand_matches("status").unwrap();
+            if args.is_present("json") {
+                return print_status_json(&wallet.lock(), keybase_broker, grinbox_broker);
+            }
+            let w = wallet.lock();
+            let wallet_status = match w.is_locked() {
+                true => "locked".bright_red(),
+                false => "unlocked".bright_green(),
+            };
+            cli_message!("{}: {}", "Wallet".bright_yellow(), wallet_status);
+            let node_uri = w
+                .active_node_uri()
+                .unwrap_or(String::from("none responding"));
+            cli_message!(
+                "{}: {}",
+                "Active node".bright_yellow(),
+                node_uri.bright_green()
+            );
+            let listener_status = |is_running: bool| match is_running {
+                true => "listening".bright_green(),
+                false => "stopped".bright_red(),
+            };
+            let grinbox_running = match grinbox_broker {
+                Some((_, subscriber)) => subscriber.is_running(),
+                _ => false,
+            };
+            let keybase_running = match keybase_broker {
+                Some((_, subscriber)) => subscriber.is_running(),
+                _ => false,
+            };
+            cli_message!(
+                "{}: {}",
+                "Grinbox listener".bright_yellow(),
+                listener_status(grinbox_running)
+            );
+            cli_message!(
+                "{}: {}",
+                "Keybase listener".bright_yellow(),
+                listener_status(keybase_running)
+            );
+        }
+        Some("reload") => {
+            reload_config(config, wallet.clone())?;
+        }
+        Some("ping") => {
+            let args = matches.subcommand_matches("ping").unwrap();
+            let (to, display_to) =
+                resolve_address(args.value_of("to").unwrap(), address_book.clone())?;
+            if to.address_type() != AddressType::Grinbox {
+                return Err(ErrorKind::PingRequiresGrinbox(to.to_string()).into());
+            }
+            let elapsed = match grinbox_broker {
+                Some((ref publisher, _)) => publisher.ping(to.borrow())?,
+                None => Err(ErrorKind::ClosedListener("grinbox".to_string()))?,
+            };
+            let millis = elapsed.as_secs() * 1000 + elapsed.subsec_millis() as u64;
+            cli_message!(
+                "[{}] is reachable, it answered in {} ms",
+                display_to.bright_green(),
+                millis
+            );
+        }
+        Some("stats") => {
+            let stats = match grinbox_broker {
+                Some((ref publisher, _)) => publisher.stats(),
+                None => Err(ErrorKind::ClosedListener("grinbox".to_string()))?,
+            };
+            cli_message!("{}: {}", "Sent".bright_yellow(), stats.sent);
+            cli_message!("{}: {}", "Received".bright_yellow(), stats.received);
+            cli_message!("{}: {}", "Failed".bright_yellow(), stats.failed);
+            cli_message!("{}: {}", "Reconnects".bright_yellow(), stats.reconnects);
+        }
+        Some("whoami") => {
+            let w = wallet.lock();
+            let unavailable = || "unavailable".bright_red();
+            match w.is_locked() {
+                true => cli_message!("{}: {}", "Account".bright_yellow(), "locked".bright_red()),
+                false => cli_message!(
+                    "{}: {}",
+                    "Account".bright_yellow(),
+                    w.active_account().bright_green()
+                ),
+            };
+            match config.get_grinbox_address() {
+                Ok(address) => cli_message!(
+                    "{}: {}",
+                    "Grinbox address".bright_yellow(),
+                    address.stripped().bright_green()
+                ),
+                Err(_) => cli_message!("{}: {}", "Grinbox address".bright_yellow(), unavailable()),
+            };
+            let network = match is_mainnet() {
+                true => "mainnet",
+                false => "floonet",
+            };
+            cli_message!("{}: {}", "Network".bright_yellow(), network.bright_green());
+            match w
+                .active_node_uri()
+                .or_else(|| config.grin_node_uris().first().cloned())
+            {
+                Some(node_uri) => {
+                    cli_message!("{}: {}", "Node".bright_yellow(), node_uri.bright_green())
+                }
+                None => cli_message!("{}: {}", "Node".bright_yellow(), unavailable()),
+            };
+            let grinbox_running = match grinbox_broker {
+                Some((_, subscriber)) => subscriber.is_running(),
+                _ => false,
+            };
+            let keybase_running = match keybase_broker {
+                Some((_, subscriber)) => subscriber.is_running(),
+                _ => false,
+            };
+            let listening = match (grinbox_running, keybase_running) {
+                (true, true) => "grinbox, keybase".bright_green(),
+                (true, false) => "grinbox".bright_green(),
+                (false, true) => "keybase".bright_green(),
+                (false, false) => "not listening".bright_red(),
+            };
+            cli_message!("{}: {}", "Listening".bright_yellow(), listening);
+            match w.retrieve_summary_info(true) {
+                Ok(info) => cli_message!(
+                    "{}: {} ({} spendable, {} awaiting confirmation)",
+                    "Balance".bright_yellow(),
+                    common::amount_to_string(info.total, false).bright_green(),
+                    common::amount_to_string(info.amount_currently_spendable, false),
+                    common::amount_to_string(info.amount_awaiting_confirmation, false)
+                ),
+                _ => cli_message!("{}: {}", "Balance".bright_yellow(), unavailable()),
+            };
+        }
+        Some("info") => {
+            let args = matches.subcommand_matches("info").unwrap();
+            let mut w = wallet.lock();
+            if args.is_present("cached") {
+                Wallet::cached_info(config)?;
+            } else if args.is_present("accounts") {
+                w.info_all_accounts()?;
+            } else if w.is_locked() && Wallet::has_watch_data(config) {
+                w.watch_info(config)?;
+            } else {
+                w.info(config)?;
+            }
+        }
+        Some("txs") => {
+            let args = matches.subcommand_matches("txs").unwrap();
+            if let Some(show_args) = args.subcommand_matches("show") {
+                let id = resolve_tx_id(show_args.value_of("id").unwrap(), &wallet)?;
+                let absolute_time = show_args.is_present("absolute-time");
+                let log = show_args.is_present("log");
+                wallet.lock().show_tx(id, absolute_time, log)?;
+            } else if let Some(note_args) = args.subcommand_matches("note") {
+                let id = resolve_tx_id(note_args.value_of("id").unwrap(), &wallet)?;
+                let text = note_args.value_of("text").unwrap();
+                wallet.lock().set_tx_note(id, text)?;
+                match text.trim().is_empty() {
+                    true => cli_message!(
+                        "note of transaction [{}] cleared",
+                        id.to_string().bright_green()
+                    ),
+                    false => cli_message!(
+                        "note of transaction [{}] set",
+                        id.to_string().bright_green()
+                    ),
+                }
+            } else if let Some(bundle_args) = args.subcommand_matches("proof-bundle") {
+                let id = resolve_tx_id(bundle_args.value_of("id").unwrap(), &wallet)?;
+                let output = bundle_args.value_of("file").unwrap();
+                let signer = config.get_grinbox_address()?.public_key.clone();
+                let secret_key = config.get_grinbox_secret_key()?;
+                let bundle = wallet.lock().proof_bundle(id, &signer, &secret_key)?;
+                let mut file = File::create(output.replace("~", &home_dir))?;
+                file.write_all(serde_json::to_string_pretty(&bundle)?.as_bytes())?;
+                cli_message!(
+                    "proof bundle of transaction [{}] written to {}",
+                    id.to_string().bright_green(),
+                    output
+                );
+                if bundle.content()?.proof.is_none() {
+                    cli_message!(
+                        "{}: the bundle holds no payment proof, only transactions sent over grinbox have one",
+                        "WARNING".bright_yellow()
+                    );
+                }
+            } else if let Some(resend_args) = args.subcommand_matches("resend") {
+                let id = resend_args.value_of("id").unwrap();
+                let id = id
+                    .parse::<u32>()
+                    .map_err(|_| ErrorKind::InvalidTxId(id.to_string()))?;
+                let slate = wallet.lock().get_stored_slate(id)?;
+
+                if let Some(output) = resend_args.value_of("file") {
+                    let mut file = File::create(output.replace("~", &home_dir))?;
+                    file.write_all(serde_json::to_string(&slate)?.as_bytes())?;
+                    wallet.lock().log_tx_state(&slate.id, TxState::Sent)?;
+                    cli_message!("{} created successfully.", output);
+                    return Ok(());
+                }
+
+                let to = resend_args.value_of("to").unwrap();
+                let (to, display_to) = resolve_address(to, address_book.clone())?;
+                let response = deliver_slate(&slate, &to, keybase_broker, grinbox_broker)?;
+                wallet.lock().log_tx_state(&slate.id, TxState::Sent)?;
+                cli_message!(
+                    "{}",
+                    tr!(
+                        "slate_resent",
+                        slate.id.to_string().bright_green(),
+                        display_to.bright_green()
+                    )
+                );
+                if let Some(mut slate) = response {
+                    wallet.lock().finalize_slate(&mut slate, None)?;
+                    cli_message!(
+                        "{}",
+                        tr!("slate_finalized", slate.id.to_string().bright_green())
+                    );
+                }
+            } else {
+                let include_pruned = args.is_present("include-pruned");
+                let absolute_time = args.is_present("absolute-time");
+                wallet
+                    .lock()
+                    .txs(Some(address_book.clone()), include_pruned, absolute_time)?;
+            }
+        }
+        Some("contacts") => {
+            let arg_matches = matches.subcommand_matches("contacts").unwrap();
+            do_contacts(&arg_matches, address_book.clone())?;
+        }
+        Some("prune") => {
+            let args = matches.subcommand_matches("prune").unwrap();
+            let days = match args.value_of("days") {
+                Some(days) => u64::from_str_radix(days, 10)
+                    .ok()
+                    .filter(|days| *days <= time::MAX_SPAN_MINUTES / time::MINUTES_PER_DAY)
+                    .ok_or(ErrorKind::NumberParsingError)?,
+                None => config.prune_cancelled_after_days.unwrap_or(30),
+            };
+            let pruned = wallet.lock().prune(days)?;
+            cli_message!(
+                "pruned [{}] cancelled transaction(s) older than {} days",
+                pruned.to_string().bright_green(),
+                days
+            );
+        }
+        Some("outputs") => {
+            let args = matches.subcommand_matches("outputs").unwrap();
+            let show_spent = args.is_present("show-spent");
+            let coinbase_only = args.is_present("coinbase-only");
+            if let Some(export) = args.value_of("export") {
+                let outputs = wallet.lock().export_outputs(show_spent, coinbase_only)?;
+                let content = match export.to_lowercase().ends_with(".csv") {
+                    true => {
+                        let mut lines = vec![ExportedOutput::csv_header().to_string()];
+                        lines.extend(outputs.iter().map(|output| output.to_csv_row()));
+                        lines.join("\n") + "\n"
+                    }
+                    false => serde_json::to_string_pretty(&outputs)?,
+                };
+                let mut file = File::create(export.replace("~", &home_dir))?;
+                file.write_all(content.as_bytes())?;
+                cli_message!("{} outputs written to {}", outputs.len(), export);
+            } else {
+                wallet.lock().outputs(show_spent, coinbase_only)?;
+            }
+        }
+        Some("repost") => {
+            let args = matches.subcommand_matches("repost").unwrap();
+            let id = resolve_tx_id(args.value_of("id").unwrap(), &wallet)?;
+            if let Some(dump) = args.value_of("dump") {
+                let tx = wallet.lock().dump_tx(id)?;
+                let mut file = File::create(dump.replace("~", &home_dir))?;
+                file.write_all(serde_json::to_string(&tx)?.as_bytes())?;
+                cli_message!(
+                    "transaction [{}] written to {}",
+                    id.to_string().bright_green(),
+                    dump
+                );
+                return Ok(());
+            }
+            let fluff = args.is_present("fluff");
+            let allow_high_fee = args.is_present("allow-high-fee");
+            wallet.lock().repost(id, fluff, allow_high_fee)?;
+        }
+        Some("cancel") => {
+            let args = matches.subcommand_matches("cancel").unwrap();
+            if let Some(to) = args.value_of("to") {
+                let (to, display_to) = resolve_address(to, address_book.clone())?;
+                let ids = wallet.lock().pending_tx_ids_with(&to)?;
+                for id in &ids {
+                    wallet.lock().cancel(*id)?;
+                    cli_message!("transaction [{}] cancelled", id.to_string().bright_green());
+                }
+                cli_message!(
+                    "{} pending transaction(s) with [{}] cancelled",
+                    ids.len(),
+                    display_to.bright_green()
+                );
+            } else {
+                let id = resolve_tx_id(args.value_of("id").unwrap(), &wallet)?;
+                wallet.lock().cancel(id)?;
+            }
+        }
+        Some("accept") => {
+            let args = matches.subcommand_matches("accept").unwrap();
+            let id = args.value_of("id").unwrap();
+            let id = Uuid::parse_str(id).map_err(|_| ErrorKind::InvalidSlateId(id.to_string()))?;
+            let mut pending = wallet.lock().get_pending_slate(&id)?;
+            let from = Address::parse(&pending.from)?;
+            // check the reply can go out before the slate is processed
+            if !pending.is_response() {
+                ensure_transport(&from, keybase_broker, grinbox_broker)?;
+            }
+            let is_finalized = process_incoming_slate(
+                &wallet,
+                Some(pending.from.clone()),
+                &mut pending.slate,
+                pending.tx_proof.as_mut(),
+            )?;
+            wallet.lock().remove_pending_slate(&id)?;
+            if is_finalized {
+                cli_message!("{}", tr!("slate_finalized", id.to_string().bright_green()));
+            } else {
+                post_reply(&pending.slate, &from, keybase_broker, grinbox_broker)?;
+                cli_message!(
+                    "{}",
+                    tr!(
+                        "slate_sent_back",
+                        id.to_string().bright_green(),
+                        from.stripped().bright_green()
+                    )
+                );
+            }
+        }
+        Some("reject") => {
+            let args = matches.subcommand_matches("reject").unwrap();
+            let id = args.value_of("id").unwrap();
+            let id = Uuid::parse_str(id).map_err(|_| ErrorKind::InvalidSlateId(id.to_string()))?;
+            let pending = wallet.lock().get_pending_slate(&id)?;
+            // a response to one of our own sends still has its outputs locked
+            if pending.is_response() {
+                wallet.lock().cancel_by_slate_id(&id)?;
+            }
+            wallet.lock().remove_pending_slate(&id)?;
+            cli_message!("{}", tr!("slate_rejected", id.to_string().bright_green()));
+        }
+        Some("pending") => {
+            let args = matches.subcommand_matches("pending").unwrap();
+            let absolute_time = args.is_present("absolute-time");
+            wallet
+                .lock()
+                .pending_slates(Some(address_book.clone()), absolute_time)?;
+        }
+        Some("receive") => {
+            let args = matches.subcommand_matches("receive").unwrap();
+            let input = args.value_of("file").unwrap();
+            let passphrase = match args.is_present("decrypt-slate") {
+                true => Some(slate_passphrase(args.value_of("decrypt-slate"), false)?),
+                false => None,
+            };
+            *out_is_safe = args.value_of("decrypt-slate").is_none();
+            let mut file = File::open(input.replace("~", &home_dir))?;
+            let mut slate = String::new();
+            file.read_to_string(&mut slate)?;
+            let mut slate = decode_slate_file(&slate, input, passphrase.as_ref().map(|x| &**x))?;
+            let mut file = File::create(&format!("{}.response", input.replace("~", &home_dir)))?;
+            wallet
+                .lock()
+                .process_sender_initiated_slate(Some(String::from("file")), &mut slate)?;
+            cli_message!("{} received.", input);
+            // the response goes back over the same channel, so it is encrypted the same way
+            let response = encode_slate_file(&slate, passphrase.as_ref().map(|x| &**x))?;
+            file.write_all(response.as_bytes())?;
+            cli_message!("{}.response created successfully.", input);
+        }
+        Some("finalize") => {
+            let args = matches.subcommand_matches("finalize").unwrap();
+            let input = args.value_of("file").unwrap();
+            let passphrase = match args.is_present("decrypt-slate") {
+                true => Some(slate_passphrase(args.value_of("decrypt-slate"), false)?),
+                false => None,
+            };
+            *out_is_safe = args.value_of("decrypt-slate").is_none();
+            let mut file = File::open(input.replace("~", &home_dir))?;
+            let mut slate = String::new();
+            file.read_to_string(&mut slate)?;
+            let mut slate = decode_slate_file(&slate, input, passphrase.as_ref().map(|x| &**x))?;
+            wallet.lock().finalize_slate(&mut slate, None)?;
+            cli_message!("{} finalized.", input);
+        }
+        Some("sign-slate") => {
+            let args = matches.subcommand_matches("sign-slate").unwrap();
+            sign_slate_file(args, &home_dir)?;
+        }
+        Some("export-signing-key") => {
+            let args = matches.subcommand_matches("export-signing-key").unwrap();
+            let id = args.value_of("id").unwrap();
+            let id = id
+                .parse::<u32>()
+                .map_err(|_| ErrorKind::InvalidTxId(id.to_string()))?;
+            let output = args.value_of("out").unwrap();
+            let path = output.replace("~", &home_dir);
+            if Path::new(&path).exists() {
+                return Err(ErrorKind::FileExists(output.to_string()))?;
+            }
+            let key = wallet.lock().export_signing_key(id)?;
+            let mut file = File::create(path)?;
+            file.write_all(serde_json::to_string(&key).unwrap().as_bytes())?;
+            cli_message!(
+                "the signing key of transaction [{}] is in {}, the wallet doesn't have it anymore.",
+                id.to_string().bright_green(),
+                output
+            );
+        }
+        Some("send") => {
+            let args = matches.subcommand_matches("send").unwrap();
+            let to = args.value_of("to");
+
+            // a contact's stored defaults apply unless they are overridden by flags
+            let contact = match to {
+                Some(to) if to.starts_with("@") => address_book.lock().get_contact(&to[1..]).ok(),
+                _ => None,
+            };
+            let contact_file = match contact.as_ref() {
+                Some(contact) if contact.get_transport() == Some("file") => {
+                    let dir = config.contact_slate_dir()?;
+                    std::fs::create_dir_all(&dir)?;
+                    let path = dir.join(format!("{}.tx", contact.get_name()));
+                    Some(path.to_string_lossy().into_owned())
+                }
+                _ => None,
+            };
+            let input = args
+                .value_of("file")
+                .map(|file| file.to_string())
+                .or(contact_file);
+            let message = args.value_of("message").map(|s| s.to_string());
+            let change_account = args.value_of("change-account");
+            let fluff = args.is_present("fluff");
+            let allow_high_fee = args.is_present("allow-high-fee");
+            let allow_unconfirmed_change = args.is_present("allow-unconfirmed-change");
+            let json = args.is_present("json");
+            let _json_output = match json {
+                true => Some(JsonOutput::start()),
+                false => None,
+            };
+            let use_outputs: Option<Vec<String>> = args
+                .values_of("use-output")
+                .map(|commits| commits.map(|c| c.to_string()).collect());
+
+            let strategy = args
+                .value_of("strategy")
+                .or(contact.as_ref().and_then(|contact| contact.get_strategy()))
+                .unwrap_or("smallest");
+            if strategy != "smallest" && strategy != "all" {
+                return Err(ErrorKind::InvalidStrategy.into());
+            }
+            check_network(wallet.clone());
+
+            let confirmations = match args.value_of("confirmations") {
+                Some(confirmations) => u64::from_str_radix(confirmations, 10)
+                    .map_err(|_| ErrorKind::InvalidMinConfirmations(confirmations.to_string()))?,
+                None => config.spend_confirmations(),
+            };
+
+            let change_outputs = args.value_of("change-outputs").unwrap_or("1");
+            let change_outputs = usize::from_str_radix(change_outputs, 10)
+                .map_err(|_| ErrorKind::InvalidNumOutputs(change_outputs.to_string()))?;
+
+            let max_outputs = match args.value_of("max-outputs") {
+                Some(max_outputs) => usize::from_str_radix(max_outputs, 10)
+                    .ok()
+                    .filter(|max_outputs| *max_outputs > 0)
+                    .ok_or_else(|| ErrorKind::InvalidNumOutputs(max_outputs.to_string()))?,
+                None => config.max_selectable_outputs(),
+            };
+
+            let lock_height = match args.value_of("lock-height") {
+                Some(h) => Some(
+                    u64::from_str_radix(h, 10)
+                        .map_err(|_| ErrorKind::InvalidLockHeight(h.to_string()))?,
+                ),
+                None => None,
+            };
+
+            let expires_in = match args.value_of("expires-in") {
+                Some(minutes) => Some(
+                    u64::from_str_radix(minutes, 10)
+                        .ok()
+                        .filter(|minutes| *minutes > 0 && *minutes <= time::MAX_SPAN_MINUTES)
+                        .ok_or(ErrorKind::NumberParsingError)?,
+                ),
+                None => None,
+            };
+
+            // only count down when someone is at the terminal to stop the send
+            let interactive = !args.is_present("yes") && atty::is(atty::Stream::Stdin);
+            let mut countdown = match interactive {
+                true => config.send_countdown_seconds(),
+                false => 0,
+            };
+
+            let amount = args.value_of("amount");
+            if to.is_none() && input.is_none() {
+                if amount != Some("-") {
+                    return Err(ErrorKind::MissingDestination.into());
+                }
+                let recipients =
+                    read_recipients(address_book.clone(), keybase_broker, grinbox_broker)?;
+                let total = recipients.len();
+                let mut failed = 0;
+                for (to, amount) in recipients {
+                    let result = send_to(
+                        &to,
+                        amount,
+                        confirmations,
+                        strategy,
+                        change_outputs,
+                        max_outputs,
+                        use_outputs.clone(),
+                        lock_height,
+                        message.clone(),
+                        change_account,
+                        fluff,
+                        allow_high_fee,
+                        allow_unconfirmed_change,
+                        expires_in,
+                        countdown,
+                        wallet.clone(),
+                        address_book.clone(),
+                        keybase_broker,
+                        grinbox_broker,
+                    );
+                    match result {
+                        Ok(ref result) if json => print_send_result(result)?,
+                        Ok(_) => {}
+                        Err(e) => {
+                            failed += 1;
+                            cli_message!(
+                                "{}: could not send to [{}]: {}",
+                                "ERROR".bright_red(),
+                                to.bright_green(),
+                                format_error(&e)
+                            );
+                        }
+                    }
+                }
+                if failed > 0 {
+                    return Err(ErrorKind::SendsFailed(failed, total).into());
+                }
+                return Ok(());
+            }
+
+            let amount = match args.value_of("percent") {
+                Some(percent) => {
+                    let percent = u64::from_str_radix(percent, 10)
+                        .ok()
+                        .filter(|percent| *percent > 0 && *percent <= 100)
+                        .ok_or_else(|| ErrorKind::InvalidPercent(percent.to_string()))?;
+                    let amount =
+                        wallet
+                            .lock()
+                            .amount_for_percent(percent, confirmations, change_outputs)?;
+                    cli_message!(
+                        "sending [{}] {}, {}% of the spendable balance less the fee",
+                        common::amount_to_string(amount, false).bright_green(),
+                        common::amount_units(),
+                        percent
+                    );
+                    amount
+                }
+                None => match args.value_of("fiat") {
+                    Some(fiat) => {
+                        let (value, currency) = common::fiat_amount_from_string(fiat)?;
+                        let url = config
+                            .price_api_url
+                            .as_ref()
+                            .ok_or(ErrorKind::MissingPriceApiUrl)?;
+                        let price = wallet.lock().grin_price(url, &currency)?;
+                        let amount = (value / price * 1_000_000_000.0).round();
+                        if amount >= u64::max_value() as f64 {
+                            return Err(ErrorKind::AmountOverflow.into());
+                        }
+                        let amount = amount as u64;
+                        if amount == 0 {
+                            return Err(ErrorKind::InvalidFiatAmount(fiat.to_string()).into());
+                        }
+                        cli_message!(
+                            "at {} {} per grin, {} {} is [{}] {}",
+                            price,
+                            currency.to_uppercase(),
+                            value,
+                            currency.to_uppercase(),
+                            common::amount_to_string(amount, false).bright_green(),
+                            common::amount_units()
+                        );
+                        // the rate was just confirmed, no need to count down as well
+                        if interactive {
+                            if !confirm_prompt("send at this rate?") {
+                                return Err(ErrorKind::SendCancelled.into());
+                            }
+                            countdown = 0;
+                        }
+                        amount
+                    }
+                    None => common::amount_from_string(amount.unwrap())?,
+                },
+            };
+
+            // Store slate in a file
+            if let Some(input) = input {
+                let passphrase = match args.is_present("encrypt-slate") {
+                    true => Some(slate_passphrase(args.value_of("encrypt-slate"), true)?),
+                    false => None,
+                };
+                *out_is_safe = args.value_of("encrypt-slate").is_none();
+                let mut file = File::create(input.replace("~", &home_dir))?;
+                let (slate, result) = wallet.lock().send(
+                    Some(String::from("file")),
+                    amount,
+                    confirmations,
+                    strategy,
+                    change_outputs,
+                    max_outputs,
+                    use_outputs,
+                    lock_height,
+                    message,
+                    change_account,
+                    fluff,
+                    allow_high_fee,
+                    allow_unconfirmed_change,
+                    expires_in,
+                )?;
+                let content = encode_slate_file(&slate, passphrase.as_ref().map(|x| &**x))?;
+                file.write_all(content.as_bytes())?;
+                wallet.lock().log_tx_state(&slate.id, TxState::Sent)?;
+                cli_message!("{} created successfully.", input);
+                if json {
+                    print_send_result(&result)?;
+                }
+                return Ok(());
+            }
+
+            let to = to.unwrap();
+            if to.starts_with("@") {
+                let name = &to[1..];
+                let contact = address_book.lock().get_contact(name);
+                if let Err(e) = contact {
+                    // not a contact, try resolving as a group of contacts
+                    let group = address_book.lock().get_group(name).map_err(|_| e)?;
+                    let shares = group.split_amount(amount)?;
+                    let total = shares.len();
+                    let mut failed = 0;
+                    for (member, share) in shares {
+                        let result = send_to(
+                            &format!("@{}", member),
+                            share,
+                            confirmations,
+                            strategy,
+                            change_outputs,
+                            max_outputs,
+                            use_outputs.clone(),
+                            lock_height,
+                            message.clone(),
+                            change_account,
+                            fluff,
+                            allow_high_fee,
+                            allow_unconfirmed_change,
+                            expires_in,
+                            countdown,
+                            wallet.clone(),
+                            address_book.clone(),
+                            keybase_broker,
+                            grinbox_broker,
+                        );
+                        match result {
+                            Ok(ref result) if json => print_send_result(result)?,
+                            Ok(_) => {}
+                            Err(e) => {
+                                failed += 1;
+                                cli_message!(
+                                    "{}: could not send to [{}]: {}",
+                                    "ERROR".bright_red(),
+                                    member.bright_green(),
+                                    format_error(&e)
+                                );
+                            }
+                        }
+                    }
+                    if failed > 0 {
+                        return Err(ErrorKind::SendsFailed(failed, total).into());
+                    }
+                    return Ok(());
+                }
+            }
+
+            let result = send_to(
+                to,
+                amount,
+                confirmations,
+                strategy,
+                change_outputs,
+                max_outputs,
+                use_outputs,
+                lock_height,
+                message,
+                change_account,
+                fluff,
+                allow_high_fee,
+                allow_unconfirmed_change,
+                expires_in,
+                countdown,
+                wallet.clone(),
+                address_book.clone(),
+                keybase_broker,
+                grinbox_broker,
+            )?;
+            if json {
+                print_send_result(&result)?;
+            }
+        }
+        Some("invoice") => {
+            let args = matches.subcommand_matches("invoice").unwrap();
+            let to = args.value_of("to").unwrap();
+            let outputs = args.value_of("outputs").unwrap_or("1");
+            let outputs = usize::from_str_radix(outputs, 10)
+                .map_err(|_| ErrorKind::InvalidNumOutputs(outputs.to_string()))?;
+            let amount = args.value_of("amount").unwrap();
+            let amount = common::amount_from_string(amount)?;
+
+            let mut to = to.to_string();
+            let mut display_to = None;
+            if to.starts_with("@") {
+                let contact = address_book.lock().get_contact(&to[1..])?;
+                to = contact.get_address().to_string();
+                display_to = Some(contact.get_name().to_string());
+            }
+
+            // try parse as a general address
+            let address = Address::parse(&to);
+            let address: Result<Box<Address>> = match address {
+                Ok(address) => Ok(address),
+                Err(e) => {
+                    Ok(Box::new(GrinboxAddress::from_str(&to).map_err(|_| e)?) as Box<Address>)
+                }
+            };
+
+            let to = address?;
+            if display_to.is_none() {
+                display_to = Some(to.stripped());
+            }
+            let slate: Result<Slate> = match to.address_type() {
+                AddressType::Keybase => {
+                    if let Some((publisher, _)) = keybase_broker {
+                        let slate = wallet.lock().initiate_receive_tx(amount, outputs)?;
+                        publisher.post_slate(&slate, to.borrow())?;
+                        Ok(slate)
+                    } else {
+                        Err(ErrorKind::ClosedListener("keybase".to_string()))?
+                    }
+                }
+                AddressType::Grinbox => {
+                    if let Some((publisher, _)) = grinbox_broker {
+                        let slate = wallet.lock().initiate_receive_tx(amount, outputs)?;
+                        publisher.post_slate(&slate, to.borrow())?;
+                        Ok(slate)
+                    } else {
+                        Err(ErrorKind::ClosedListener("grinbox".to_string()))?
+                    }
+                }
+                _ => Err(ErrorKind::HttpRequest.into()),
+            };
+
+            let slate = slate?;
+            cli_message!(
+                "{}",
+                tr!(
+                    "invoice_sent",
+                    slate.id.to_string().bright_green(),
+                    common::amount_to_string(slate.amount, false).bright_green(),
+                    common::amount_units(),
+                    display_to.unwrap().bright_green()
+                )
+            );
+        }
+        Some("restore") => {
+            *out_is_safe = false;
+            if keybase_broker.is_some() || grinbox_broker.is_some() {
+                return Err(ErrorKind::HasListener.into());
+            }
+            let args = matches.subcommand_matches("restore").unwrap();
+            let passphrase = match args.is_present("passphrase") {
+                true => password_prompt(args.value_of("passphrase")),
+                false => "".to_string(),
+            };
+            *out_is_safe = args.value_of("passphrase").is_none();
+            let force = args.is_present("force");
+
+            let existing = {
+                let mut w = wallet.lock();
+                w.init(config, "default", passphrase.as_str(), false, false, 24)?;
+                // the local transaction log holds addresses and notes the chain doesn't
+                w.tx_log_count()?
+            };
+            if existing > 0 && !force {
+                if !atty::is(atty::Stream::Stdin) {
+                    return Err(ErrorKind::RestoreHasTransactions(existing).into());
+                }
+                let question = format!(
+                    "{}: the wallet already has {} transaction(s). restore keeps them, but adds an entry for every output it can't match to one of them, so the same funds may be listed twice. continue?",
+                    "WARNING".bright_yellow(),
+                    existing
+                );
+                if !confirm_prompt(&question) {
+                    cli_message!("restore cancelled.");
+                    return Ok(());
+                }
+            }
+
+            println!("restoring... please wait as this could take a few minutes to complete.");
+            wallet.lock().restore_state()?;
+
+            derive_address_key(config, wallet, grinbox_broker)?;
+            if passphrase.is_empty() {
+                println!("{}: wallet with no passphrase.", "WARNING".bright_yellow());
+            }
+
+            cli_message!("wallet restoration done!");
+            return Ok(());
+        }
+        Some("recover") => {
+            *out_is_safe = false;
+            if keybase_broker.is_some() || grinbox_broker.is_some() {
+                return Err(ErrorKind::HasListener.into());
+            }
+            let args = matches.subcommand_matches("recover").unwrap();
+            let passphrase = match args.is_present("passphrase") {
+                true => password_prompt(args.value_of("passphrase")),
+                false => "".to_string(),
+            };
+            *out_is_safe = args.value_of("passphrase").is_none();
+
+            if let Some(words) = args.values_of("words") {
+                println!("recovering... please wait as this could take a few minutes to complete.");
+                let words: Vec<&str> = words.collect();
+                {
+                    let mut w = wallet.lock();
+                    w.restore_seed(config, &words, passphrase.as_str())?;
+                    w.init(config, "default", passphrase.as_str(), false, false, 24)?;
+                    w.restore_state()?;
+                }
+
+                derive_address_key(config, wallet, grinbox_broker)?;
+                if passphrase.is_empty() {
+                    println!("{}: wallet with no passphrase.", "WARNING".bright_yellow());
+                }
+
+                cli_message!("wallet restoration done!");
+                *out_is_safe = false;
+                return Ok(());
+            } else if args.is_present("display") {
+                let mut w = wallet.lock();
+                w.show_mnemonic(config, &passphrase)?;
+                return Ok(());
+            }
+        }
+        Some("seed") => {
+            *out_is_safe = false;
+            let args = matches.subcommand_matches("seed").unwrap();
+            if let Some(args) = args.subcommand_matches("export") {
+                let file = args.value_of("file").unwrap().replace("~", &home_dir);
+                let passphrase = match args.is_present("passphrase") {
+                    true => password_prompt(args.value_of("passphrase")),
+                    false => "".to_string(),
+                };
+                let password = match args.value_of("password") {
+                    Some(password) => password.to_string(),
+                    None => {
+                        let password = rpassword::prompt_password_stdout("backup password: ")?;
+                        let repeated = rpassword::prompt_password_stdout("repeat password: ")?;
+                        if password != repeated {
+                            return Err(ErrorKind::PasswordsDontMatch.into());
+                        }
+                        password
+                    }
+                };
+                *out_is_safe =
+                    args.value_of("passphrase").is_none() && args.value_of("password").is_none();
+                wallet.lock().export_seed(
+                    config,
+                    &passphrase,
+                    &file,
+                    &password,
+                    args.is_present("force"),
+                )?;
+                cli_message!(
+                    "seed backup written to [{}]. keep the password safe, the backup can't be opened without it.",
+                    file.bright_green()
+                );
+            } else if let Some(args) = args.subcommand_matches("import") {
+                if keybase_broker.is_some() || grinbox_broker.is_some() {
+                    return Err(ErrorKind::HasListener.into());
+                }
+                let file = args.value_of("file").unwrap().replace("~", &home_dir);
+                let passphrase = match args.is_present("passphrase") {
+                    true => password_prompt(args.value_of("passphrase")),
+                    false => "".to_string(),
+                };
+                let password = match args.value_of("password") {
+                    Some(password) => password.to_string(),
+                    None => rpassword::prompt_password_stdout("backup password: ")?,
+                };
+                *out_is_safe =
+                    args.value_of("passphrase").is_none() && args.value_of("password").is_none();
+
+                println!("recovering... please wait as this could take a few minutes to complete.");
+                {
+                    let mut w = wallet.lock();
+                    w.import_seed(config, &file, &password, passphrase.as_str())?;
+                    w.init(config, "default", passphrase.as_str(), false, false, 24)?;
+                    w.restore_state()?;
+                }
+
+                derive_address_key(config, wallet, grinbox_broker)?;
+                if passphrase.is_empty() {
+                    println!("{}: wallet with no passphrase.", "WARNING".bright_yellow());
+                }
+
+                cli_message!("wallet restoration done!");
+            }
+        }
+        Some("check") => {
+            if keybase_broker.is_some() || grinbox_broker.is_some() {
+                return Err(ErrorKind::HasListener.into());
+            }
+            println!("checking and repairing... please wait as this could take a few minutes to complete.");
+            let mut wallet = wallet.lock();
+            wallet.check_repair()?;
+            cli_message!("check and repair done!");
+        }
+        Some("export-proof") => {
+            let args = matches.subcommand_matches("export-proof").unwrap();
+            let input = args.value_of("file").unwrap();
+            let id = args.value_of("id").unwrap();
+            let id = id
+                .parse::<u32>()
+                .map_err(|_| ErrorKind::InvalidTxId(id.to_string()))?;
+            let w = wallet.lock();
+            let tx_proof = w.get_tx_proof(id)?;
+            match w.verify_tx_proof(&tx_proof) {
+                Ok((sender, receiver, amount, outputs, kernel)) => {
+                    let mut file = File::create(input.replace("~", &home_dir))?;
+                    file.write_all(serde_json::to_string(&tx_proof)?.as_bytes())?;
+                    println!("proof written to {}", input);
+                    proof_ok(sender, receiver, amount, outputs, kernel);
+                }
+                Err(_) => {
+                    cli_message!("unable to verify proof");
+                }
+            }
+        }
+        Some("import-outputs") => {
+            let args = matches.subcommand_matches("import-outputs").unwrap();
+            let input = args.value_of("file").unwrap();
+            let path = Path::new(&input.replace("~", &home_dir)).to_path_buf();
+            if !path.exists() {
+                return Err(ErrorKind::FileNotFound(input.to_string()).into());
+            }
+            let mut file = File::open(path)?;
+            let mut outputs = String::new();
+            file.read_to_string(&mut outputs)?;
+            let outputs: Vec<ExportedOutput> = serde_json::from_str(&outputs)?;
+            let (imported, rejected) = wallet.lock().import_outputs(outputs)?;
+            cli_message!("imported [{}] outputs", imported.to_string().bright_green());
+            if rejected > 0 {
+                cli_message!(
+                    "{}: rejected [{}] outputs that are not unspent on chain",
+                    "WARNING".bright_yellow(),
+                    rejected.to_string().bright_yellow()
+                );
+            }
+        }
+        Some("export-watch") => {
+            let args = matches.subcommand_matches("export-watch").unwrap();
+            let input = args.value_of("file").unwrap();
+            let watch_data = wallet.lock().export_watch()?;
+            let mut file = File::create(input.replace("~", &home_dir))?;
+            file.write_all(serde_json::to_string(&watch_data)?.as_bytes())?;
+            cli_message!(
+                "watch data for account [{}] written to {}",
+                watch_data.account.bright_green(),
+                input
+            );
+            cli_message!(
+                "{}: the file holds the rewind key of the wallet. anyone with it can find every output of the wallet on chain and see its value, in all accounts, but can't spend them or sign transactions. keep the file private.",
+                "WARNING".bright_yellow()
+            );
+        }
+        Some("verify-proof") => {
+            let args = matches.subcommand_matches("verify-proof").unwrap();
+            let input = args.value_of("file").unwrap();
+            let path = Path::new(&input.replace("~", &home_dir)).to_path_buf();
+            if !path.exists() {
+                return Err(ErrorKind::FileNotFound(input.to_string()).into());
+            }
+            let mut file = File::open(path)?;
+            let mut proof = String::new();
+            file.read_to_string(&mut proof)?;
+            let mut tx_proof: TxProof = serde_json::from_str(&proof)?;
+
+            let mut wallet = wallet.lock();
+            match wallet.verify_tx_proof(&tx_proof) {
+                Ok((sender, receiver, amount, outputs, kernel)) => {
+                    proof_ok(sender, receiver, amount, outputs, kernel);
+                }
+                Err(_) => {
+                    cli_message!("unable to verify proof");
+                }
+            }
+        }
+        Some("verify-proof-bundle") => {
+            let args = matches.subcommand_matches("verify-proof-bundle").unwrap();
+            let input = args.value_of("file").unwrap();
+            let path = Path::new(&input.replace("~", &home_dir)).to_path_buf();
+            if !path.exists() {
+                return Err(ErrorKind::FileNotFound(input.to_string()).into());
+            }
+            let mut file = File::open(path)?;
+            let mut bundle = String::new();
+            file.read_to_string(&mut bundle)?;
+            let bundle: ProofBundle = serde_json::from_str(&bundle)?;
+            let content = bundle.verify()?;
+
+            cli_message!("proof bundle is consistent");
+            println!("signed by: {}", bundle.signer.bright_green());
+            println!(
+                "transaction: [{}] {:?}",
+                content.tx_id.to_string().bright_green(),
+                content.tx_type
+            );
+            if let Some(slate_id) = content.slate_id {
+                println!("slate: {}", slate_id.to_string().bright_green());
+            }
+            if let Some(ref address) = content.address {
+                println!("counterparty: {}", address.bright_green());
+            }
+            println!(
+                "credited: {}, debited: {}, fee: {}",
+                common::amount_to_string(content.amount_credited, false).bright_green(),
+                common::amount_to_string(content.amount_debited, false).bright_green(),
+                common::amount_to_string(content.fee.unwrap_or(0), false).bright_green()
+            );
+            match content.kernel_excess {
+                Some(ref excess) => println!("kernel excess: {}", excess.bright_magenta()),
+                None => println!("kernel excess: {}", "unknown".bright_yellow()),
+            }
+            println!(
+                "payment proof: {}",
+                match content.proof.is_some() {
+                    true => "included".bright_green(),
+                    false => "not included".bright_yellow(),
+                }
+            );
+            println!("\n{}: the bundle only shows what the signing wallet recorded, check the kernel is on-chain before relying on it", "WARNING".bright_yellow());
+        }
+        Some(subcommand) => {
+            cli_message!(
+                "{}: subcommand `{}` not implemented!",
+                "ERROR".bright_red(),
+                subcommand.bright_green()
+            );
+        }
+        None => {}
+    };
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn data_path_override_leaves_the_config_file_alone() {
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("wallet713.toml");
+        let config_path = config_path.to_str().unwrap();
+        let chain = Some(ChainTypes::Floonet);
+        let matches = cli_app().get_matches_from(vec!["wallet713"]);
+        do_config(&matches, &chain, true, None, Some(config_path)).unwrap();
+        let before = std::fs::read_to_string(config_path).unwrap();
+
+        let matches = cli_app().get_matches_from(vec!["wallet713", "--data-path", "/elsewhere"]);
+        let config = do_config(&matches, &chain, true, None, Some(config_path)).unwrap();
+
+        assert_eq!(std::fs::read_to_string(config_path).unwrap(), before);
+        assert_ne!(config.wallet713_data_path, "/elsewhere");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,3 +1,7 @@
+mod app;
 mod parser;
+mod spinner;
 
+pub use self::app::run;
 pub use self::parser::Parser;
+pub use self::spinner::Spinner;
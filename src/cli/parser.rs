@@ -1,16 +1,83 @@
-use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
-use common::Result;
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, ErrorKind, SubCommand};
+use common::{self, Result};
 
 #[derive(Clone)]
 pub struct Parser {}
 
 impl<'a, 'b> Parser {
     pub fn parse(command: &str) -> Result<ArgMatches> {
-        let command = command.trim();
-        let matches = Parser::parser().get_matches_from_safe(command.split_whitespace())?;
+        let matches = Parser::parser().get_matches_from_safe(Parser::split(command)?)?;
         Ok(matches)
     }
 
+    /// Splits a command into its arguments the way a shell does. Whitespace separates
+    /// arguments except inside single or double quotes, and a backslash keeps the
+    /// character after it, so `txs note 3 "rent for march"` has four arguments and
+    /// `""` is an empty one.
+    pub fn split(command: &str) -> Result<Vec<String>> {
+        let mut args = vec![];
+        let mut arg = String::new();
+        // an argument can be empty if it was quoted, so track whether one was started
+        let mut in_arg = false;
+        let mut quote: Option<char> = None;
+        let mut chars = command.chars();
+        while let Some(c) = chars.next() {
+            match (quote, c) {
+                (Some('\''), '\'') => quote = None,
+                (Some('\''), c) => arg.push(c),
+                (Some('"'), '"') => quote = None,
+                (Some('"'), '\\') => match chars.next() {
+                    Some(next) if next == '"' || next == '\\' => arg.push(next),
+                    Some(next) => {
+                        arg.push('\\');
+                        arg.push(next);
+                    }
+                    None => return Err(common::ErrorKind::UnclosedQuote.into()),
+                },
+                (Some(_), c) => arg.push(c),
+                (None, '\'') | (None, '"') => {
+                    quote = Some(c);
+                    in_arg = true;
+                }
+                (None, '\\') => {
+                    if let Some(next) = chars.next() {
+                        arg.push(next);
+                    }
+                    in_arg = true;
+                }
+                (None, c) if c.is_whitespace() => {
+                    if in_arg {
+                        args.push(arg.clone());
+                        arg.clear();
+                        in_arg = false;
+                    }
+                }
+                (None, c) => {
+                    arg.push(c);
+                    in_arg = true;
+                }
+            }
+        }
+        if quote.is_some() {
+            return Err(common::ErrorKind::UnclosedQuote.into());
+        }
+        if in_arg {
+            args.push(arg);
+        }
+        Ok(args)
+    }
+
+    /// Returns the detailed usage of a subcommand, given its path (e.g. `txs show`)
+    pub fn help(subcommands: &[&str]) -> Result<String> {
+        let mut args = subcommands.to_vec();
+        args.push("--help");
+        match Parser::parser().get_matches_from_safe(args) {
+            Err(ref e) if e.kind == ErrorKind::HelpDisplayed => Ok(e.message.clone()),
+            Err(e) => Err(e.into()),
+            Ok(_) => Ok(String::new()),
+        }
+    }
+
     fn parser() -> App<'a, 'b> {
         App::new("")
             .setting(AppSettings::NoBinaryName)
@@ -18,6 +85,10 @@ impl<'a, 'b> Parser {
                 SubCommand::with_name("exit")
                     .about("exits wallet713 cli")
             )
+            .subcommand(
+                SubCommand::with_name("reload")
+                    .about("re-reads the config file and applies the settings that can change while running")
+            )
             .subcommand(
                 SubCommand::with_name("config")
                     .about("configures wallet713")
@@ -42,10 +113,28 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[node-secret] -s, --secret=<node-secret> 'the grin node api secret'")
                     )
+                    .subcommand(
+                        SubCommand::with_name("show")
+                            .about("displays the configuration in effect, including defaults")
+                    )
+                    .subcommand(
+                        SubCommand::with_name("check")
+                            .about("validates the configuration and reports each problem found")
+                    )
+                    .subcommand(
+                        SubCommand::with_name("verify-key")
+                            .about("derives the grinbox key from the seed again and checks your address belongs to it")
+                            .arg(
+                                Arg::from_usage("[address] -a, --address=<address> 'an address you expect to be yours, e.g. one recorded on another machine'")
+                            )
+                    )
             )
             .subcommand(
                 SubCommand::with_name("address")
                     .about("shows your current grinbox address")
+                    .arg(
+                        Arg::from_usage("[copy] --copy 'also copy the address to the clipboard'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("init")
@@ -54,6 +143,25 @@ impl<'a, 'b> Parser {
                         Arg::from_usage("[passphrase] -p, --passphrase=<passphrase> 'the passphrase to use'")
                             .min_values(0)
                     )
+                    .arg(
+                        Arg::from_usage("[watch] -w, --watch=<file> 'initialize a watch-only wallet from an `export-watch` file'")
+                    )
+                    .arg(
+                        Arg::from_usage("[force] --force 'initialize even if a wallet already exists in the data path'")
+                    )
+                    .arg(
+                        Arg::from_usage("[account] -a, --account=<account> 'the name of the primary account to create'")
+                    )
+                    .arg(
+                        Arg::from_usage("[words] --words=<words> 'the number of words of the seed mnemonic (12/24). Default: 24'")
+                    )
+                    .arg(
+                        Arg::from_usage("[strict] --strict 'refuse a weak passphrase instead of warning about it'")
+                    )
+                    .arg(
+                        Arg::from_usage("[no-password-check] --no-password-check 'skip the passphrase strength check'")
+                            .conflicts_with("strict")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("lock")
@@ -99,9 +207,42 @@ impl<'a, 'b> Parser {
                 SubCommand::with_name("accounts")
                     .about("lists available accounts")
             )
+            .subcommand(
+                SubCommand::with_name("xpub")
+                    .about("displays the root public key and the xpub of the active account")
+            )
+            .subcommand(
+                SubCommand::with_name("status")
+                    .about("displays the wallet, node and listener status")
+                    .arg(
+                        Arg::from_usage("[json] --json 'print the status as json, with an overall `healthy` flag for health checks'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("ping")
+                    .about("checks that a grinbox address or contact is listening, without sending a transaction")
+                    .arg(
+                        Arg::from_usage("<to> 'the grinbox address or @contact to ping'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("stats")
+                    .about("displays the message counters of the grinbox listener since it was started")
+            )
+            .subcommand(
+                SubCommand::with_name("whoami")
+                    .about("displays your account, address, node, network and balance at a glance")
+            )
             .subcommand(
                 SubCommand::with_name("info")
                     .about("displays wallet info")
+                    .arg(
+                        Arg::from_usage("[accounts] -a, --accounts 'show the balances of all accounts'")
+                    )
+                    .arg(
+                        Arg::from_usage("[cached] -c, --cached 'show the balance from the last refresh, without querying the node'")
+                            .conflicts_with("accounts")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("contacts")
@@ -116,6 +257,26 @@ impl<'a, 'b> Parser {
                                 Arg::from_usage("<address> 'the contact address'")
                             )
                     )
+                    .subcommand(
+                        SubCommand::with_name("set")
+                            .about("sets the defaults used when sending to a contact")
+                            .arg(
+                                Arg::from_usage("<name> 'the contact name'")
+                            )
+                            .arg(
+                                Arg::from_usage("[transport] --transport=<transport> '`file` to write slates for the contact to a file instead of sending them'")
+                            )
+                            .arg(
+                                Arg::from_usage("[strategy] -s, --strategy=<strategy> 'the input selection strategy (all/smallest)'")
+                            )
+                            .arg(
+                                Arg::from_usage("[min-confirmations] --min-confirmations=<min-confirmations> 'the confirmations a payment with the contact needs to be reported as confirmed'")
+                            )
+                            .arg(
+                                Arg::from_usage("[clear] --clear 'remove the stored defaults'")
+                                    .conflicts_with_all(&["transport", "strategy", "min-confirmations"])
+                            )
+                    )
                     .subcommand(
                         SubCommand::with_name("remove")
                             .about("removes an existing contact")
@@ -123,10 +284,101 @@ impl<'a, 'b> Parser {
                                 Arg::from_usage("<name> 'the contact name'")
                             )
                     )
+                    .subcommand(
+                        SubCommand::with_name("group")
+                            .about("manages groups of contacts")
+                            .subcommand(
+                                SubCommand::with_name("create")
+                                    .about("creates a new group")
+                                    .arg(
+                                        Arg::from_usage("<name> 'the group name'")
+                                    )
+                            )
+                            .subcommand(
+                                SubCommand::with_name("add")
+                                    .about("adds an existing contact to a group")
+                                    .arg(
+                                        Arg::from_usage("<group> 'the group name'")
+                                    )
+                                    .arg(
+                                        Arg::from_usage("<contact> 'the contact name'")
+                                    )
+                                    .arg(
+                                        Arg::from_usage("[weight] -w, --weight=<weight> 'the share of group payments for this contact. Default: 1'")
+                                    )
+                            )
+                            .subcommand(
+                                SubCommand::with_name("list")
+                                    .about("lists all groups")
+                            )
+                    )
             )
             .subcommand(
                 SubCommand::with_name("txs")
                     .about("displays transactions")
+                    .arg(
+                        Arg::from_usage("[include-pruned] --include-pruned 'also display how many cancelled transactions were pruned'")
+                    )
+                    .arg(
+                        Arg::from_usage("[absolute-time] --absolute-time 'display full timestamps instead of relative times'")
+                    )
+                    .subcommand(
+                        SubCommand::with_name("show")
+                            .about("displays the full details of a transaction")
+                            .arg(
+                                Arg::from_usage("<id> 'the transaction id, or its slate id'")
+                            )
+                            .arg(
+                                Arg::from_usage("[absolute-time] --absolute-time 'display full timestamps instead of relative times'")
+                            )
+                            .arg(
+                                Arg::from_usage("[log] --log 'also display the states the transaction went through'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("note")
+                            .about("sets the note of a transaction")
+                            .arg(
+                                Arg::from_usage("<id> 'the transaction id, or its slate id'")
+                            )
+                            .arg(
+                                Arg::from_usage("<text> 'the note, or an empty string to clear it'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("proof-bundle")
+                            .about("exports a signed bundle of everything known about a transaction, for disputes")
+                            .arg(
+                                Arg::from_usage("<id> 'the transaction id, or its slate id'")
+                            )
+                            .arg(
+                                Arg::from_usage("<file> 'the file to write to'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("resend")
+                            .about("resends the slate of a pending transaction to a new destination")
+                            .arg(
+                                Arg::from_usage("<id> 'the transaction id'")
+                            )
+                            .arg(
+                                Arg::from_usage("[to] -t, --to=<address> 'the address to send the slate to'")
+                            )
+                            .arg(
+                                Arg::from_usage("[file] -f, --file=<file> 'the file to store the slate in'")
+                            )
+                            .group(ArgGroup::with_name("destination")
+                                .args(&["to", "file"])
+                                .required(true)
+                            )
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("prune")
+                    .about("removes cancelled transactions older than a number of days")
+                    .arg(
+                        Arg::from_usage("[days] -d, --days=<days> 'the minimum age in days of the cancelled transactions to remove. Default: 30'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("outputs")
@@ -134,6 +386,12 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[show-spent] -s, --show-spent 'show spent outputs'")
                     )
+                    .arg(
+                        Arg::from_usage("[coinbase-only] --coinbase-only 'only show coinbase outputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[export] --export=<file> 'write the outputs to a file instead, as csv if it ends in .csv and json otherwise'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("listen")
@@ -144,6 +402,36 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[keybase] -k, --keybase 'start the keybase listener'")
                     )
+                    .arg(
+                        Arg::from_usage("[manual] -m, --manual 'queue received slates until they are accepted or rejected'")
+                    )
+                    .arg(
+                        Arg::from_usage("[http] --http=<address> 'serve the foreign api on this address, e.g. 0.0.0.0:13415'")
+                    )
+                    .arg(
+                        Arg::from_usage("[retry] -r, --retry 'keep trying to reach the grinbox relay in the background instead of failing'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("accept")
+                    .about("processes a slate queued by a manual listener")
+                    .arg(
+                        Arg::from_usage("<id> 'the slate id'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("reject")
+                    .about("discards a slate queued by a manual listener")
+                    .arg(
+                        Arg::from_usage("<id> 'the slate id'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("pending")
+                    .about("lists the slates queued by a manual listener")
+                    .arg(
+                        Arg::from_usage("[absolute-time] --absolute-time 'display full timestamps instead of relative times'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("stop")
@@ -164,25 +452,94 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("[file] -f, --file=<file> 'the file to store the slate in'")
                     )
+                    .arg(
+                        Arg::from_usage("[encrypt-slate] --encrypt-slate=<passphrase> 'encrypt the slate file with a passphrase shared with the recipient'")
+                            .min_values(0)
+                    )
                     .group(ArgGroup::with_name("destination")
                         .args(&["to", "file"])
-                        .required(true)
                     )
                     .arg(
-                        Arg::from_usage("<amount> 'the amount of grins to send'")
+                        Arg::from_usage("[amount] 'the amount of grins to send, or - to read `<amount> <address>` lines from stdin'")
+                    )
+                    .arg(
+                        Arg::from_usage("[percent] --percent=<percent> 'send this percentage (1-100) of the spendable balance, less the fee'")
+                    )
+                    .arg(
+                        Arg::from_usage("[fiat] --fiat=<amount> 'send the grin value of a fiat amount like 10.00usd, at the rate given by price_api_url'")
+                    )
+                    .group(ArgGroup::with_name("amount-source")
+                        .args(&["amount", "percent", "fiat"])
+                        .required(true)
                     )
                     .arg(
                         Arg::from_usage("[strategy] -s, --strategy=<strategy> 'the input selection strategy (all/smallest). Default: smallest'")
                     )
                     .arg(
-                        Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for inputs'")
+                        Arg::from_usage("[confirmations] -c, --confirmations=<confirmations> 'the number of confirmations required for inputs. Default: spend_confirmations from the config, or 10'")
+                            .alias("min-confirmations")
                     )
                     .arg(
                         Arg::from_usage("[change-outputs] -o, --change-outputs=<change-outputs> 'the number of change outputs'")
                     )
+                    .arg(
+                        Arg::from_usage("[max-outputs] --max-outputs=<count> 'the most outputs to select as inputs, defaults to max_selectable_outputs'")
+                    )
+                    .arg(
+                        Arg::from_usage("[use-output] -u, --use-output=<commitment> 'spend exactly this output, can be given several times'")
+                            .multiple(true)
+                            .number_of_values(1)
+                    )
+                    .arg(
+                        Arg::from_usage("[lock-height] --lock-height=<height> 'the block height before which the transaction can not be included in the chain'")
+                    )
                     .arg(
                         Arg::from_usage("[message] -g, --message=<message> 'the message to include in the tx'")
                     )
+                    .arg(
+                        Arg::from_usage("[fluff] --fluff 'fluff the transaction to the network immediately instead of using dandelion'")
+                    )
+                    .arg(
+                        Arg::from_usage("[allow-high-fee] --allow-high-fee 'allow a fee above the max_fee cap'")
+                    )
+                    .arg(
+                        Arg::from_usage("[expires-in] --expires-in=<minutes> 'cancel the send while listening if it is not finalized within this many minutes'")
+                    )
+                    .arg(
+                        Arg::from_usage("[allow-unconfirmed-change] --allow-unconfirmed-change 'allow spending change of a send that is not confirmed yet'")
+                    )
+                    .arg(
+                        Arg::from_usage("[json] --json 'also print the slate id, amount, fee and number of inputs of each send as json'")
+                    )
+                    .arg(
+                        Arg::from_usage("[change-account] --change-account=<name> 'the account to send the change to, instead of the spending account'")
+                    )
+                    .arg(
+                        Arg::from_usage("[yes] -y, --yes 'send right away, without the countdown to cancel it'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("export-signing-key")
+                    .about("moves the signing key of a send that wasn't finalized yet out of the wallet, into a file")
+                    .arg(
+                        Arg::from_usage("-i, --id=<id> 'the transaction id'")
+                    )
+                    .arg(
+                        Arg::from_usage("-o, --out=<file> 'the file to store the signing key in'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("sign-slate")
+                    .about("signs a slate with a key kept outside of the wallet")
+                    .arg(
+                        Arg::from_usage("-i, --in=<file> 'the slate to sign'")
+                    )
+                    .arg(
+                        Arg::from_usage("-k, --key=<file> 'the file holding the signing key'")
+                    )
+                    .arg(
+                        Arg::from_usage("-o, --out=<file> 'the file to store the signed slate in'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("invoice")
@@ -201,14 +558,31 @@ impl<'a, 'b> Parser {
                 SubCommand::with_name("repost")
                     .about("reposts an existing transaction.")
                     .arg(
-                        Arg::from_usage("-i, --id=<id> 'the transaction id'")
+                        Arg::from_usage("-i, --id=<id> 'the transaction id, or its slate id'")
+                    )
+                    .arg(
+                        Arg::from_usage("[fluff] --fluff 'fluff the transaction to the network immediately instead of using dandelion'")
+                    )
+                    .arg(
+                        Arg::from_usage("[allow-high-fee] --allow-high-fee 'allow a fee above the max_fee cap'")
+                    )
+                    .arg(
+                        Arg::from_usage("[dump] --dump=<file> 'write the transaction to a file for broadcasting elsewhere, instead of posting it to the node'")
+                            .conflicts_with_all(&["fluff", "allow-high-fee"])
                     )
             )
             .subcommand(
                 SubCommand::with_name("cancel")
                     .about("cancels an existing transaction.")
                     .arg(
-                        Arg::from_usage("-i, --id=<id> 'the transaction id'")
+                        Arg::from_usage("[id] -i, --id=<id> 'the transaction id, or its slate id'")
+                    )
+                    .arg(
+                        Arg::from_usage("[to] -t, --to=<address> 'cancel every pending transaction with this address or @contact'")
+                    )
+                    .group(ArgGroup::with_name("transactions")
+                        .args(&["id", "to"])
+                        .required(true)
                     )
             )
             .subcommand(
@@ -218,6 +592,9 @@ impl<'a, 'b> Parser {
                         Arg::from_usage("[passphrase] -p, --passphrase=<passphrase> 'the passphrase to use'")
                             .min_values(0)
                     )
+                    .arg(
+                        Arg::from_usage("[force] --force 'restore without asking, even if the wallet already has transactions'")
+                    )
             )
             .subcommand(
                 SubCommand::with_name("recover")
@@ -238,12 +615,51 @@ impl<'a, 'b> Parser {
                     )
 
             )
+            .subcommand(
+                SubCommand::with_name("seed")
+                    .about("backs up the wallet seed to an encrypted file, or recovers it from one")
+                    .subcommand(
+                        SubCommand::with_name("export")
+                            .about("writes the seed, encrypted with a password, to a file")
+                            .arg(
+                                Arg::from_usage("<file> 'the backup file to write'")
+                            )
+                            .arg(
+                                Arg::from_usage("[passphrase] -p, --passphrase=<passphrase> 'the passphrase of the wallet'")
+                                    .min_values(0)
+                            )
+                            .arg(
+                                Arg::from_usage("[password] --password=<password> 'the password to encrypt the backup with, prompted for if not given'")
+                            )
+                            .arg(
+                                Arg::from_usage("[force] --force 'overwrite the file if it already exists'")
+                            )
+                    )
+                    .subcommand(
+                        SubCommand::with_name("import")
+                            .about("recovers the wallet from a seed backup file")
+                            .arg(
+                                Arg::from_usage("<file> 'the backup file to read'")
+                            )
+                            .arg(
+                                Arg::from_usage("[passphrase] -p, --passphrase=<passphrase> 'the passphrase to protect the recovered wallet with'")
+                                    .min_values(0)
+                            )
+                            .arg(
+                                Arg::from_usage("[password] --password=<password> 'the password of the backup, prompted for if not given'")
+                            )
+                    )
+            )
             .subcommand(
                 SubCommand::with_name("receive")
                     .about("receives a sender initiated slate from file and produces signed slate")
                     .arg(
                         Arg::from_usage("-f, --file=<file> 'the slate file'")
                     )
+                    .arg(
+                        Arg::from_usage("[decrypt-slate] --decrypt-slate=<passphrase> 'open an encrypted slate file, the response is encrypted the same way'")
+                            .min_values(0)
+                    )
             )
             .subcommand(
                 SubCommand::with_name("finalize")
@@ -251,6 +667,10 @@ impl<'a, 'b> Parser {
                     .arg(
                         Arg::from_usage("-f, --file=<file> 'the slate file'")
                     )
+                    .arg(
+                        Arg::from_usage("[decrypt-slate] --decrypt-slate=<passphrase> 'open an encrypted slate response file'")
+                            .min_values(0)
+                    )
             )
             .subcommand(
                 SubCommand::with_name("check")
@@ -266,6 +686,20 @@ impl<'a, 'b> Parser {
                         Arg::from_usage("-f, --file=<file> 'the file to write to'")
                     )
             )
+            .subcommand(
+                SubCommand::with_name("export-watch")
+                    .about("exports the data needed to watch the account balance from another machine, including the rewind key of the wallet")
+                    .arg(
+                        Arg::from_usage("<file> 'the file to write to. keep it private, the rewind key shows every output of the wallet and its value, though it can't spend them'")
+                    )
+            )
+            .subcommand(
+                SubCommand::with_name("import-outputs")
+                    .about("imports outputs from a backup file, keeping only the ones unspent on chain")
+                    .arg(
+                        Arg::from_usage("<file> 'the file to read from'")
+                    )
+            )
             .subcommand(
                 SubCommand::with_name("verify-proof")
                     .about("verifies a transaction proof")
@@ -273,5 +707,77 @@ impl<'a, 'b> Parser {
                         Arg::from_usage("-f, --file=<file> 'the file to read from'")
                     )
             )
+            .subcommand(
+                SubCommand::with_name("verify-proof-bundle")
+                    .about("checks that a proof bundle is signed and consistent")
+                    .arg(
+                        Arg::from_usage("<file> 'the file to read from'")
+                    )
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_like_a_shell() {
+        let split = |command: &str| Parser::split(command).unwrap();
+        assert_eq!(
+            split("  send 10   --to @alice "),
+            vec!["send", "10", "--to", "@alice"]
+        );
+        assert_eq!(
+            split(r#"txs note 3 "rent for march""#),
+            vec!["txs", "note", "3", "rent for march"]
+        );
+        assert_eq!(split(r#"txs note 3 """#), vec!["txs", "note", "3", ""]);
+        assert_eq!(split("send '1 000.5'"), vec!["send", "1 000.5"]);
+        assert_eq!(split(r#"-g "say \"hi\"""#), vec!["-g", r#"say "hi""#]);
+        assert_eq!(split(r"a\ b c"), vec!["a b", "c"]);
+        assert_eq!(split(r#"ab"c d"e"#), vec!["abc de"]);
+    }
+
+    #[test]
+    fn refuses_unclosed_quotes() {
+        for command in &[r#"txs note 3 "rent"#, "send 'ten", r#"-g "ends with \"#] {
+            let err = Parser::split(command).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<common::ErrorKind>(),
+                Some(&common::ErrorKind::UnclosedQuote)
+            );
+        }
+    }
+
+    #[test]
+    fn parses_a_quoted_note() {
+        let matches = Parser::parse(r#"txs note 3 "rent for march""#).unwrap();
+        let note = matches
+            .subcommand_matches("txs")
+            .and_then(|txs| txs.subcommand_matches("note"))
+            .unwrap();
+        assert_eq!(note.value_of("text"), Some("rent for march"));
+    }
+
+    #[test]
+    fn parses_a_quoted_message() {
+        let matches = Parser::parse(r#"send 10 --to @alice -g "thanks for lunch""#).unwrap();
+        let send = matches.subcommand_matches("send").unwrap();
+        assert_eq!(send.value_of("message"), Some("thanks for lunch"));
+    }
+
+    #[test]
+    fn parses_a_quoted_amount_with_spaces() {
+        let matches = Parser::parse(r#"send "1 000.5" --to @alice"#).unwrap();
+        let amount = matches
+            .subcommand_matches("send")
+            .unwrap()
+            .value_of("amount");
+        assert_eq!(amount, Some("1 000.5"));
+        assert_eq!(
+            common::amount_from_string(amount.unwrap()).unwrap(),
+            1_000_500_000_000
+        );
     }
 }
@@ -0,0 +1,62 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use common::is_cli;
+
+const FRAMES: &[char] = &['|', '/', '-', '\\'];
+const FRAME_INTERVAL_MS: u64 = 100;
+
+/// Activity indicator shown while a blocking call is in flight.
+/// The spinner is cleared as soon as it goes out of scope.
+pub struct Spinner {
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    pub fn start(message: &str) -> Self {
+        let running = Arc::new(AtomicBool::new(false));
+        if !Spinner::enabled() {
+            return Self {
+                running,
+                handle: None,
+            };
+        }
+
+        running.store(true, Ordering::SeqCst);
+        let cloned_running = running.clone();
+        let message = message.to_string();
+        let handle = thread::spawn(move || {
+            let mut frame = 0;
+            while cloned_running.load(Ordering::SeqCst) {
+                print!("\r{} {}", FRAMES[frame % FRAMES.len()], message);
+                io::stdout().flush().ok();
+                frame += 1;
+                thread::sleep(Duration::from_millis(FRAME_INTERVAL_MS));
+            }
+            print!("\r{}\r", " ".repeat(message.len() + 2));
+            io::stdout().flush().ok();
+        });
+
+        Self {
+            running,
+            handle: Some(handle),
+        }
+    }
+
+    fn enabled() -> bool {
+        unsafe { is_cli() && atty::is(atty::Stream::Stdout) }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            handle.join().ok();
+        }
+    }
+}
@@ -180,3 +180,59 @@ fn double_sha256(payload: &[u8]) -> Vec<u8> {
     let hash = hasher.result();
     hash.to_vec()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VECTORS: &'static [(&'static [u8], &'static str)] = &[
+        (b"", ""),
+        (b"a", "2g"),
+        (b"bbb", "a3gV"),
+        (b"ccc", "aPEr"),
+        (b"hello world", "StV1DL6CwTryKyV"),
+        (b"simply a long string", "2cFupjhnEsSn59qHXstmK2ffpLv2"),
+        (&[0, 0, 0x28, 0x7f, 0xb4, 0xcd], "11233QC4"),
+    ];
+
+    #[test]
+    fn encodes_and_decodes_vectors() {
+        for &(bytes, encoded) in VECTORS {
+            assert_eq!(bytes.to_base58(), encoded);
+            assert_eq!(encoded.from_base58().unwrap(), bytes.to_vec());
+        }
+    }
+
+    #[test]
+    fn encodes_and_decodes_check() {
+        let hash = [
+            0xeb, 0x15, 0x23, 0x1d, 0xfc, 0xeb, 0x60, 0x92, 0x58, 0x86, 0xb6, 0x7d, 0x06, 0x52,
+            0x99, 0x92, 0x59, 0x15, 0xae, 0xb1,
+        ];
+        let encoded = "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L";
+        assert_eq!(hash.to_base58_check(vec![0]), encoded);
+        let (version, payload) = encoded.from_base58_check(1).unwrap();
+        assert_eq!(version, vec![0]);
+        assert_eq!(payload, hash.to_vec());
+    }
+
+    #[test]
+    fn rejects_bad_checksum() {
+        let err = "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9M"
+            .from_base58_check(1)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::InvalidBase58Checksum)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_character() {
+        let err = "StV1DL0CwTryKyV".from_base58().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::InvalidBase58Character('0', 6))
+        );
+    }
+}
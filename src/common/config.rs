@@ -1,17 +1,36 @@
 use std::fmt;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{Read, Write};
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
 use grin_core::global::ChainTypes;
 use grin_wallet::WalletConfig;
+use url::Url;
 
 use common::crypto::{public_key_from_secret_key, PublicKey, SecretKey};
+use common::time::{MAX_SPAN_MINUTES, MINUTES_PER_DAY};
 use common::{ErrorKind, Result};
-use contacts::{GrinboxAddress, DEFAULT_GRINBOX_PORT};
+use contacts::{Address, GrinboxAddress, DEFAULT_GRINBOX_PORT};
 
 const WALLET713_HOME: &str = ".wallet713";
 const WALLET713_DEFAULT_CONFIG_FILENAME: &str = "wallet713.toml";
+const DEFAULT_MAX_MESSAGE_BYTES: usize = 10 * 1024 * 1024;
+const DEFAULT_CONFIRMATION_POLL_INTERVAL_SECS: u64 = 60;
+const DEFAULT_ACCOUNT: &str = "default";
+const DEFAULT_LANGUAGE: &str = "en";
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_COLOR_MODE: &str = "auto";
+const DEFAULT_SPEND_CONFIRMATIONS: u64 = 10;
+const DEFAULT_MAX_SELECTABLE_OUTPUTS: usize = 500;
+const DEFAULT_PENDING_SLATE_TTL_HOURS: u64 = 24;
+/// Ten years, far beyond any use but still a valid duration
+const MAX_PENDING_SLATE_TTL_HOURS: u64 = 87_600;
+const DEFAULT_SEND_COUNTDOWN_SECONDS: u64 = 5;
+const DEFAULT_FEE_BUMP_AFTER_MINUTES: u64 = 60;
+const DEFAULT_RESTORE_MAX_RETRIES: u32 = 5;
+const DEFAULT_STORAGE_BACKEND: &str = "lmdb";
+const DEFAULT_CONTACT_SLATE_DIR: &str = "slates";
 
 const DEFAULT_CONFIG: &str = r#"
 	wallet713_data_path = "wallet713_data"
@@ -27,12 +46,41 @@ pub struct Wallet713Config {
     pub grinbox_port: Option<u16>,
     pub grinbox_protocol_unsecure: Option<bool>,
     pub grinbox_address_index: Option<u32>,
+    pub grinbox_expected_address: Option<String>,
+    pub default_account: Option<String>,
+    pub max_message_bytes: Option<usize>,
     pub grin_node_uri: Option<String>,
+    pub grin_node_uris: Option<Vec<String>>,
     pub grin_node_secret: Option<String>,
+    pub grin_node_secret_file: Option<String>,
+    pub timeout: Option<u64>,
     pub grinbox_listener_auto_start: Option<bool>,
     pub keybase_listener_auto_start: Option<bool>,
+    pub auto_listen: Option<bool>,
+    pub passphrase_file: Option<String>,
     pub max_auto_accept_invoice: Option<u64>,
+    /// Read from `fee_per_output` too, the name it had before
+    #[serde(alias = "fee_per_output")]
+    pub fee_per_weight: Option<u64>,
+    pub min_fee: Option<u64>,
+    pub max_fee: Option<u64>,
+    pub receive_webhook_url: Option<String>,
+    pub price_api_url: Option<String>,
+    pub confirmation_poll_interval: Option<u64>,
+    pub prune_cancelled_after_days: Option<u64>,
+    pub prompt: Option<String>,
+    pub use_color: Option<String>,
+    pub language: Option<String>,
+    pub spend_confirmations: Option<u64>,
+    pub max_selectable_outputs: Option<usize>,
+    pub pending_slate_ttl_hours: Option<u64>,
+    pub send_countdown_seconds: Option<u64>,
+    pub auto_fee_bump: Option<bool>,
+    pub fee_bump_after_minutes: Option<u64>,
+    pub restore_max_retries: Option<u32>,
+    pub storage_backend: Option<String>,
     pub default_keybase_ttl: Option<String>,
+    pub contact_slate_dir: Option<String>,
     pub owner_api: Option<bool>,
     pub owner_api_address: Option<String>,
     pub owner_api_secret: Option<String>,
@@ -40,9 +88,18 @@ pub struct Wallet713Config {
     pub foreign_api: Option<bool>,
     pub foreign_api_address: Option<String>,
     pub foreign_api_secret: Option<String>,
+    pub checkout_api_token: Option<String>,
+    pub metrics_bind: Option<String>,
+    pub metrics_api_secret: Option<String>,
     #[serde(skip)]
     pub config_home: Option<String>,
     #[serde(skip)]
+    pub data_path_override: Option<String>,
+    #[serde(skip)]
+    pub color_override: Option<String>,
+    #[serde(skip)]
+    pub timeout_override: Option<u64>,
+    #[serde(skip)]
     pub grinbox_address_key: Option<SecretKey>,
 }
 
@@ -64,11 +121,53 @@ impl Wallet713Config {
         let mut file = File::open(config_path)?;
         let mut toml_str = String::new();
         file.read_to_string(&mut toml_str)?;
-        let mut config: Wallet713Config = toml::from_str(&toml_str[..])?;
+        let mut config: Wallet713Config = match toml::from_str(&toml_str[..]) {
+            Ok(config) => config,
+            Err(e) => {
+                // a save that was cut short leaves the previous config in the backup
+                let backup_path = Wallet713Config::backup_path(config_path);
+                let config = Wallet713Config::read(&backup_path).map_err(|_| e)?;
+                cli_message!(
+                    "warning: could not parse the config at {}, using the backup at {}",
+                    config_path,
+                    backup_path
+                );
+                config
+            }
+        };
         config.config_home = Some(config_path.to_string());
         Ok(config)
     }
 
+    fn read(config_path: &str) -> Result<Wallet713Config> {
+        let mut file = File::open(config_path)?;
+        let mut toml_str = String::new();
+        file.read_to_string(&mut toml_str)?;
+        Ok(toml::from_str(&toml_str[..])?)
+    }
+
+    fn backup_path(config_path: &str) -> String {
+        format!("{}.bak", config_path)
+    }
+
+    fn temp_path(config_path: &str) -> String {
+        format!("{}.tmp", config_path)
+    }
+
+    /// Removes the half written config a crash during `to_file` leaves behind, returning
+    /// its path if there was one
+    pub fn remove_temp_file(&self) -> Result<Option<PathBuf>> {
+        let temp_path = match self.config_home {
+            Some(ref config_path) => PathBuf::from(Wallet713Config::temp_path(config_path)),
+            None => return Ok(None),
+        };
+        if !temp_path.is_file() {
+            return Ok(None);
+        }
+        fs::remove_file(&temp_path)?;
+        Ok(Some(temp_path))
+    }
+
     pub fn default_config_path(chain: &Option<ChainTypes>) -> Result<PathBuf> {
         let mut path = Wallet713Config::default_home_path(chain)?;
         path.push(WALLET713_DEFAULT_CONFIG_FILENAME);
@@ -101,8 +200,19 @@ impl Wallet713Config {
         let default_path = default_path_buf.to_str().unwrap();
         let config_path = config_path.unwrap_or(default_path);
         let toml_str = toml::to_string(&self)?;
-        let mut f = File::create(config_path)?;
-        f.write_all(toml_str.as_bytes())?;
+
+        // write the new config next to the old one and swap it in with a rename, so
+        // a crash mid-write can't leave a truncated config behind
+        let temp_path = Wallet713Config::temp_path(config_path);
+        {
+            let mut f = File::create(&temp_path)?;
+            f.write_all(toml_str.as_bytes())?;
+            f.sync_all()?;
+        }
+        if Wallet713Config::read(config_path).is_ok() {
+            fs::copy(config_path, Wallet713Config::backup_path(config_path))?;
+        }
+        fs::rename(&temp_path, config_path)?;
         self.config_home = Some(config_path.to_string());
         Ok(())
     }
@@ -113,7 +223,11 @@ impl Wallet713Config {
         let mut wallet_config = WalletConfig::default();
         wallet_config.chain_type = self.chain.clone();
         wallet_config.data_file_dir = data_path.to_string();
-        wallet_config.check_node_api_http_addr = self.grin_node_uri().clone();
+        wallet_config.check_node_api_http_addr = self
+            .grin_node_uris()
+            .first()
+            .cloned()
+            .ok_or(ErrorKind::NoNodeUri)?;
         Ok(wallet_config)
     }
 
@@ -125,6 +239,109 @@ impl Wallet713Config {
         self.grinbox_address_index.unwrap_or(0)
     }
 
+    pub fn default_account(&self) -> &str {
+        self.default_account
+            .as_ref()
+            .map(|x| &**x)
+            .unwrap_or(DEFAULT_ACCOUNT)
+    }
+
+    /// The language of the messages: `en` or `es`
+    pub fn language(&self) -> &str {
+        self.language
+            .as_ref()
+            .map(|language| language.as_str())
+            .unwrap_or(DEFAULT_LANGUAGE)
+    }
+
+    /// Whether to color output: `auto`, `always` or `never`
+    pub fn color_mode(&self) -> &str {
+        self.color_override
+            .as_ref()
+            .or(self.use_color.as_ref())
+            .map(|x| &**x)
+            .unwrap_or(DEFAULT_COLOR_MODE)
+    }
+
+    /// Confirmations an output needs before it can be selected as a transaction input
+    pub fn spend_confirmations(&self) -> u64 {
+        self.spend_confirmations
+            .unwrap_or(DEFAULT_SPEND_CONFIRMATIONS)
+    }
+
+    /// The most outputs a send may select as inputs, a larger send needs a consolidation first
+    pub fn max_selectable_outputs(&self) -> usize {
+        self.max_selectable_outputs
+            .unwrap_or(DEFAULT_MAX_SELECTABLE_OUTPUTS)
+    }
+
+    pub fn pending_slate_ttl_hours(&self) -> u64 {
+        std::cmp::min(
+            self.pending_slate_ttl_hours
+                .unwrap_or(DEFAULT_PENDING_SLATE_TTL_HOURS),
+            MAX_PENDING_SLATE_TTL_HOURS,
+        )
+    }
+
+    /// Seconds a send waits for a key press that stops it, 0 sends right away
+    pub fn send_countdown_seconds(&self) -> u64 {
+        self.send_countdown_seconds
+            .unwrap_or(DEFAULT_SEND_COUNTDOWN_SECONDS)
+    }
+
+    /// Whether the grinbox listener rebuilds sends that are stuck unconfirmed with a higher fee
+    pub fn auto_fee_bump(&self) -> bool {
+        self.auto_fee_bump.unwrap_or(false)
+    }
+
+    /// Minutes a finalized send may stay unconfirmed before its fee is bumped
+    pub fn fee_bump_after_minutes(&self) -> u64 {
+        self.fee_bump_after_minutes
+            .unwrap_or(DEFAULT_FEE_BUMP_AFTER_MINUTES)
+    }
+
+    /// Times `restore` retries a batch the node failed to return before it gives up
+    pub fn restore_max_retries(&self) -> u32 {
+        self.restore_max_retries
+            .unwrap_or(DEFAULT_RESTORE_MAX_RETRIES)
+    }
+
+    /// Where wallet data is kept: `lmdb` on disk, or `memory` until the wallet exits
+    pub fn storage_backend(&self) -> &str {
+        self.storage_backend
+            .as_ref()
+            .map(|x| &**x)
+            .unwrap_or(DEFAULT_STORAGE_BACKEND)
+    }
+
+    /// Where `send` stores the slates of contacts with the `file` transport, a
+    /// `slates` directory in the data path unless configured
+    pub fn contact_slate_dir(&self) -> Result<PathBuf> {
+        match self.contact_slate_dir {
+            Some(ref dir) => Ok(PathBuf::from(dir)),
+            None => Ok(self.get_data_path()?.join(DEFAULT_CONTACT_SLATE_DIR)),
+        }
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout_override
+            .or(self.timeout)
+            .unwrap_or(DEFAULT_TIMEOUT_SECS)
+    }
+
+    /// Seconds between checks for confirmed outgoing transactions while listening
+    pub fn confirmation_poll_interval(&self) -> u64 {
+        std::cmp::max(
+            1,
+            self.confirmation_poll_interval
+                .unwrap_or(DEFAULT_CONFIRMATION_POLL_INTERVAL_SECS),
+        )
+    }
+
+    pub fn max_message_bytes(&self) -> usize {
+        self.max_message_bytes.unwrap_or(DEFAULT_MAX_MESSAGE_BYTES)
+    }
+
     pub fn get_grinbox_address(&self) -> Result<GrinboxAddress> {
         let public_key = self.get_grinbox_public_key()?;
         Ok(GrinboxAddress::new(
@@ -144,6 +361,11 @@ impl Wallet713Config {
     }
 
     pub fn get_data_path(&self) -> Result<PathBuf> {
+        // a data path given for this run only is used as is, never persisted
+        if let Some(ref data_path) = self.data_path_override {
+            return Ok(PathBuf::from(data_path));
+        }
+
         let mut data_path = PathBuf::new();
         data_path.push(self.wallet713_data_path.clone());
         if data_path.is_absolute() {
@@ -169,6 +391,23 @@ impl Wallet713Config {
         })
     }
 
+    /// The primary node uri(s) followed by any configured fallback nodes, in the
+    /// order they should be tried
+    pub fn grin_node_uris(&self) -> Vec<String> {
+        let mut uris: Vec<String> = self
+            .grin_node_uri()
+            .split(',')
+            .map(|uri| uri.trim().to_string())
+            .filter(|uri| !uri.is_empty())
+            .collect();
+        for uri in self.grin_node_uris.clone().unwrap_or(vec![]) {
+            if !uris.contains(&uri) {
+                uris.push(uri);
+            }
+        }
+        uris
+    }
+
     pub fn grin_node_secret(&self) -> Option<String> {
         let chain_type = self.chain.as_ref().unwrap_or(&ChainTypes::Mainnet);
         match self.grin_node_uri {
@@ -202,6 +441,11 @@ impl Wallet713Config {
             })
     }
 
+    /// Whether to start listening on grinbox as soon as the wallet starts
+    pub fn auto_listen(&self) -> bool {
+        self.auto_listen.unwrap_or(false)
+    }
+
     pub fn owner_api(&self) -> bool {
         self.owner_api.unwrap_or(false)
     }
@@ -209,6 +453,299 @@ impl Wallet713Config {
     pub fn foreign_api(&self) -> bool {
         self.foreign_api.unwrap_or(false)
     }
+
+    /// The settings in effect for this run, with defaults filled in and secrets hidden.
+    pub fn effective_settings(&self) -> Vec<(&'static str, String)> {
+        let data_path = self
+            .get_data_path()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or(self.wallet713_data_path.clone());
+        let hidden = |secret: &Option<String>| match secret {
+            Some(_) => String::from("{...}"),
+            None => String::from("none"),
+        };
+        vec![
+            (
+                "config_file",
+                self.config_home.clone().unwrap_or(String::from("none")),
+            ),
+            (
+                "chain",
+                match self.chain {
+                    Some(ChainTypes::Floonet) => String::from("floonet"),
+                    _ => String::from("mainnet"),
+                },
+            ),
+            ("wallet713_data_path", data_path),
+            ("default_account", self.default_account().to_string()),
+            ("grinbox_domain", self.grinbox_domain.clone()),
+            (
+                "grinbox_port",
+                self.grinbox_port
+                    .unwrap_or(DEFAULT_GRINBOX_PORT)
+                    .to_string(),
+            ),
+            (
+                "grinbox_protocol_unsecure",
+                self.grinbox_protocol_unsecure().to_string(),
+            ),
+            (
+                "grinbox_address_index",
+                self.grinbox_address_index().to_string(),
+            ),
+            (
+                "grinbox_expected_address",
+                self.grinbox_expected_address
+                    .clone()
+                    .unwrap_or(String::from("none")),
+            ),
+            ("grin_node_uris", self.grin_node_uris().join(", ")),
+            ("grin_node_secret", hidden(&self.grin_node_secret())),
+            (
+                "grin_node_secret_file",
+                self.grin_node_secret_file
+                    .clone()
+                    .unwrap_or(String::from("none")),
+            ),
+            ("timeout", format!("{}s", self.timeout())),
+            ("auto_listen", self.auto_listen().to_string()),
+            (
+                "passphrase_file",
+                self.passphrase_file.clone().unwrap_or(String::from("none")),
+            ),
+            ("storage_backend", self.storage_backend().to_string()),
+            ("use_color", self.color_mode().to_string()),
+            ("language", self.language().to_string()),
+            (
+                "spend_confirmations",
+                self.spend_confirmations().to_string(),
+            ),
+            (
+                "max_selectable_outputs",
+                self.max_selectable_outputs().to_string(),
+            ),
+            (
+                "pending_slate_ttl_hours",
+                format!("{}h", self.pending_slate_ttl_hours()),
+            ),
+            (
+                "send_countdown_seconds",
+                format!("{}s", self.send_countdown_seconds()),
+            ),
+            ("auto_fee_bump", self.auto_fee_bump().to_string()),
+            (
+                "fee_bump_after_minutes",
+                format!("{}m", self.fee_bump_after_minutes()),
+            ),
+            (
+                "restore_max_retries",
+                self.restore_max_retries().to_string(),
+            ),
+            (
+                "confirmation_poll_interval",
+                format!("{}s", self.confirmation_poll_interval()),
+            ),
+            ("owner_api", self.owner_api().to_string()),
+            ("owner_api_address", self.owner_api_address()),
+            ("owner_api_secret", hidden(&self.owner_api_secret)),
+            ("foreign_api", self.foreign_api().to_string()),
+            ("foreign_api_address", self.foreign_api_address()),
+            ("foreign_api_secret", hidden(&self.foreign_api_secret)),
+            ("checkout_api_token", hidden(&self.checkout_api_token)),
+            (
+                "metrics_bind",
+                self.metrics_bind.clone().unwrap_or(String::from("none")),
+            ),
+            ("metrics_api_secret", hidden(&self.metrics_api_secret)),
+            (
+                "receive_webhook_url",
+                self.receive_webhook_url
+                    .clone()
+                    .unwrap_or(String::from("none")),
+            ),
+            (
+                "price_api_url",
+                self.price_api_url.clone().unwrap_or(String::from("none")),
+            ),
+        ]
+    }
+
+    /// Checks that the uri, address and key settings are well-formed, returning
+    /// a description of each problem found.
+    pub fn check(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        match self.language() {
+            "en" | "es" => {}
+            language => problems.push(format!("language: `{}` is not one of en or es", language)),
+        }
+
+        match self.storage_backend() {
+            "lmdb" | "memory" => {}
+            backend => problems.push(format!(
+                "storage_backend: `{}` is not one of lmdb or memory",
+                backend
+            )),
+        }
+
+        match self.color_mode() {
+            "auto" | "always" | "never" => {}
+            mode => problems.push(format!(
+                "use_color: `{}` is not one of auto, always or never",
+                mode
+            )),
+        }
+
+        if self.grinbox_domain.trim().is_empty()
+            || self.grinbox_domain.contains(char::is_whitespace)
+        {
+            problems.push(format!(
+                "grinbox_domain: `{}` is not a valid domain",
+                self.grinbox_domain
+            ));
+        }
+
+        if self.max_selectable_outputs == Some(0) {
+            problems.push(String::from(
+                "max_selectable_outputs: at least one output must be selectable",
+            ));
+        }
+
+        if let Some(hours) = self.pending_slate_ttl_hours {
+            if hours > MAX_PENDING_SLATE_TTL_HOURS {
+                problems.push(format!(
+                    "pending_slate_ttl_hours: {} is more than the maximum of {}",
+                    hours, MAX_PENDING_SLATE_TTL_HOURS
+                ));
+            }
+        }
+
+        if self.fee_bump_after_minutes == Some(0) {
+            problems.push(String::from(
+                "fee_bump_after_minutes: a send has to be given some time to confirm",
+            ));
+        }
+
+        if let Some(minutes) = self.fee_bump_after_minutes {
+            if minutes > MAX_SPAN_MINUTES {
+                problems.push(format!(
+                    "fee_bump_after_minutes: {} is more than the maximum of {}",
+                    minutes, MAX_SPAN_MINUTES
+                ));
+            }
+        }
+
+        if let Some(days) = self.prune_cancelled_after_days {
+            if days > MAX_SPAN_MINUTES / MINUTES_PER_DAY {
+                problems.push(format!(
+                    "prune_cancelled_after_days: {} is more than the maximum of {}",
+                    days,
+                    MAX_SPAN_MINUTES / MINUTES_PER_DAY
+                ));
+            }
+        }
+
+        if self.grinbox_port == Some(0) {
+            problems.push(String::from("grinbox_port: port 0 is not valid"));
+        }
+
+        if let Some(ref address) = self.grinbox_expected_address {
+            if GrinboxAddress::from_str(address).is_err() {
+                problems.push(format!(
+                    "grinbox_expected_address: `{}` is not a grinbox address",
+                    address
+                ));
+            }
+        }
+
+        let uris = self.grin_node_uris();
+        if uris.is_empty() {
+            problems.push(String::from("grin_node_uri: no node uri is configured"));
+        }
+        for uri in uris {
+            match Url::parse(&uri) {
+                Ok(ref url) if url.scheme() == "http" || url.scheme() == "https" => {}
+                _ => problems.push(format!(
+                    "grin_node_uri: `{}` is not a valid http(s) uri",
+                    uri
+                )),
+            }
+        }
+
+        if let Some(ref secret) = self.grin_node_secret {
+            if secret.trim().is_empty() {
+                problems.push(String::from("grin_node_secret: the secret is empty"));
+            }
+        }
+
+        if let Some(ref path) = self.grin_node_secret_file {
+            if !Path::new(path).is_file() {
+                problems.push(format!("grin_node_secret_file: `{}` does not exist", path));
+            }
+        }
+
+        if let Some(ref path) = self.passphrase_file {
+            if !Path::new(path).is_file() {
+                problems.push(format!("passphrase_file: `{}` does not exist", path));
+            }
+        }
+
+        if let Some(ref url) = self.receive_webhook_url {
+            if Url::parse(url).is_err() {
+                problems.push(format!("receive_webhook_url: `{}` is not a valid url", url));
+            }
+        }
+
+        if let Some(ref url) = self.price_api_url {
+            if Url::parse(&url.replace("{currency}", "usd")).is_err() {
+                problems.push(format!("price_api_url: `{}` is not a valid url", url));
+            }
+        }
+
+        if self.owner_api() {
+            if self.owner_api_address().parse::<SocketAddr>().is_err() {
+                problems.push(format!(
+                    "owner_api_address: `{}` is not a valid socket address",
+                    self.owner_api_address()
+                ));
+            }
+            if self.owner_api_secret.is_none() {
+                problems.push(String::from(
+                    "owner_api_secret: the owner api is enabled without a secret",
+                ));
+            }
+        }
+
+        if self.foreign_api() && self.foreign_api_address().parse::<SocketAddr>().is_err() {
+            problems.push(format!(
+                "foreign_api_address: `{}` is not a valid socket address",
+                self.foreign_api_address()
+            ));
+        }
+
+        if let Some(ref token) = self.checkout_api_token {
+            if token.trim().is_empty() {
+                problems.push(String::from("checkout_api_token: the token is empty"));
+            }
+        }
+
+        if let Some(ref address) = self.metrics_bind {
+            if address.parse::<SocketAddr>().is_err() {
+                problems.push(format!(
+                    "metrics_bind: `{}` is not a valid socket address",
+                    address
+                ));
+            }
+            match self.metrics_api_secret {
+                Some(ref secret) if !secret.trim().is_empty() => {}
+                _ => problems.push(String::from(
+                    "metrics_api_secret: the metrics endpoint is enabled without a secret",
+                )),
+            }
+        }
+
+        problems
+    }
 }
 
 impl fmt::Display for Wallet713Config {
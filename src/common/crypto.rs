@@ -1,7 +1,12 @@
+use grin_core::global::is_mainnet;
 pub use grin_util::secp::key::{PublicKey, SecretKey};
 use grin_util::secp::pedersen::Commitment;
 pub use grin_util::secp::{Message, Secp256k1, Signature};
 
+use rand::thread_rng;
+use rand::Rng;
+use ring::{aead, digest, pbkdf2};
+
 use super::base58::{FromBase58, ToBase58};
 use common::{ErrorKind, Result};
 use sha2::{Digest, Sha256};
@@ -10,6 +15,52 @@ use std::fmt::Write;
 pub const GRINBOX_ADDRESS_VERSION_MAINNET: [u8; 2] = [1, 11];
 pub const GRINBOX_ADDRESS_VERSION_TESTNET: [u8; 2] = [1, 120];
 
+/// PBKDF2 rounds for keys derived from a passphrase, slowing down guessing it
+const PASSPHRASE_KDF_ITERATIONS: u32 = 100_000;
+
+/// The base58 version bytes of grinbox addresses on the chain we run on
+pub fn grinbox_address_version() -> Vec<u8> {
+    if is_mainnet() {
+        GRINBOX_ADDRESS_VERSION_MAINNET.to_vec()
+    } else {
+        GRINBOX_ADDRESS_VERSION_TESTNET.to_vec()
+    }
+}
+
+/// Fails when a base58check grinbox key carries the version bytes of the other network
+pub fn check_address_network(key: &str) -> Result<()> {
+    let version = match key.from_base58_check(GRINBOX_ADDRESS_VERSION_MAINNET.len()) {
+        Ok((version, _)) => version,
+        // not ours to judge, key parsing reports malformed input
+        Err(_) => return Ok(()),
+    };
+    let network = if version == GRINBOX_ADDRESS_VERSION_MAINNET {
+        "mainnet"
+    } else if version == GRINBOX_ADDRESS_VERSION_TESTNET {
+        "floonet"
+    } else {
+        return Ok(());
+    };
+    let ours = if is_mainnet() { "mainnet" } else { "floonet" };
+    if network != ours {
+        Err(ErrorKind::NetworkMismatch(
+            key.to_string(),
+            network.to_string(),
+            ours.to_string(),
+        ))?;
+    }
+    Ok(())
+}
+
+/// Parses a public key given either as hex or as a base58check grinbox key
+pub fn parse_public_key(key: &str) -> Result<PublicKey> {
+    let is_hex = key.len() == 66 && key.chars().all(|c| c.is_ascii_hexdigit());
+    match is_hex {
+        true => PublicKey::from_hex(key),
+        false => PublicKey::from_base58_check(key, grinbox_address_version()),
+    }
+}
+
 pub trait Hex<T> {
     fn from_hex(str: &str) -> Result<T>;
     fn to_hex(&self) -> String;
@@ -162,3 +213,130 @@ fn split_n(s: &str, n: usize) -> Vec<&str> {
         .map(|i| &s[2 * i..2 * i + n])
         .collect()
 }
+
+/// Data encrypted with a key derived from a passphrase with PBKDF2-HMAC-SHA512. The
+/// ChaCha20-Poly1305 tag makes a wrong passphrase or a tampered file fail to open.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PassphraseEncrypted {
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl PassphraseEncrypted {
+    pub fn seal(data: &[u8], passphrase: &str) -> Result<Self> {
+        let salt: [u8; 16] = thread_rng().gen();
+        let nonce: [u8; 12] = thread_rng().gen();
+        let key = passphrase_key(passphrase, &salt, PASSPHRASE_KDF_ITERATIONS);
+
+        let mut enc_bytes = data.to_vec();
+        let suffix_len = aead::CHACHA20_POLY1305.tag_len();
+        for _ in 0..suffix_len {
+            enc_bytes.push(0);
+        }
+        let sealing_key = aead::SealingKey::new(&aead::CHACHA20_POLY1305, &key)
+            .map_err(|_| ErrorKind::Encryption)?;
+        aead::seal_in_place(&sealing_key, &nonce, &[], &mut enc_bytes, suffix_len)
+            .map_err(|_| ErrorKind::Encryption)?;
+
+        Ok(Self {
+            iterations: PASSPHRASE_KDF_ITERATIONS,
+            salt: to_hex(salt.to_vec()),
+            nonce: to_hex(nonce.to_vec()),
+            ciphertext: to_hex(enc_bytes),
+        })
+    }
+
+    pub fn open(&self, passphrase: &str) -> Result<Vec<u8>> {
+        let salt = from_hex(self.salt.clone()).map_err(|_| ErrorKind::Decryption)?;
+        let nonce = from_hex(self.nonce.clone()).map_err(|_| ErrorKind::Decryption)?;
+        let mut enc_bytes = from_hex(self.ciphertext.clone()).map_err(|_| ErrorKind::Decryption)?;
+        let key = passphrase_key(passphrase, &salt, self.iterations);
+
+        let opening_key = aead::OpeningKey::new(&aead::CHACHA20_POLY1305, &key)
+            .map_err(|_| ErrorKind::Decryption)?;
+        let data = aead::open_in_place(&opening_key, &nonce, &[], 0, &mut enc_bytes)
+            .map_err(|_| ErrorKind::WrongBackupPassword)?;
+        Ok(data.to_vec())
+    }
+}
+
+fn passphrase_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0; 32];
+    pbkdf2::derive(
+        &digest::SHA512,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERATOR_HEX: &'static str =
+        "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn secret_key_one() -> SecretKey {
+        SecretKey::from_hex("0000000000000000000000000000000000000000000000000000000000000001")
+            .unwrap()
+    }
+
+    #[test]
+    fn derives_public_key() {
+        let public_key = public_key_from_secret_key(&secret_key_one()).unwrap();
+        assert_eq!(public_key.to_hex(), GENERATOR_HEX);
+        assert_eq!(PublicKey::from_hex(GENERATOR_HEX).unwrap(), public_key);
+    }
+
+    #[test]
+    fn round_trips_base58_check_key() {
+        let public_key = PublicKey::from_hex(GENERATOR_HEX).unwrap();
+        let version = GRINBOX_ADDRESS_VERSION_MAINNET.to_vec();
+        let encoded = public_key.to_base58_check(version.clone());
+        assert_eq!(
+            PublicKey::from_base58_check(&encoded, version).unwrap(),
+            public_key
+        );
+        let err = PublicKey::from_base58_check(&encoded, GRINBOX_ADDRESS_VERSION_TESTNET.to_vec())
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::InvalidBase58Version)
+        );
+    }
+
+    #[test]
+    fn signs_and_verifies_challenge() {
+        let secret_key = secret_key_one();
+        let public_key = public_key_from_secret_key(&secret_key).unwrap();
+        let signature = sign_challenge("challenge", &secret_key).unwrap();
+        let signature = Signature::from_hex(&signature.to_hex()).unwrap();
+        assert!(verify_signature("challenge", &signature, &public_key).is_ok());
+        assert!(verify_signature("other challenge", &signature, &public_key).is_err());
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(from_hex("00ff7f".to_string()).unwrap(), vec![0, 255, 127]);
+        assert_eq!(from_hex("0x0a".to_string()).unwrap(), vec![10]);
+        assert_eq!(to_hex(vec![0, 255, 127]), "00ff7f");
+        assert!(from_hex("abc".to_string()).is_err());
+        assert!(from_hex("zz".to_string()).is_err());
+    }
+
+    #[test]
+    fn opens_only_with_the_passphrase() {
+        let sealed = PassphraseEncrypted::seal(b"secret", "passphrase").unwrap();
+        assert_eq!(sealed.open("passphrase").unwrap(), b"secret".to_vec());
+        let err = sealed.open("wrong").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::WrongBackupPassword)
+        );
+    }
+}
@@ -1,6 +1,8 @@
-use failure::Fail;
+use failure::{Error, Fail};
 use grin_wallet::libwallet;
 
+use super::{strip_colors, use_color};
+
 #[derive(Clone, Eq, PartialEq, Debug, Fail)]
 pub enum ErrorKind {
     #[fail(display = "\x1b[31;1merror:\x1b[0m secp error")]
@@ -15,6 +17,11 @@ pub enum ErrorKind {
     DeriveKeychainError,
     #[fail(display = "\x1b[31;1merror:\x1b[0m wallet should be empty before attempting restore!")]
     WalletShouldBeEmpty,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the wallet already has {} transaction(s)! restore keeps them, but adds an entry for every output it can't match to one of them. use `restore --force` to go ahead",
+        0
+    )]
+    RestoreHasTransactions(usize),
     #[fail(
         display = "\x1b[31;1merror:\x1b[0m transaction with slate id {} already received!",
         0
@@ -30,6 +37,10 @@ pub enum ErrorKind {
         0
     )]
     TransactionNotCancellable(String),
+    #[fail(display = "\x1b[31;1merror:\x1b[0m no pending slate with id {}!", 0)]
+    PendingSlateNotFound(String),
+    #[fail(display = "\x1b[31;1merror:\x1b[0m invalid slate id: {}", 0)]
+    InvalidSlateId(String),
     #[fail(
         display = "\x1b[31;1merror:\x1b[0m transaction cancellation error: {}",
         _0
@@ -49,6 +60,28 @@ pub enum ErrorKind {
         needed: u64,
         needed_disp: String,
     },
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the fee of {} is above the cap of {}! check your fee settings, or pass `--allow-high-fee` if this is intended.",
+        computed_disp, cap_disp
+    )]
+    FeeTooHigh {
+        computed: u64,
+        computed_disp: String,
+        cap: u64,
+        cap_disp: String,
+    },
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m this send needs {} outputs but at most {} may be selected! consolidate your outputs by sending to yourself first, or raise the limit with `--max-outputs`.",
+        needed, max
+    )]
+    TooManyOutputs { needed: usize, max: usize },
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m {} of the selected outputs are change of a send that isn't confirmed yet! wait for it to confirm, or pass `--allow-unconfirmed-change` to spend it anyway.",
+        0
+    )]
+    UnconfirmedChange(usize),
+    #[fail(display = "\x1b[31;1merror:\x1b[0m send cancelled, nothing was sent.")]
+    SendCancelled,
     #[fail(
         display = "\x1b[31;1merror:\x1b[0m Account label {} already exists!",
         0
@@ -61,6 +94,42 @@ pub enum ErrorKind {
     InvalidTxId(String),
     #[fail(display = "\x1b[31;1merror:\x1b[0m invalid amount given: `{}`", 0)]
     InvalidAmount(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m amount too large, it is more than the wallet can represent"
+    )]
+    AmountOverflow,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m ambiguous amount given: `{}`, use a dot for decimals and only spaces between groups of thousands",
+        0
+    )]
+    AmbiguousAmount(String),
+    #[fail(display = "\x1b[31;1merror:\x1b[0m a quote in the command is never closed!")]
+    UnclosedQuote,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m invalid percentage given: `{}`, use a whole number from 1 to 100",
+        0
+    )]
+    InvalidPercent(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m invalid fiat amount given: `{}`, use an amount followed by a currency code, like 10.00usd",
+        0
+    )]
+    InvalidFiatAmount(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m no price source configured, set price_api_url to send fiat amounts"
+    )]
+    MissingPriceApiUrl,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not get the grin price: {}, nothing was sent",
+        0
+    )]
+    PriceFetchFailed(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m no destination given, use --to or --file, or - as the amount to read recipients from stdin"
+    )]
+    MissingDestination,
+    #[fail(display = "\x1b[31;1merror:\x1b[0m {} of {} sends failed!", 0, 1)]
+    SendsFailed(usize, usize),
     #[fail(
         display = "\x1b[31;1merror:\x1b[0m invalid selection strategy, use either 'smallest' or 'all'"
     )]
@@ -75,12 +144,52 @@ pub enum ErrorKind {
         0
     )]
     InvalidNumOutputs(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m no output with commitment `{}` in this account!",
+        0
+    )]
+    OutputNotFound(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m output `{}` is not available for spending!",
+        0
+    )]
+    OutputNotSpendable(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the key does not belong to any participant of the slate!"
+    )]
+    KeyNotInSlate,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the signing key was used already, its nonce can't sign again!"
+    )]
+    SigningKeyUsed,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the signing key of this slate was exported already!"
+    )]
+    SigningKeyExported,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the slate has to be signed with its external key first!"
+    )]
+    SlateNotSigned,
+    #[fail(display = "\x1b[31;1merror:\x1b[0m invalid lock height: {}", 0)]
+    InvalidLockHeight(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m lock height {} must be above the current chain height {}!",
+        0, 1
+    )]
+    LockHeightTooLow(u64, u64),
+    #[fail(display = "\x1b[31;1merror:\x1b[0m invalid fee override: {}", 0)]
+    InvalidFeeOverride(String),
     #[fail(
         display = "\x1b[31;1merror:\x1b[0m could not unlock wallet! are you using the correct passphrase?"
     )]
     WalletUnlockFailed,
     #[fail(display = "\x1b[31;1merror:\x1b[0m could not open wallet! use `unlock` or `init`.")]
     NoWallet,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the wallet data in {} is in use by another wallet713 instance (pid {})! if none is running, start with `--force-unlock`",
+        0, 1
+    )]
+    WalletLocked(String, String),
     #[fail(
         display = "\x1b[31;1merror:\x1b[0m {} listener is closed! consider using `listen` first.",
         0
@@ -101,6 +210,26 @@ pub enum ErrorKind {
         0
     )]
     ContactNotFound(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m unsupported transport `{}`, only 'file' can be set for a contact",
+        0
+    )]
+    InvalidContactTransport(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m group named `{}` already exists!",
+        0
+    )]
+    ContactGroupAlreadyExists(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not find group named `{}`!",
+        0
+    )]
+    ContactGroupNotFound(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m group `{}` has no members to send to!",
+        0
+    )]
+    ContactGroupEmpty(String),
     #[fail(display = "\x1b[31;1merror:\x1b[0m invalid character!")]
     InvalidBase58Character(char, usize),
     #[fail(display = "\x1b[31;1merror:\x1b[0m invalid length!")]
@@ -109,10 +238,45 @@ pub enum ErrorKind {
     InvalidBase58Checksum,
     #[fail(display = "\x1b[31;1merror:\x1b[0m invalid network!")]
     InvalidBase58Version,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m address `{}` is for {} but this wallet runs on {}!",
+        0, 1, 2
+    )]
+    NetworkMismatch(String, String, String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m a seed mnemonic has either 12 or 24 words, not {}!",
+        0
+    )]
+    InvalidSeedWords(usize),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m unknown color mode `{}`, use either 'auto', 'always' or 'never'",
+        0
+    )]
+    InvalidColorMode(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m found {} problem(s) in the configuration, it was not reloaded!",
+        0
+    )]
+    InvalidConfig(usize),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m unknown units `{}`, use either 'grin' or 'nano'",
+        0
+    )]
+    InvalidAmountUnits(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m unknown language `{}`, use either 'en' or 'es'",
+        0
+    )]
+    InvalidLanguage(String),
     #[fail(display = "\x1b[31;1merror:\x1b[0m invalid key!")]
     InvalidBase58Key,
     #[fail(display = "\x1b[31;1merror:\x1b[0m could not parse number from string!")]
     NumberParsingError,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m {} minutes is longer than the ten years allowed!",
+        0
+    )]
+    SpanTooLong(u64),
     #[fail(display = "\x1b[31;1merror:\x1b[0m unknown address type `{}`!", 0)]
     UnknownAddressType(String),
     #[fail(
@@ -120,6 +284,11 @@ pub enum ErrorKind {
         0
     )]
     GrinboxAddressParsingError(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m `{}` is neither a hex public key nor a grinbox address for this network!",
+        0
+    )]
+    InvalidAddress(String),
     #[fail(
         display = "\x1b[31;1merror:\x1b[0m could not parse `{}` to a keybase address!",
         0
@@ -138,6 +307,11 @@ pub enum ErrorKind {
     GrinWalletVerifySlateMessagesError,
     #[fail(display = "\x1b[31;1merror:\x1b[0m failed finalizing slate!")]
     GrinWalletFinalizeError,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m slate `{}` has already been finalized!",
+        0
+    )]
+    AlreadyFinalized(String),
     #[fail(display = "\x1b[31;1merror:\x1b[0m failed posting transaction!")]
     GrinWalletPostError,
     #[fail(
@@ -146,6 +320,41 @@ pub enum ErrorKind {
     KeybaseNotFound,
     #[fail(display = "\x1b[31;1merror:\x1b[0m grinbox websocket terminated unexpectedly!")]
     GrinboxWebsocketAbnormalTermination,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m grinbox rejected the challenge signature while your clock is {} seconds off from the relay! please check your system clock.",
+        0
+    )]
+    ClockSkew(i64),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m grinbox relay rejected the subscription ({}). check that your grinbox address was derived from this wallet's seed, or run `address` to derive it again.",
+        0
+    )]
+    GrinboxAuthFailed(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not connect to the grinbox relay at {}! check your network connection and the `grinbox_domain` and `grinbox_port` settings.",
+        0
+    )]
+    GrinboxConnectionRefused(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not resolve the grinbox relay domain `{}`! check your network connection and the `grinbox_domain` setting.",
+        0
+    )]
+    GrinboxDnsFailure(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the TLS handshake with the grinbox relay at {} failed ({})! check your system's certificates, and that `grinbox_protocol_unsecure` matches the relay.",
+        0, 1
+    )]
+    GrinboxTlsError(String, String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m unexpected response from the grinbox relay ({}). your wallet713 version may not be compatible with the relay, consider upgrading.",
+        0
+    )]
+    GrinboxProtocolError(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m recipient [{}] appears to be offline and the grinbox relay did not keep the slate! the transaction is still pending, deliver it later with `txs resend` or unlock its outputs with `cancel`.",
+        0
+    )]
+    RecipientOffline(String),
     #[fail(
         display = "\x1b[31;1merror:\x1b[0m rejecting invoice as auto invoice acceptance is turned off!"
     )]
@@ -161,22 +370,279 @@ pub enum ErrorKind {
     HasListener,
     #[fail(display = "\x1b[31;1merror:\x1b[0m wallet already unlocked")]
     WalletAlreadyUnlocked,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m a wallet already exists in this data path! use `unlock`, or `init --force` if you really want to initialize it again"
+    )]
+    WalletExists,
     #[fail(display = "\x1b[31;1merror:\x1b[0m unable to encrypt message")]
     Encryption,
     #[fail(display = "\x1b[31;1merror:\x1b[0m unable to decrypt message")]
     Decryption,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not decrypt the backup! the password is wrong or the file was modified"
+    )]
+    WrongBackupPassword,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m the address {} does not belong to the key derived from your seed, which gives {}! check `grinbox_address_index` in your config",
+        0, 1
+    )]
+    AddressKeyMismatch(String, String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m {} is encrypted! pass `--decrypt-slate` to open it",
+        0
+    )]
+    SlateFileEncrypted(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not decrypt the slate! the passphrase is wrong or the file was modified"
+    )]
+    WrongSlatePassphrase,
+    #[fail(display = "\x1b[31;1merror:\x1b[0m the passwords don't match!")]
+    PasswordsDontMatch,
+    #[fail(display = "\x1b[31;1merror:\x1b[0m the passphrase is weak, {}!", 0)]
+    WeakPassphrase(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m {} already exists! use `--force` to overwrite it",
+        0
+    )]
+    FileExists(String),
     #[fail(display = "\x1b[31;1merror:\x1b[0m restore error")]
     Restore,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m wallet data at {} is corrupted! move it aside and use `recover` or `restore` to rebuild the wallet from your seed.",
+        0
+    )]
+    CorruptWallet(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m unknown storage backend `{}`, use either 'lmdb' or 'memory'",
+        0
+    )]
+    InvalidStorageBackend(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m restore interrupted! progress was saved, run `restore` again to resume"
+    )]
+    RestoreInterrupted,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m restore stopped, the node failed {} retries in a row! progress was saved, run `restore` again to resume",
+        0
+    )]
+    RestoreNodeFailed(u32),
     #[fail(display = "\x1b[31;1merror:\x1b[0m unknown account: {}", 0)]
     UnknownAccountLabel(String),
     #[fail(display = "\x1b[31;1merror:\x1b[0m http request error")]
     HttpRequest,
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m `{}` is not a valid listen address, use `<ip>:<port>`",
+        0
+    )]
+    InvalidListenAddress(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not listen on {}: {}. is the port already in use?",
+        0, 1
+    )]
+    HttpListenerBind(String, String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m request timed out after {} seconds!",
+        0
+    )]
+    Timeout(u64),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m [{}] did not answer the ping within {} seconds, it may not be listening",
+        0, 1
+    )]
+    PingTimeout(String, u64),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m only grinbox addresses can be pinged, `{}` is not one",
+        0
+    )]
+    PingRequiresGrinbox(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not read the node secret from {}!",
+        0
+    )]
+    NodeSecretFile(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not read the passphrase from {}!",
+        0
+    )]
+    PassphraseFile(String),
+    #[fail(display = "\x1b[31;1merror:\x1b[0m no node uri is configured!")]
+    NoNodeUri,
     #[fail(display = "Node API error")]
     Node,
     #[fail(display = "{}", 0)]
     GenericError(String),
     #[fail(display = "\x1b[31;1merror:\x1b[0m unable to verify proof")]
     VerifyProof,
+    #[fail(display = "\x1b[31;1merror:\x1b[0m invalid proof bundle: {}", 0)]
+    InvalidProofBundle(String),
     #[fail(display = "\x1b[31;1merror:\x1b[0m file '{}' not found", 0)]
     FileNotFound(String),
+    #[fail(display = "\x1b[31;1merror:\x1b[0m file '{}' exists already", 0)]
+    FileExists(String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m could not write to '{}': {}. make sure the disk is not full or read-only!",
+        _0, _1
+    )]
+    StorageUnwritable(String, String),
+    #[fail(
+        display = "\x1b[31;1merror:\x1b[0m no cached balance yet, run `info` once to create it!"
+    )]
+    NoCachedBalance,
+}
+
+const ERROR_PREFIX: &str = "\x1b[31;1merror:\x1b[0m ";
+
+impl ErrorKind {
+    /// A stable code identifying this error, so users can search for it and scripts can
+    /// match on it without parsing the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::Secp => "E_SECP",
+            ErrorKind::ModelNotFound => "E_MODEL_NOT_FOUND",
+            ErrorKind::WalletSeedCouldNotBeOpened => "E_WALLET_SEED_COULD_NOT_BE_OPENED",
+            ErrorKind::OpenWalletError => "E_OPEN_WALLET",
+            ErrorKind::DeriveKeychainError => "E_DERIVE_KEYCHAIN",
+            ErrorKind::WalletShouldBeEmpty => "E_WALLET_SHOULD_BE_EMPTY",
+            ErrorKind::RestoreHasTransactions(..) => "E_RESTORE_HAS_TRANSACTIONS",
+            ErrorKind::TransactionAlreadyReceived(..) => "E_TRANSACTION_ALREADY_RECEIVED",
+            ErrorKind::TransactionDoesntExist(..) => "E_TRANSACTION_DOESNT_EXIST",
+            ErrorKind::FeeTooHigh { .. } => "E_FEE_TOO_HIGH",
+            ErrorKind::TransactionNotCancellable(..) => "E_TRANSACTION_NOT_CANCELLABLE",
+            ErrorKind::PendingSlateNotFound(..) => "E_PENDING_SLATE_NOT_FOUND",
+            ErrorKind::InvalidSlateId(..) => "E_INVALID_SLATE_ID",
+            ErrorKind::TransactionCancellationError(..) => "E_TRANSACTION_CANCELLATION",
+            ErrorKind::TransactionHasNoProof => "E_TRANSACTION_HAS_NO_PROOF",
+            ErrorKind::LibTX(..) => "E_LIBTX",
+            ErrorKind::NotEnoughFunds { .. } => "E_INSUFFICIENT_FUNDS",
+            ErrorKind::TooManyOutputs { .. } => "E_TOO_MANY_OUTPUTS",
+            ErrorKind::UnconfirmedChange(..) => "E_UNCONFIRMED_CHANGE",
+            ErrorKind::SendCancelled => "E_SEND_CANCELLED",
+            ErrorKind::AccountLabelAlreadyExists(..) => "E_ACCOUNT_LABEL_ALREADY_EXISTS",
+            ErrorKind::InvalidTxId(..) => "E_INVALID_TX_ID",
+            ErrorKind::InvalidAmount(..) => "E_INVALID_AMOUNT",
+            ErrorKind::AmountOverflow => "E_AMOUNT_OVERFLOW",
+            ErrorKind::AmbiguousAmount(..) => "E_AMBIGUOUS_AMOUNT",
+            ErrorKind::UnclosedQuote => "E_UNCLOSED_QUOTE",
+            ErrorKind::InvalidPercent(..) => "E_INVALID_PERCENT",
+            ErrorKind::InvalidFiatAmount(..) => "E_INVALID_FIAT_AMOUNT",
+            ErrorKind::MissingPriceApiUrl => "E_MISSING_PRICE_API_URL",
+            ErrorKind::PriceFetchFailed(..) => "E_PRICE_FETCH_FAILED",
+            ErrorKind::MissingDestination => "E_MISSING_DESTINATION",
+            ErrorKind::SendsFailed(..) => "E_SENDS_FAILED",
+            ErrorKind::InvalidStrategy => "E_INVALID_STRATEGY",
+            ErrorKind::InvalidMinConfirmations(..) => "E_INVALID_MIN_CONFIRMATIONS",
+            ErrorKind::InvalidNumOutputs(..) => "E_INVALID_NUM_OUTPUTS",
+            ErrorKind::OutputNotFound(..) => "E_OUTPUT_NOT_FOUND",
+            ErrorKind::OutputNotSpendable(..) => "E_OUTPUT_NOT_SPENDABLE",
+            ErrorKind::KeyNotInSlate => "E_KEY_NOT_IN_SLATE",
+            ErrorKind::SigningKeyUsed => "E_SIGNING_KEY_USED",
+            ErrorKind::SigningKeyExported => "E_SIGNING_KEY_EXPORTED",
+            ErrorKind::SlateNotSigned => "E_SLATE_NOT_SIGNED",
+            ErrorKind::InvalidLockHeight(..) => "E_INVALID_LOCK_HEIGHT",
+            ErrorKind::LockHeightTooLow(..) => "E_LOCK_HEIGHT_TOO_LOW",
+            ErrorKind::InvalidFeeOverride(..) => "E_INVALID_FEE_OVERRIDE",
+            ErrorKind::WalletUnlockFailed => "E_WALLET_UNLOCK_FAILED",
+            ErrorKind::NoWallet => "E_NO_WALLET",
+            ErrorKind::WalletLocked(..) => "E_WALLET_LOCKED",
+            ErrorKind::ClosedListener(..) => "E_CLOSED_LISTENER",
+            ErrorKind::AlreadyListening(..) => "E_ALREADY_LISTENING",
+            ErrorKind::ContactAlreadyExists(..) => "E_CONTACT_ALREADY_EXISTS",
+            ErrorKind::ContactNotFound(..) => "E_CONTACT_NOT_FOUND",
+            ErrorKind::InvalidContactTransport(..) => "E_INVALID_CONTACT_TRANSPORT",
+            ErrorKind::ContactGroupAlreadyExists(..) => "E_CONTACT_GROUP_ALREADY_EXISTS",
+            ErrorKind::ContactGroupNotFound(..) => "E_CONTACT_GROUP_NOT_FOUND",
+            ErrorKind::ContactGroupEmpty(..) => "E_CONTACT_GROUP_EMPTY",
+            ErrorKind::InvalidBase58Character(..) => "E_INVALID_BASE58_CHARACTER",
+            ErrorKind::InvalidBase58Length => "E_INVALID_BASE58_LENGTH",
+            ErrorKind::InvalidBase58Checksum => "E_INVALID_BASE58_CHECKSUM",
+            ErrorKind::InvalidBase58Version => "E_INVALID_BASE58_VERSION",
+            ErrorKind::NetworkMismatch(..) => "E_NETWORK_MISMATCH",
+            ErrorKind::InvalidSeedWords(..) => "E_INVALID_SEED_WORDS",
+            ErrorKind::InvalidColorMode(..) => "E_INVALID_COLOR_MODE",
+            ErrorKind::InvalidConfig(..) => "E_INVALID_CONFIG",
+            ErrorKind::InvalidAmountUnits(..) => "E_INVALID_AMOUNT_UNITS",
+            ErrorKind::InvalidLanguage(..) => "E_INVALID_LANGUAGE",
+            ErrorKind::InvalidBase58Key => "E_INVALID_BASE58_KEY",
+            ErrorKind::NumberParsingError => "E_NUMBER_PARSING",
+            ErrorKind::SpanTooLong(..) => "E_SPAN_TOO_LONG",
+            ErrorKind::UnknownAddressType(..) => "E_UNKNOWN_ADDRESS_TYPE",
+            ErrorKind::GrinboxAddressParsingError(..) => "E_GRINBOX_ADDRESS_PARSING",
+            ErrorKind::InvalidAddress(..) => "E_INVALID_ADDRESS",
+            ErrorKind::KeybaseAddressParsingError(..) => "E_KEYBASE_ADDRESS_PARSING",
+            ErrorKind::HttpsAddressParsingError(..) => "E_HTTPS_ADDRESS_PARSING",
+            ErrorKind::KeybaseMessageSendError => "E_KEYBASE_MESSAGE_SEND",
+            ErrorKind::GrinWalletReceiveError => "E_GRIN_WALLET_RECEIVE",
+            ErrorKind::GrinWalletVerifySlateMessagesError => "E_GRIN_WALLET_VERIFY_SLATE_MESSAGES",
+            ErrorKind::GrinWalletFinalizeError => "E_GRIN_WALLET_FINALIZE",
+            ErrorKind::AlreadyFinalized(..) => "E_ALREADY_FINALIZED",
+            ErrorKind::GrinWalletPostError => "E_GRIN_WALLET_POST",
+            ErrorKind::KeybaseNotFound => "E_KEYBASE_NOT_FOUND",
+            ErrorKind::GrinboxWebsocketAbnormalTermination => {
+                "E_GRINBOX_WEBSOCKET_ABNORMAL_TERMINATION"
+            }
+            ErrorKind::ClockSkew(..) => "E_CLOCK_SKEW",
+            ErrorKind::GrinboxAuthFailed(..) => "E_GRINBOX_AUTH_FAILED",
+            ErrorKind::GrinboxConnectionRefused(..) => "E_GRINBOX_CONNECTION_REFUSED",
+            ErrorKind::GrinboxDnsFailure(..) => "E_GRINBOX_DNS_FAILURE",
+            ErrorKind::GrinboxTlsError(..) => "E_GRINBOX_TLS_ERROR",
+            ErrorKind::GrinboxProtocolError(..) => "E_GRINBOX_PROTOCOL_ERROR",
+            ErrorKind::RecipientOffline(..) => "E_RECIPIENT_OFFLINE",
+            ErrorKind::DoesNotAcceptInvoices => "E_DOES_NOT_ACCEPT_INVOICES",
+            ErrorKind::InvoiceAmountTooBig(..) => "E_INVOICE_AMOUNT_TOO_BIG",
+            ErrorKind::HasListener => "E_HAS_LISTENER",
+            ErrorKind::WalletAlreadyUnlocked => "E_WALLET_ALREADY_UNLOCKED",
+            ErrorKind::WalletExists => "E_WALLET_EXISTS",
+            ErrorKind::Encryption => "E_ENCRYPTION",
+            ErrorKind::Decryption => "E_DECRYPTION",
+            ErrorKind::WrongBackupPassword => "E_WRONG_BACKUP_PASSWORD",
+            ErrorKind::AddressKeyMismatch(..) => "E_ADDRESS_KEY_MISMATCH",
+            ErrorKind::SlateFileEncrypted(..) => "E_SLATE_FILE_ENCRYPTED",
+            ErrorKind::WrongSlatePassphrase => "E_WRONG_SLATE_PASSPHRASE",
+            ErrorKind::PasswordsDontMatch => "E_PASSWORDS_DONT_MATCH",
+            ErrorKind::WeakPassphrase(..) => "E_WEAK_PASSPHRASE",
+            ErrorKind::FileExists(..) => "E_FILE_EXISTS",
+            ErrorKind::Restore => "E_RESTORE",
+            ErrorKind::CorruptWallet(..) => "E_CORRUPT_WALLET",
+            ErrorKind::InvalidStorageBackend(..) => "E_INVALID_STORAGE_BACKEND",
+            ErrorKind::RestoreInterrupted => "E_RESTORE_INTERRUPTED",
+            ErrorKind::RestoreNodeFailed(..) => "E_RESTORE_NODE_FAILED",
+            ErrorKind::UnknownAccountLabel(..) => "E_UNKNOWN_ACCOUNT_LABEL",
+            ErrorKind::HttpRequest => "E_HTTP_REQUEST",
+            ErrorKind::InvalidListenAddress(..) => "E_INVALID_LISTEN_ADDRESS",
+            ErrorKind::HttpListenerBind(..) => "E_HTTP_LISTENER_BIND",
+            ErrorKind::Timeout(..) => "E_TIMEOUT",
+            ErrorKind::PingTimeout(..) => "E_PING_TIMEOUT",
+            ErrorKind::PingRequiresGrinbox(..) => "E_PING_REQUIRES_GRINBOX",
+            ErrorKind::NodeSecretFile(..) => "E_NODE_SECRET_FILE",
+            ErrorKind::PassphraseFile(..) => "E_PASSPHRASE_FILE",
+            ErrorKind::NoNodeUri => "E_NO_NODE_URI",
+            ErrorKind::Node => "E_NODE",
+            ErrorKind::GenericError(..) => "E_GENERIC",
+            ErrorKind::VerifyProof => "E_VERIFY_PROOF",
+            ErrorKind::InvalidProofBundle(..) => "E_INVALID_PROOF_BUNDLE",
+            ErrorKind::FileNotFound(..) => "E_FILE_NOT_FOUND",
+            ErrorKind::FileExists(..) => "E_FILE_EXISTS",
+            ErrorKind::StorageUnwritable(..) => "E_STORAGE_UNWRITABLE",
+            ErrorKind::NoCachedBalance => "E_NO_CACHED_BALANCE",
+        }
+    }
+}
+
+/// Formats an error for display, tagging our own errors with their code.
+pub fn format_error(err: &Error) -> String {
+    let formatted = match err.downcast_ref::<ErrorKind>() {
+        Some(kind) => {
+            let message = kind.to_string();
+            format!(
+                "\x1b[31;1merror [{}]:\x1b[0m {}",
+                kind.code(),
+                message.trim_left_matches(ERROR_PREFIX)
+            )
+        }
+        None => err.to_string(),
+    };
+    match unsafe { use_color() } {
+        true => formatted,
+        false => strip_colors(&formatted),
+    }
 }
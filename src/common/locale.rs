@@ -0,0 +1,152 @@
+use std::fmt::{Display, Write};
+
+use common::{ErrorKind, Result};
+
+/// The messages of the listeners and the slates they exchange, keyed by name. Other
+/// output and errors are only in English. Placeholders are filled in order by `format`.
+const ENGLISH: &[(&str, &str)] = &[
+    ("label_error", "ERROR"),
+    ("label_info", "INFO"),
+    ("label_warning", "WARNING"),
+    (
+        "welcome_footer",
+        "Use `listen` to connect to grinbox or `help` to see available commands",
+    ),
+    (
+        "unlock_or_init",
+        "Unlock your existing wallet or type `init` to initiate a new one",
+    ),
+    ("listener_starting", "starting {} listener..."),
+    ("listener_stopping", "stopping {} listener..."),
+    ("listener_started", "listener started for [{}]"),
+    ("listener_stopped", "listener [{}] stopped"),
+    (
+        "listener_stopped_unexpectedly",
+        "{}: listener [{}] stopped unexpectedly",
+    ),
+    (
+        "listener_dropped",
+        "{}: listener [{}] lost connection. it will keep trying to restore connection in the background.",
+    ),
+    (
+        "listener_reestablished",
+        "{}: listener [{}] reestablished connection.",
+    ),
+    ("slate_received", "slate [{}] received from [{}] for [{}] {}"),
+    (
+        "slate_received_back",
+        "slate [{}] received back from [{}] for [{}] {}",
+    ),
+    ("slate_message", "message from [{}]: {}"),
+    ("slate_sent_back", "slate [{}] sent back to [{}] successfully"),
+    ("slate_finalized", "slate [{}] finalized successfully"),
+    (
+        "slate_sent",
+        "slate [{}] for [{}] {} sent successfully to [{}]",
+    ),
+    ("tx_confirmed", "transaction [{}] for [{}] {} was confirmed"),
+    (
+        "slate_queued",
+        "slate [{}] queued. use `accept {}` or `reject {}` to handle it",
+    ),
+    ("slate_resent", "slate [{}] resent successfully to [{}]"),
+    ("slate_rejected", "slate [{}] rejected"),
+    (
+        "invoice_sent",
+        "invoice slate [{}] for [{}] {} sent successfully to [{}]",
+    ),
+];
+
+const SPANISH: &[(&str, &str)] = &[
+    ("label_error", "ERROR"),
+    ("label_info", "INFO"),
+    ("label_warning", "AVISO"),
+    (
+        "welcome_footer",
+        "Usa `listen` para conectarte a grinbox o `help` para ver los comandos disponibles",
+    ),
+    (
+        "unlock_or_init",
+        "Desbloquea tu billetera o escribe `init` para crear una nueva",
+    ),
+    ("listener_starting", "iniciando la escucha de {}..."),
+    ("listener_stopping", "deteniendo la escucha de {}..."),
+    ("listener_started", "escucha iniciada para [{}]"),
+    ("listener_stopped", "escucha [{}] detenida"),
+    (
+        "listener_stopped_unexpectedly",
+        "{}: la escucha [{}] se detuvo inesperadamente",
+    ),
+    (
+        "listener_dropped",
+        "{}: la escucha [{}] perdió la conexión. seguirá intentando restablecerla en segundo plano.",
+    ),
+    (
+        "listener_reestablished",
+        "{}: la escucha [{}] restableció la conexión.",
+    ),
+    ("slate_received", "slate [{}] recibido de [{}] por [{}] {}"),
+    ("slate_received_back", "slate [{}] devuelto por [{}] por [{}] {}"),
+    ("slate_message", "mensaje de [{}]: {}"),
+    ("slate_sent_back", "slate [{}] devuelto a [{}] correctamente"),
+    ("slate_finalized", "slate [{}] finalizado correctamente"),
+    (
+        "slate_sent",
+        "slate [{}] por [{}] {} enviado correctamente a [{}]",
+    ),
+    (
+        "tx_confirmed",
+        "la transacción [{}] por [{}] {} fue confirmada",
+    ),
+    (
+        "slate_queued",
+        "slate [{}] en espera. usa `accept {}` o `reject {}` para gestionarlo",
+    ),
+    ("slate_resent", "slate [{}] reenviado correctamente a [{}]"),
+    ("slate_rejected", "slate [{}] rechazado"),
+    (
+        "invoice_sent",
+        "factura [{}] por [{}] {} enviada correctamente a [{}]",
+    ),
+];
+
+static mut CATALOG: &[(&str, &str)] = ENGLISH;
+
+/// Selects the language of the messages: `en` or `es`
+pub unsafe fn set_language(language: &str) -> Result<()> {
+    CATALOG = match language {
+        "en" => ENGLISH,
+        "es" => SPANISH,
+        _ => Err(ErrorKind::InvalidLanguage(language.to_string()))?,
+    };
+    Ok(())
+}
+
+/// The message for `key` in the selected language. Messages missing from a
+/// translation fall back to English, and unknown keys to the key itself.
+pub fn message(key: &str) -> &str {
+    let lookup = |catalog: &[(&'static str, &'static str)]| {
+        catalog
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, message)| *message)
+    };
+    unsafe { lookup(CATALOG) }
+        .or_else(|| lookup(ENGLISH))
+        .unwrap_or(key)
+}
+
+/// Formats the message for `key`, replacing each `{}` with the next argument
+pub fn format(key: &str, args: &[&Display]) -> String {
+    let mut parts = message(key).split("{}");
+    let mut formatted = String::new();
+    formatted.push_str(parts.next().unwrap_or(""));
+    let mut args = args.iter();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            write!(formatted, "{}", arg).is_ok();
+        }
+        formatted.push_str(part);
+    }
+    formatted
+}
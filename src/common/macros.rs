@@ -3,9 +3,9 @@ macro_rules! cli_message {
         () => {
             unsafe {
                 use std::io::Write;
-                use crate::common::{is_cli, COLORED_PROMPT};
+                use $crate::common::{colored_prompt, is_cli};
                 if is_cli() {
-                    print!("\r{}", COLORED_PROMPT);
+                    print!("\r{}", colored_prompt());
                     std::io::stdout().flush().unwrap();
                 }
             }
@@ -14,11 +14,11 @@ macro_rules! cli_message {
         ($fmt_string:expr, $( $arg:expr ),+) => {
             unsafe {
                 use std::io::Write;
-                use crate::common::{is_cli, COLORED_PROMPT};
+                use $crate::common::{colored_prompt, is_cli};
                 if is_cli() {
                     print!("\r");
                     print!($fmt_string, $( $arg ),*);
-                    print!("\n{}", COLORED_PROMPT);
+                    print!("\n{}", colored_prompt());
                     std::io::stdout().flush().unwrap();
                 } else {
                     info!($fmt_string, $( $arg ),*);
@@ -29,11 +29,11 @@ macro_rules! cli_message {
         ($fmt_string:expr) => {
             unsafe {
                 use std::io::Write;
-                use crate::common::{is_cli, COLORED_PROMPT};
+                use $crate::common::{colored_prompt, is_cli};
                 if is_cli() {
                     print!("\r");
                     print!($fmt_string);
-                    print!("\n{}", COLORED_PROMPT);
+                    print!("\n{}", colored_prompt());
                     std::io::stdout().flush().unwrap();
                 } else {
                     info!($fmt_string);
@@ -41,3 +41,15 @@ macro_rules! cli_message {
             }
         };
     }
+
+/// Formats a message from the catalog in `common::locale`, in the selected language
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::common::locale::format($key, &[])
+    };
+
+    ($key:expr, $( $arg:expr ),+) => {
+        $crate::common::locale::format($key, &[ $( &$arg as &::std::fmt::Display ),+ ])
+    };
+}
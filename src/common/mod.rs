@@ -5,11 +5,16 @@ pub mod config;
 pub mod crypto;
 mod error_kind;
 pub mod hasher;
+pub mod locale;
 pub mod message;
+pub mod password;
+pub mod time;
 
-pub use self::error_kind::ErrorKind;
+pub use self::error_kind::{format_error, ErrorKind};
 pub use self::macros::*;
 pub use failure::Error;
+use grin_core::consensus::GRIN_BASE;
+use grin_core::core::{amount_from_hr_string, amount_to_hr_string};
 pub use parking_lot::{Mutex, MutexGuard};
 pub use std::sync::Arc;
 pub type Result<T> = std::result::Result<T, Error>;
@@ -32,3 +37,186 @@ pub unsafe fn is_cli() -> bool {
 
 pub const COLORED_PROMPT: &'static str = "\x1b[36mwallet713>\x1b[0m ";
 pub const PROMPT: &'static str = "wallet713> ";
+
+static mut CUSTOM_PROMPT: Option<String> = None;
+
+pub unsafe fn set_prompt(prompt: Option<String>) {
+    CUSTOM_PROMPT = prompt;
+}
+
+pub unsafe fn prompt() -> String {
+    match CUSTOM_PROMPT {
+        Some(ref prompt) => prompt.clone(),
+        None => PROMPT.to_string(),
+    }
+}
+
+pub unsafe fn colored_prompt() -> String {
+    if !USE_COLOR {
+        return prompt();
+    }
+    match CUSTOM_PROMPT {
+        Some(ref prompt) => format!("\x1b[36m{}\x1b[0m", prompt),
+        None => COLORED_PROMPT.to_string(),
+    }
+}
+
+static mut USE_COLOR: bool = true;
+
+/// Turns colored output on or off. `auto` colors only when stdout is a terminal.
+pub unsafe fn set_color_mode(mode: &str) -> Result<()> {
+    let enabled = match mode {
+        "auto" => atty::is(atty::Stream::Stdout),
+        "always" => true,
+        "never" => false,
+        _ => Err(ErrorKind::InvalidColorMode(mode.to_string()))?,
+    };
+    colored::control::set_override(enabled);
+    USE_COLOR = enabled;
+    Ok(())
+}
+
+pub unsafe fn use_color() -> bool {
+    USE_COLOR
+}
+
+static mut NANO_UNITS: bool = false;
+
+/// Sets the units amounts are printed in: `grin`, or `nano` for whole nanogrins
+pub unsafe fn set_amount_units(units: &str) -> Result<()> {
+    NANO_UNITS = match units {
+        "grin" => false,
+        "nano" => true,
+        _ => Err(ErrorKind::InvalidAmountUnits(units.to_string()))?,
+    };
+    Ok(())
+}
+
+/// Formats an amount in the units set by `set_amount_units`. Nanogrins are printed
+/// as a plain integer, so `truncate` only applies to grins.
+pub fn amount_to_string(amount: u64, truncate: bool) -> String {
+    match unsafe { NANO_UNITS } {
+        true => amount.to_string(),
+        false => amount_to_hr_string(amount, truncate),
+    }
+}
+
+const CURRENCY_SYMBOLS: &[char] = &['$', '€', '£', '¥', 'ツ'];
+
+/// Reads an amount of grins the way it may be pasted from elsewhere: surrounding
+/// whitespace and currency symbols are dropped, and spaces may separate groups of
+/// thousands, as in `1 000.5`. Commas are refused, they could be either a decimal
+/// or a thousands separator.
+pub fn amount_from_string(amount: &str) -> Result<u64> {
+    let invalid = || ErrorKind::InvalidAmount(amount.to_string());
+    let ambiguous = || ErrorKind::AmbiguousAmount(amount.to_string());
+    let trimmed = amount.trim().trim_matches(CURRENCY_SYMBOLS).trim();
+    if trimmed.contains(',') {
+        return Err(ambiguous())?;
+    }
+
+    let mut parts = trimmed.splitn(2, '.');
+    let whole = parts.next().unwrap_or("");
+    let groups: Vec<&str> = whole
+        .split(|c: char| c == ' ' || c == '\u{a0}' || c == '\u{202f}')
+        .collect();
+    if groups.len() > 1 {
+        let first = groups[0].len();
+        if first == 0 || first > 3 || groups[1..].iter().any(|group| group.len() != 3) {
+            return Err(ambiguous())?;
+        }
+    }
+    let mut cleaned = groups.concat();
+    if let Some(fraction) = parts.next() {
+        cleaned.push('.');
+        cleaned.push_str(fraction);
+    }
+    if cleaned.is_empty() || !cleaned.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return Err(invalid())?;
+    }
+    // the conversion to nanogrins goes through a float, which silently saturates
+    let whole = cleaned.split('.').next().unwrap_or("");
+    let whole = whole.trim_start_matches('0');
+    if !whole.is_empty()
+        && whole
+            .parse::<u64>()
+            .ok()
+            .and_then(|whole| whole.checked_mul(GRIN_BASE))
+            .is_none()
+    {
+        return Err(ErrorKind::AmountOverflow)?;
+    }
+    Ok(amount_from_hr_string(&cleaned).map_err(|_| invalid())?)
+}
+
+/// Reads a fiat amount given as a number followed by a currency code, as in `10.00usd`.
+/// Returns the amount and the lowercase currency code.
+pub fn fiat_amount_from_string(fiat: &str) -> Result<(f64, String)> {
+    let invalid = || ErrorKind::InvalidFiatAmount(fiat.to_string());
+    let trimmed = fiat.trim();
+    let split = trimmed
+        .find(|c: char| c.is_ascii_alphabetic())
+        .ok_or_else(invalid)?;
+    let (value, currency) = trimmed.split_at(split);
+    let value = value.trim();
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return Err(invalid())?;
+    }
+    let value = value
+        .parse::<f64>()
+        .ok()
+        .filter(|value| *value > 0.0)
+        .ok_or_else(invalid)?;
+    if currency.len() < 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(invalid())?;
+    }
+    Ok((value, currency.to_lowercase()))
+}
+
+/// The name of the units amounts are printed in, for messages like `[1.5] grins`
+pub fn amount_units() -> &'static str {
+    match unsafe { NANO_UNITS } {
+        true => "nanogrins",
+        false => "grins",
+    }
+}
+
+/// Removes terminal color escape sequences from the given text
+pub fn strip_colors(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip up to and including the final letter of the sequence
+            while let Some(c) = chars.next() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_amounts_near_the_max() {
+        assert_eq!(amount_from_string("1").unwrap(), 1_000_000_000);
+        assert!(amount_from_string("18446744073").is_ok());
+        let err = amount_from_string("18446744074").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::AmountOverflow)
+        );
+        let err = amount_from_string("99999999999999999999999").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::AmountOverflow)
+        );
+    }
+}
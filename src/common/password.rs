@@ -0,0 +1,67 @@
+/// Passphrases at least this long count as strong whatever characters they use, so
+/// word based passphrases aren't flagged for lacking digits or symbols
+const STRONG_LENGTH: usize = 16;
+const MIN_LENGTH: usize = 8;
+/// Character classes (lowercase, uppercase, digits, others) needed below `STRONG_LENGTH`
+const MIN_CLASSES: usize = 3;
+
+const COMMON_PASSWORDS: &[&str] = &[
+    "password",
+    "passw0rd",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "qwertyuiop",
+    "qwerty123",
+    "iloveyou",
+    "letmein1",
+    "abcdefgh",
+    "grin1234",
+    "wallet713",
+];
+
+/// Explains why a passphrase is weak, or returns `None` when it looks strong enough.
+/// The check only catches the obvious cases: short, repetitive or well known
+/// passphrases and ones mixing too few kinds of characters.
+pub fn passphrase_weakness(passphrase: &str) -> Option<&'static str> {
+    let length = passphrase.chars().count();
+    let lowercase = passphrase.to_lowercase();
+    if COMMON_PASSWORDS.contains(&lowercase.as_str()) {
+        return Some("it is a commonly used password");
+    }
+    if length < MIN_LENGTH {
+        return Some("it is shorter than 8 characters");
+    }
+
+    let mut distinct: Vec<char> = passphrase.chars().collect();
+    distinct.sort();
+    distinct.dedup();
+    if distinct.len() * 3 < length || distinct.len() < 4 {
+        return Some("it repeats the same few characters");
+    }
+    if is_sequence(passphrase) {
+        return Some("it is a simple sequence of characters");
+    }
+    if length >= STRONG_LENGTH {
+        return None;
+    }
+
+    let classes = [
+        passphrase.chars().any(|c| c.is_lowercase()),
+        passphrase.chars().any(|c| c.is_uppercase()),
+        passphrase.chars().any(|c| c.is_numeric()),
+        passphrase.chars().any(|c| !c.is_alphanumeric()),
+    ];
+    if classes.iter().filter(|&&c| c).count() < MIN_CLASSES {
+        return Some("it mixes too few kinds of characters. use a longer passphrase, or mix letters, digits and symbols");
+    }
+    None
+}
+
+/// Whether every character follows the previous one, like `abcdefgh` or `87654321`
+fn is_sequence(passphrase: &str) -> bool {
+    let chars: Vec<u32> = passphrase.chars().map(|c| c as u32).collect();
+    let ascending = chars.windows(2).all(|w| w[1] == w[0] + 1);
+    let descending = chars.windows(2).all(|w| w[0] == w[1] + 1);
+    ascending || descending
+}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Duration, Utc};
+
+use common::{ErrorKind, Result};
+
+const ABSOLUTE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const MAX_RELATIVE_DAYS: i64 = 30;
+
+pub const MINUTES_PER_DAY: u64 = 24 * 60;
+/// Ten years, the longest span taken from the user. Anything longer is a typo, and
+/// chrono panics on spans that don't fit its range
+pub const MAX_SPAN_MINUTES: u64 = 3_650 * MINUTES_PER_DAY;
+
+/// The time `minutes` from now
+pub fn minutes_from_now(minutes: u64) -> Result<DateTime<Utc>> {
+    let span = span(minutes)?;
+    Ok(Utc::now()
+        .checked_add_signed(span)
+        .ok_or(ErrorKind::SpanTooLong(minutes))?)
+}
+
+/// The time `minutes` ago
+pub fn minutes_ago(minutes: u64) -> Result<DateTime<Utc>> {
+    let span = span(minutes)?;
+    Ok(Utc::now()
+        .checked_sub_signed(span)
+        .ok_or(ErrorKind::SpanTooLong(minutes))?)
+}
+
+fn span(minutes: u64) -> Result<Duration> {
+    match minutes <= MAX_SPAN_MINUTES {
+        true => Ok(Duration::minutes(minutes as i64)),
+        false => Err(ErrorKind::SpanTooLong(minutes).into()),
+    }
+}
+
+/// Formats a timestamp as `3 hours ago` style text, falling back to the absolute
+/// time for old or future timestamps. Both sides of the comparison are in UTC,
+/// so the result doesn't depend on the local timezone.
+pub fn format_time(ts: &DateTime<Utc>, absolute: bool) -> String {
+    let elapsed = Utc::now().signed_duration_since(*ts);
+    if absolute || elapsed.num_seconds() < 0 || elapsed.num_days() > MAX_RELATIVE_DAYS {
+        return ts.format(ABSOLUTE_FORMAT).to_string();
+    }
+
+    let (count, unit) = if elapsed.num_minutes() < 1 {
+        return String::from("just now");
+    } else if elapsed.num_hours() < 1 {
+        (elapsed.num_minutes(), "minute")
+    } else if elapsed.num_days() < 1 {
+        (elapsed.num_hours(), "hour")
+    } else {
+        (elapsed.num_days(), "day")
+    };
+    match count {
+        1 => format!("1 {} ago", unit),
+        _ => format!("{} {}s ago", count, unit),
+    }
+}
@@ -6,11 +6,12 @@ use grin_core::ser::Error as CoreError;
 use grin_core::ser::{Readable, Reader, Writeable, Writer};
 use grin_store::{self, option_to_not_found, to_key};
 
-use super::types::{Address, AddressBookBackend, AddressBookBatch, Contact};
+use super::types::{Address, AddressBookBackend, AddressBookBatch, Contact, ContactGroup};
 use common::{Arc, Error};
 
 const DB_DIR: &'static str = "contacts";
 const CONTACT_PREFIX: u8 = 'X' as u8;
+const GROUP_PREFIX: u8 = 'G' as u8;
 
 pub struct Backend {
     db: grin_store::Store,
@@ -43,6 +44,19 @@ impl AddressBookBackend for Backend {
         Box::new(self.db.iter(&[CONTACT_PREFIX]).unwrap())
     }
 
+    fn get_group(&mut self, name: &[u8]) -> Result<ContactGroup, Error> {
+        let group_key = to_key(GROUP_PREFIX, &mut name.to_vec());
+        option_to_not_found(
+            self.db.get_ser(&group_key),
+            &format!("Group id: {:x?}", name.to_vec()),
+        )
+        .map_err(|e| e.into())
+    }
+
+    fn groups(&self) -> Box<Iterator<Item = ContactGroup>> {
+        Box::new(self.db.iter(&[GROUP_PREFIX]).unwrap())
+    }
+
     fn batch<'a>(&'a self) -> Result<Box<AddressBookBatch + 'a>, Error> {
         let batch = self.db.batch()?;
         let batch = Batch {
@@ -80,6 +94,17 @@ impl<'a> AddressBookBatch for Batch<'a> {
             .map_err(|e| e.into())
     }
 
+    fn save_group(&mut self, group: &ContactGroup) -> Result<(), Error> {
+        let mut key = group.get_name().to_string().into_bytes();
+        let group_key = to_key(GROUP_PREFIX, &mut key);
+        self.db
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .put_ser(&group_key, group)?;
+        Ok(())
+    }
+
     fn commit(&mut self) -> Result<(), Error> {
         let db = self.db.replace(None);
         db.unwrap().commit()?;
@@ -92,6 +117,9 @@ impl Writeable for Contact {
         let json = json!({
             "name": self.get_name(),
             "address": self.get_address().to_string(),
+            "transport": self.get_transport(),
+            "strategy": self.get_strategy(),
+            "min_confirmations": self.get_min_confirmations(),
         });
         writer.write_bytes(&json.to_string().as_bytes())
     }
@@ -108,9 +136,35 @@ impl Readable for Contact {
         let address = Address::parse(json["address"].as_str().unwrap())
             .map_err(|_| CoreError::CorruptedData)?;
 
-        let contact = Contact::new(json["name"].as_str().unwrap(), address)
+        let mut contact = Contact::new(json["name"].as_str().unwrap(), address)
+            .map_err(|_| CoreError::CorruptedData)?;
+        // transports matching the address used to be stored too, they are the default
+        let transport = json["transport"].as_str().filter(|t| *t == "file");
+        contact
+            .set_transport(transport)
+            .map_err(|_| CoreError::CorruptedData)?;
+        contact
+            .set_strategy(json["strategy"].as_str())
+            .map_err(|_| CoreError::CorruptedData)?;
+        contact
+            .set_min_confirmations(json["min_confirmations"].as_u64())
             .map_err(|_| CoreError::CorruptedData)?;
 
         Ok(contact)
     }
 }
+
+impl Writeable for ContactGroup {
+    fn write<W: Writer>(&self, writer: &mut W) -> Result<(), CoreError> {
+        let json = serde_json::to_string(self).map_err(|_| CoreError::CorruptedData)?;
+        writer.write_bytes(&json.as_bytes())
+    }
+}
+
+impl Readable for ContactGroup {
+    fn read(reader: &mut Reader) -> Result<ContactGroup, CoreError> {
+        let data = reader.read_bytes_len_prefix()?;
+        let data = std::str::from_utf8(&data).map_err(|_| CoreError::CorruptedData)?;
+        serde_json::from_str(&data).map_err(|_| CoreError::CorruptedData)
+    }
+}
@@ -2,6 +2,6 @@ mod backend;
 mod types;
 pub use self::backend::Backend;
 pub use self::types::{
-    Address, AddressBook, AddressBookBackend, AddressType, Contact, GrinboxAddress, KeybaseAddress,
-    DEFAULT_GRINBOX_PORT,
+    Address, AddressBook, AddressBookBackend, AddressType, Contact, ContactGroup, GrinboxAddress,
+    GroupMember, KeybaseAddress, DEFAULT_GRINBOX_PORT,
 };
@@ -2,15 +2,13 @@ use regex::Regex;
 use std::fmt::{self, Debug, Display};
 use url::Url;
 
-use grin_core::global::is_mainnet;
-
 use common::crypto::{
-    Base58, PublicKey, GRINBOX_ADDRESS_VERSION_MAINNET, GRINBOX_ADDRESS_VERSION_TESTNET,
+    check_address_network, grinbox_address_version, parse_public_key, Base58, PublicKey,
 };
 use common::{ErrorKind, Result};
 
 const ADDRESS_REGEX: &str = r"^((?P<address_type>keybase|grinbox|https)://).+$";
-const GRINBOX_ADDRESS_REGEX: &str = r"^(grinbox://)?(?P<public_key>[123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz]{52})(@(?P<domain>[a-zA-Z0-9\.]+)(:(?P<port>[0-9]*))?)?$";
+const GRINBOX_ADDRESS_REGEX: &str = r"^(grinbox://)?(?P<public_key>[123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz]{52}|[0-9a-fA-F]{66})(@(?P<domain>[a-zA-Z0-9\.]+)(:(?P<port>[0-9]*))?)?$";
 const KEYBASE_ADDRESS_REGEX: &str = r"^(keybase://)?(?P<username>[0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_]{1,16})(:(?P<topic>[a-zA-Z0-9_-]+))?$";
 const DEFAULT_GRINBOX_DOMAIN: &str = "grinbox.io";
 pub const DEFAULT_GRINBOX_PORT: u16 = 443;
@@ -35,7 +33,18 @@ impl Address {
         let re = Regex::new(ADDRESS_REGEX)?;
         let captures = re.captures(address);
         if captures.is_none() {
-            return Ok(Box::new(GrinboxAddress::from_str(address)?));
+            let grinbox_address = GrinboxAddress::from_str(address).map_err(|e| {
+                // a key for the other network is worth telling apart from a typo
+                let mismatch = match e.downcast_ref::<ErrorKind>() {
+                    Some(ErrorKind::NetworkMismatch(..)) => true,
+                    _ => false,
+                };
+                match mismatch {
+                    true => e,
+                    false => ErrorKind::InvalidAddress(address.to_string()).into(),
+                }
+            })?;
+            return Ok(Box::new(grinbox_address));
         }
 
         let captures = captures.unwrap();
@@ -53,12 +62,15 @@ impl Address {
 pub trait AddressBookBackend {
     fn get_contact(&mut self, name: &[u8]) -> Result<Contact>;
     fn contacts(&self) -> Box<Iterator<Item = Contact>>;
+    fn get_group(&mut self, name: &[u8]) -> Result<ContactGroup>;
+    fn groups(&self) -> Box<Iterator<Item = ContactGroup>>;
     fn batch<'a>(&'a self) -> Result<Box<AddressBookBatch + 'a>>;
 }
 
 pub trait AddressBookBatch {
     fn save_contact(&mut self, contact: &Contact) -> Result<()>;
     fn delete_contact(&mut self, public_key: &[u8]) -> Result<()>;
+    fn save_group(&mut self, group: &ContactGroup) -> Result<()>;
     fn commit(&mut self) -> Result<()>;
 }
 
@@ -95,6 +107,15 @@ impl AddressBook {
         Ok(contact)
     }
 
+    /// Saves the changes made to an existing contact
+    pub fn update_contact(&mut self, contact: &Contact) -> Result<()> {
+        self.get_contact(&contact.name)?;
+        let mut batch = self.backend.batch()?;
+        batch.save_contact(contact)?;
+        batch.commit()?;
+        Ok(())
+    }
+
     pub fn get_contact_by_address(&mut self, address: &str) -> Result<Contact> {
         for contact in self.contacts() {
             if contact.address == address {
@@ -107,12 +128,52 @@ impl AddressBook {
     pub fn contacts(&self) -> Box<Iterator<Item = Contact>> {
         self.backend.contacts()
     }
+
+    pub fn create_group(&mut self, name: &str) -> Result<()> {
+        let result = self.get_group(name);
+        if result.is_ok() {
+            return Err(ErrorKind::ContactGroupAlreadyExists(name.to_string()))?;
+        }
+        let group = ContactGroup::new(name);
+        let mut batch = self.backend.batch()?;
+        batch.save_group(&group)?;
+        batch.commit()?;
+        Ok(())
+    }
+
+    pub fn add_to_group(
+        &mut self,
+        group_name: &str,
+        contact_name: &str,
+        weight: u64,
+    ) -> Result<()> {
+        let mut group = self.get_group(group_name)?;
+        self.get_contact(contact_name)?;
+        group.add_member(contact_name, weight);
+        let mut batch = self.backend.batch()?;
+        batch.save_group(&group)?;
+        batch.commit()?;
+        Ok(())
+    }
+
+    pub fn get_group(&mut self, name: &str) -> Result<ContactGroup> {
+        self.backend
+            .get_group(name.as_bytes())
+            .map_err(|_| ErrorKind::ContactGroupNotFound(name.to_string()).into())
+    }
+
+    pub fn groups(&self) -> Box<Iterator<Item = ContactGroup>> {
+        self.backend.groups()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Contact {
     name: String,
     address: String,
+    transport: Option<String>,
+    strategy: Option<String>,
+    min_confirmations: Option<u64>,
 }
 
 impl Contact {
@@ -120,6 +181,9 @@ impl Contact {
         Ok(Self {
             name: name.to_string(),
             address: address.to_string(),
+            transport: None,
+            strategy: None,
+            min_confirmations: None,
         })
     }
 
@@ -130,6 +194,128 @@ impl Contact {
     pub fn get_address(&self) -> &String {
         &self.address
     }
+
+    /// How to send to this contact by default: `file`, or over its address when unset
+    pub fn get_transport(&self) -> Option<&str> {
+        self.transport.as_ref().map(|t| t.as_str())
+    }
+
+    /// The input selection strategy to use by default when sending to this contact
+    pub fn get_strategy(&self) -> Option<&str> {
+        self.strategy.as_ref().map(|s| s.as_str())
+    }
+
+    /// How many confirmations a payment with this contact needs before the listener
+    /// reports it as confirmed
+    pub fn get_min_confirmations(&self) -> Option<u64> {
+        self.min_confirmations
+    }
+
+    /// Sets the default transport. Only `file` can be stored, it makes `send` write
+    /// the slate to a file instead of delivering it to the contact's address.
+    pub fn set_transport(&mut self, transport: Option<&str>) -> Result<()> {
+        match transport {
+            None | Some("file") => {}
+            Some(transport) => Err(ErrorKind::InvalidContactTransport(transport.to_string()))?,
+        }
+        self.transport = transport.map(|t| t.to_string());
+        Ok(())
+    }
+
+    pub fn set_strategy(&mut self, strategy: Option<&str>) -> Result<()> {
+        match strategy {
+            None | Some("smallest") | Some("all") => {}
+            Some(_) => Err(ErrorKind::InvalidStrategy)?,
+        }
+        self.strategy = strategy.map(|s| s.to_string());
+        Ok(())
+    }
+
+    pub fn set_min_confirmations(&mut self, min_confirmations: Option<u64>) -> Result<()> {
+        if min_confirmations == Some(0) {
+            Err(ErrorKind::NumberParsingError)?;
+        }
+        self.min_confirmations = min_confirmations;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub name: String,
+    pub weight: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactGroup {
+    name: String,
+    members: Vec<GroupMember>,
+}
+
+impl ContactGroup {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            members: vec![],
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_members(&self) -> &Vec<GroupMember> {
+        &self.members
+    }
+
+    /// Adds a member, or updates its weight if it is already in the group
+    pub fn add_member(&mut self, name: &str, weight: u64) {
+        match self.members.iter_mut().find(|m| m.name == name) {
+            Some(member) => member.weight = weight,
+            None => self.members.push(GroupMember {
+                name: name.to_string(),
+                weight,
+            }),
+        }
+    }
+
+    /// Splits an amount among the members according to their weights.
+    /// Any remainder left by the integer division goes to the first member.
+    pub fn split_amount(&self, amount: u64) -> Result<Vec<(String, u64)>> {
+        let total_weight = self
+            .members
+            .iter()
+            .try_fold(0u64, |total, m| total.checked_add(m.weight))
+            .ok_or(ErrorKind::AmountOverflow)?;
+        if total_weight == 0 {
+            return Err(ErrorKind::ContactGroupEmpty(self.name.clone()))?;
+        }
+
+        let mut shares: Vec<(String, u64)> = self
+            .members
+            .iter()
+            .map(|m| {
+                let share = amount as u128 * m.weight as u128 / total_weight as u128;
+                (m.name.clone(), share as u64)
+            })
+            .collect();
+
+        let allocated: u64 = shares.iter().map(|(_, share)| share).sum();
+        shares[0].1 += amount - allocated;
+        Ok(shares.into_iter().filter(|(_, share)| *share > 0).collect())
+    }
+}
+
+impl Display for ContactGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let members: Vec<String> = self
+            .members
+            .iter()
+            .map(|m| format!("@{} ({})", m.name, m.weight))
+            .collect();
+        write!(f, "{}: {}", self.name, members.join(", "))?;
+        Ok(())
+    }
 }
 
 impl Display for Contact {
@@ -178,14 +364,6 @@ impl Display for KeybaseAddress {
     }
 }
 
-pub fn version_bytes() -> Vec<u8> {
-    if is_mainnet() {
-        GRINBOX_ADDRESS_VERSION_MAINNET.to_vec()
-    } else {
-        GRINBOX_ADDRESS_VERSION_TESTNET.to_vec()
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct GrinboxAddress {
     pub public_key: String,
@@ -196,14 +374,14 @@ pub struct GrinboxAddress {
 impl GrinboxAddress {
     pub fn new(public_key: PublicKey, domain: Option<String>, port: Option<u16>) -> Self {
         Self {
-            public_key: public_key.to_base58_check(version_bytes()),
+            public_key: public_key.to_base58_check(grinbox_address_version()),
             domain: domain.unwrap_or(DEFAULT_GRINBOX_DOMAIN.to_string()),
             port,
         }
     }
 
     pub fn public_key(&self) -> Result<PublicKey> {
-        PublicKey::from_base58_check(&self.public_key, version_bytes())
+        PublicKey::from_base58_check(&self.public_key, grinbox_address_version())
     }
 }
 
@@ -222,7 +400,10 @@ impl Address for GrinboxAddress {
             .name("port")
             .map(|m| u16::from_str_radix(m.as_str(), 10).unwrap());
 
-        let public_key = PublicKey::from_base58_check(&public_key, version_bytes())?;
+        // accepts either encoding and stores the key as base58check
+        check_address_network(&public_key)?;
+        let public_key =
+            parse_public_key(&public_key).map_err(|_| ErrorKind::InvalidAddress(s.to_string()))?;
 
         Ok(GrinboxAddress::new(public_key, domain, port))
     }
@@ -278,3 +459,31 @@ impl Display for HttpsAddress {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_weights_that_overflow() {
+        let mut group = ContactGroup::new("group");
+        group.add_member("a", u64::max_value());
+        group.add_member("b", 1);
+        let err = group.split_amount(100).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::AmountOverflow)
+        );
+    }
+
+    #[test]
+    fn splits_the_max_amount() {
+        let mut group = ContactGroup::new("group");
+        group.add_member("a", u64::max_value());
+        group.add_member("b", 0);
+        assert_eq!(
+            group.split_amount(u64::max_value()).unwrap(),
+            vec![("a".to_string(), u64::max_value())]
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! wallet713 as a library, for programs that want to drive a wallet without the
+//! interactive shell.
+//!
+//! A wallet is created from a [`Wallet713Config`] and unlocked with its passphrase,
+//! after which it can send, receive and finalize slates. Slates are exchanged through
+//! the transports in [`broker`], with the addresses they are sent to.
+//!
+//! The `wallet713` binary is built on the same api, the interactive shell and the
+//! command line parsing live in the binary only.
+
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate prettytable;
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate gotham_derive;
+extern crate atty;
+extern crate blake2_rfc;
+extern crate chrono;
+extern crate colored;
+extern crate digest;
+extern crate failure;
+extern crate futures;
+extern crate gotham;
+extern crate hmac;
+extern crate hyper;
+extern crate lmdb_zero;
+extern crate mime;
+extern crate parking_lot;
+extern crate rand;
+extern crate regex;
+extern crate ring;
+extern crate ripemd160;
+extern crate serde;
+extern crate sha2;
+extern crate term;
+extern crate url;
+extern crate uuid;
+extern crate ws;
+
+#[cfg(unix)]
+extern crate libc;
+
+extern crate grin_api;
+extern crate grin_core;
+extern crate grin_keychain;
+extern crate grin_store;
+extern crate grin_util;
+extern crate grin_wallet;
+
+/// Configuration, errors and the formatting helpers the rest of the crate shares.
+#[macro_use]
+pub mod common;
+/// The owner and foreign http apis a wallet can listen on.
+pub mod api;
+/// Transports delivering slates between wallets.
+pub mod broker;
+/// Addresses and the address book of contacts.
+pub mod contacts;
+/// The wallet, its storage and the types it keeps.
+pub mod wallet;
+
+pub use common::config::Wallet713Config;
+pub use common::{Error, ErrorKind, Result};
+pub use contacts::{Address, AddressType, GrinboxAddress, KeybaseAddress};
+pub use wallet::types::{Slate, TxProof};
+pub use wallet::Wallet;
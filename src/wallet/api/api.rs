@@ -1,3 +1,4 @@
+use chrono::{DateTime, Duration, Utc};
 use std::collections::HashSet;
 use std::marker::PhantomData;
 use uuid::Uuid;
@@ -10,11 +11,13 @@ use grin_util::secp::{ContextFlag, Secp256k1};
 use crate::contacts::GrinboxAddress;
 
 use super::keys;
+use super::restore;
 use super::tx;
 use super::types::{
-    AcctPathMapping, Arc, BlockFees, CbData, ContextType, Error, ErrorKind, Identifier, Keychain,
-    Mutex, NodeClient, OutputData, Slate, Transaction, TxLogEntry, TxLogEntryType, TxProof,
-    TxWrapper, WalletBackend, WalletInfo,
+    AcctPathMapping, Arc, BlockFees, CbData, ContextType, Error, ErrorKind, FeeOverrides,
+    Identifier, Keychain, Mutex, NodeClient, OutputData, PendingSlate, RestoreEnd, SigningKey,
+    Slate, Transaction, TxLogEntry, TxLogEntryType, TxProof, TxState, TxWrapper, WalletBackend,
+    WalletInfo,
 };
 use super::updater;
 
@@ -62,6 +65,7 @@ where
         num_change_outputs: usize,
         selection_strategy_is_use_all: bool,
         message: Option<String>,
+        fee_overrides: &FeeOverrides,
     ) -> Result<(impl FnOnce(&mut W, &Transaction) -> Result<(), Error>), Error> {
         let mut w = self.wallet.lock();
         w.open_with_credentials()?;
@@ -82,6 +86,7 @@ where
             selection_strategy_is_use_all,
             parent_key_id.clone(),
             message,
+            fee_overrides,
         );
         w.close()?;
         res
@@ -202,6 +207,31 @@ where
         res
     }
 
+    /// The summary info of every account, each refreshed from the node first if asked to
+    pub fn retrieve_summary_info_all_accounts(
+        &mut self,
+        refresh_from_node: bool,
+        minimum_confirmations: u64,
+    ) -> Result<(bool, Vec<(String, WalletInfo)>), Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+
+        let mut validated = refresh_from_node;
+        let mut infos = vec![];
+        for account in keys::accounts(&mut *w)? {
+            if refresh_from_node {
+                validated &= updater::refresh_outputs(&mut *w, &account.path, false).is_ok();
+            }
+            let wallet_info =
+                updater::retrieve_info(&mut *w, &account.path, minimum_confirmations)?;
+            infos.push((account.label, wallet_info));
+        }
+        let res = Ok((validated, infos));
+
+        w.close()?;
+        res
+    }
+
     pub fn initiate_tx(
         &mut self,
         address: Option<String>,
@@ -210,7 +240,13 @@ where
         max_outputs: usize,
         num_change_outputs: usize,
         selection_strategy_is_use_all: bool,
+        use_outputs: Option<&Vec<String>>,
+        replaces: Option<u32>,
+        lock_height: Option<u64>,
         message: Option<String>,
+        change_account: Option<&str>,
+        allow_unconfirmed_change: bool,
+        fee_overrides: &FeeOverrides,
     ) -> Result<
         (
             Slate,
@@ -221,6 +257,14 @@ where
         let mut w = self.wallet.lock();
         w.open_with_credentials()?;
         let parent_key_id = w.get_parent_key_id();
+        let change_parent_key_id = match change_account {
+            Some(label) => Some(
+                w.get_acct_path(label)
+                    .map_err(|_| ErrorKind::UnknownAccountLabel(label.to_string()))?
+                    .path,
+            ),
+            None => None,
+        };
         let (slate, mut context, lock_fn) = tx::create_send_tx(
             &mut *w,
             address,
@@ -229,8 +273,14 @@ where
             max_outputs,
             num_change_outputs,
             selection_strategy_is_use_all,
+            use_outputs,
+            replaces,
+            lock_height,
             &parent_key_id,
+            change_parent_key_id,
+            allow_unconfirmed_change,
             message,
+            fee_overrides,
         )?;
 
         for input in slate.tx.inputs() {
@@ -246,6 +296,8 @@ where
         {
             let mut batch = w.batch()?;
             batch.save_private_context(&slate.id.to_string(), &context)?;
+            // keep the initial slate around so it can be resent if it never arrives
+            batch.store_slate(&slate.id.to_string(), &slate)?;
             batch.commit()?;
         }
 
@@ -307,6 +359,23 @@ where
         }
     }
 
+    /// Whether a slate of ours was finalized already, which is the case when we have
+    /// a log entry and a stored transaction for it but its private context is gone. A
+    /// received slate we only signed has no stored transaction, we never see it final
+    pub fn is_finalized(&self, slate_id: &Uuid) -> Result<bool, Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        let has_tx = updater::retrieve_txs(&mut *w, None, Some(*slate_id), None, false)?
+            .iter()
+            .any(|t| {
+                t.tx_type == TxLogEntryType::TxSent || t.tx_type == TxLogEntryType::TxReceived
+            });
+        let has_stored_tx = w.get_stored_tx(&slate_id.to_string()).is_ok();
+        let has_context = w.get_private_context(&slate_id.to_string()).is_ok();
+        w.close()?;
+        Ok(has_tx && has_stored_tx && !has_context)
+    }
+
     pub fn cancel_tx(
         &mut self,
         tx_id: Option<u32>,
@@ -325,11 +394,118 @@ where
         Ok(())
     }
 
+    /// The number of transaction log entries, across all accounts
+    pub fn tx_log_count(&self) -> Result<usize, Error> {
+        let w = self.wallet.lock();
+        Ok(w.tx_logs().count())
+    }
+
+    pub fn set_tx_note(&mut self, tx_id: u32, note: &str) -> Result<(), Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        let parent_key_id = w.get_parent_key_id();
+        tx::set_tx_note(&mut *w, &parent_key_id, tx_id, note)?;
+        w.close()?;
+        Ok(())
+    }
+
+    pub fn set_tx_expiry(&mut self, tx_id: u32, expires_at: DateTime<Utc>) -> Result<(), Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        let parent_key_id = w.get_parent_key_id();
+        tx::set_tx_expiry(&mut *w, &parent_key_id, tx_id, expires_at)?;
+        w.close()?;
+        Ok(())
+    }
+
+    pub fn set_tx_fluff(&mut self, tx_id: u32) -> Result<(), Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        let parent_key_id = w.get_parent_key_id();
+        tx::set_tx_fluff(&mut *w, &parent_key_id, tx_id)?;
+        w.close()?;
+        Ok(())
+    }
+
+    /// Whether the send of a slate was marked to be fluffed
+    pub fn is_fluff(&self, slate_id: &Uuid) -> Result<bool, Error> {
+        let w = self.wallet.lock();
+        let fluff = w
+            .tx_logs()
+            .any(|tx| tx.tx_slate_id == Some(slate_id.clone()) && tx.fluff);
+        Ok(fluff)
+    }
+
+    pub fn log_tx_state(&mut self, slate_id: &Uuid, state: TxState) -> Result<(), Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        tx::log_tx_state(&mut *w, slate_id, state)?;
+        w.close()?;
+        Ok(())
+    }
+
     pub fn get_stored_tx(&self, uuid: &str) -> Result<Transaction, Error> {
         let w = self.wallet.lock();
         w.get_stored_tx(uuid)
     }
 
+    pub fn get_stored_slate(&self, uuid: &str) -> Result<Slate, Error> {
+        let w = self.wallet.lock();
+        w.get_stored_slate(uuid)
+    }
+
+    pub fn store_pending_slate(&self, pending: &PendingSlate) -> Result<(), Error> {
+        let w = self.wallet.lock();
+        let mut batch = w.batch()?;
+        batch.store_pending_slate(pending)?;
+        batch.commit()?;
+        Ok(())
+    }
+
+    pub fn get_pending_slate(&self, uuid: &str) -> Result<PendingSlate, Error> {
+        let w = self.wallet.lock();
+        w.get_pending_slate(uuid)
+    }
+
+    pub fn delete_pending_slate(&self, uuid: &str) -> Result<(), Error> {
+        let w = self.wallet.lock();
+        let mut batch = w.batch()?;
+        batch.delete_pending_slate(uuid)?;
+        batch.commit()?;
+        Ok(())
+    }
+
+    pub fn pending_slates(&self) -> Result<Vec<PendingSlate>, Error> {
+        let w = self.wallet.lock();
+        w.pending_slates()
+    }
+
+    /// Drops queued slates that have been waiting for longer than the ttl
+    pub fn expire_pending_slates(&self, ttl: Duration) -> Result<(), Error> {
+        let mut w = self.wallet.lock();
+        let expired: Vec<PendingSlate> = w
+            .pending_slates()?
+            .into_iter()
+            .filter(|p| p.is_expired(ttl))
+            .collect();
+        for pending in expired {
+            // a response to one of our own sends still has its outputs locked, as on reject
+            if pending.is_response() {
+                let slate_id = pending.slate.id;
+                let sent = updater::retrieve_txs(&mut *w, None, Some(slate_id), None, false)?
+                    .into_iter()
+                    .find(|tx| tx.tx_type == TxLogEntryType::TxSent && !tx.confirmed);
+                if let Some(sent) = sent {
+                    tx::cancel_tx(&mut *w, &sent.parent_key_id, None, Some(slate_id))?;
+                }
+            }
+            let mut batch = w.batch()?;
+            batch.delete_pending_slate(&pending.slate.id.to_string())?;
+            batch.commit()?;
+        }
+        Ok(())
+    }
+
     pub fn post_tx(&self, tx: &Transaction, fluff: bool) -> Result<(), Error> {
         let tx_hex = grin_util::to_hex(ser::ser_vec(tx).unwrap());
         let client = {
@@ -340,16 +516,49 @@ where
         Ok(())
     }
 
+    pub fn export_signing_key(&mut self, slate_id: &Uuid) -> Result<SigningKey, Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        let res = tx::export_signing_key(&mut *w, slate_id);
+        w.close()?;
+        res
+    }
+
     pub fn verify_slate_messages(&mut self, slate: &Slate) -> Result<(), Error> {
         let secp = Secp256k1::with_caps(ContextFlag::VerifyOnly);
         slate.verify_messages(&secp)?;
         Ok(())
     }
 
-    pub fn restore(&mut self) -> Result<(), Error> {
+    pub fn restore(&mut self, max_retries: u32) -> Result<RestoreEnd, Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        let res = w.restore(max_retries);
+        w.close()?;
+        res
+    }
+
+    pub fn import_outputs(&mut self, outputs: Vec<OutputData>) -> Result<(usize, usize), Error> {
         let mut w = self.wallet.lock();
         w.open_with_credentials()?;
-        let res = w.restore();
+        let res = restore::import_outputs(&mut *w, outputs);
+        w.close()?;
+        res
+    }
+
+    pub fn prune_cancelled_txs(&mut self, cutoff: DateTime<Utc>) -> Result<usize, Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        let parent_key_id = w.get_parent_key_id();
+        let res = updater::prune_cancelled_txs(&mut *w, &parent_key_id, cutoff);
+        w.close()?;
+        res
+    }
+
+    pub fn pruned_tx_count(&self) -> Result<u64, Error> {
+        let mut w = self.wallet.lock();
+        w.open_with_credentials()?;
+        let res = w.get_pruned_tx_count();
         w.close()?;
         res
     }
@@ -577,3 +786,71 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::backend::{test_backend, MemoryStore};
+
+    use super::super::types::{Context, SecretKey};
+
+    #[test]
+    fn tells_a_finalized_slate() {
+        let wallet = Arc::new(Mutex::new(test_backend(MemoryStore::default())));
+        let api = Wallet713OwnerAPI::new(wallet.clone());
+        let slate_id = Uuid::new_v4();
+        assert!(!api.is_finalized(&slate_id).unwrap());
+
+        // a send waiting for the response keeps its context until it is finalized
+        {
+            let w = wallet.lock();
+            let secp = Secp256k1::with_caps(ContextFlag::Full);
+            let sec_key = SecretKey::from_slice(&secp, &[1; 32]).unwrap();
+            let mut t = TxLogEntry::new(w.get_parent_key_id(), TxLogEntryType::TxSent, 0);
+            t.tx_slate_id = Some(slate_id);
+            let mut batch = w.batch().unwrap();
+            batch.save_tx_log_entry(&t).unwrap();
+            batch
+                .store_tx(&slate_id.to_string(), &Transaction::empty())
+                .unwrap();
+            batch
+                .save_private_context(
+                    &slate_id.to_string(),
+                    &Context::new(&secp, sec_key, ContextType::Tx),
+                )
+                .unwrap();
+            batch.commit().unwrap();
+        }
+        assert!(!api.is_finalized(&slate_id).unwrap());
+
+        {
+            let w = wallet.lock();
+            let mut batch = w.batch().unwrap();
+            batch.delete_private_context(&slate_id.to_string()).unwrap();
+            batch.commit().unwrap();
+        }
+        assert!(api.is_finalized(&slate_id).unwrap());
+
+        // signing a received slate leaves no context, but the round trip isn't done
+        let slate_id = Uuid::new_v4();
+        {
+            let w = wallet.lock();
+            let mut t = TxLogEntry::new(w.get_parent_key_id(), TxLogEntryType::TxReceived, 0);
+            t.tx_slate_id = Some(slate_id);
+            let mut batch = w.batch().unwrap();
+            batch.save_tx_log_entry(&t).unwrap();
+            batch.commit().unwrap();
+        }
+        assert!(!api.is_finalized(&slate_id).unwrap());
+
+        {
+            let w = wallet.lock();
+            let mut batch = w.batch().unwrap();
+            batch
+                .store_tx(&slate_id.to_string(), &Transaction::empty())
+                .unwrap();
+            batch.commit().unwrap();
+        }
+        assert!(api.is_finalized(&slate_id).unwrap());
+    }
+}
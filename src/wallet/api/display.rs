@@ -1,14 +1,18 @@
 use prettytable;
+use std::collections::HashMap;
 use std::io::prelude::Write;
 use term;
 
-use grin_core::core::{self, amount_to_hr_string};
 use grin_core::global;
 use grin_util::secp::pedersen;
 
 use super::types::{
-    AcctPathMapping, Arc, Error, Mutex, OutputData, OutputStatus, TxLogEntry, WalletInfo,
+    AcctPathMapping, Arc, CachedBalance, Error, ExtendedPubKey, Mutex, OutputData, OutputStatus,
+    PendingSlate, PublicKey, Transaction, TxLogEntry, WalletInfo,
 };
+use crate::common::crypto::Hex;
+use crate::common::time::format_time;
+use crate::common::{amount_to_string, use_color};
 use crate::contacts::AddressBook;
 
 /// Display outputs in a pretty way
@@ -51,7 +55,13 @@ pub fn outputs(
         };
         let height = format!("{}", out.height);
         let lock_height = format!("{}", out.lock_height);
-        let is_coinbase = format!("{}", out.is_coinbase);
+        // a coinbase can't be spent before its lock height, tell how far off that is
+        let is_coinbase = match out.is_coinbase {
+            true if out.lock_height > cur_height => {
+                format!("true, matures in {} blocks", out.lock_height - cur_height)
+            }
+            is_coinbase => format!("{}", is_coinbase),
+        };
 
         // Mark unconfirmed coinbase outputs as "Mining" instead of "Unconfirmed"
         let status = match out.status {
@@ -60,7 +70,7 @@ pub fn outputs(
         };
 
         let num_confirmations = format!("{}", out.num_confirmations(cur_height));
-        let value = format!("{}", core::amount_to_hr_string(out.value, false));
+        let value = format!("{}", amount_to_string(out.value, false));
         let tx = match out.tx_log_entry {
             None => "".to_owned(),
             Some(t) => t.to_string(),
@@ -94,7 +104,7 @@ pub fn outputs(
     }
 
     table.set_format(*prettytable::format::consts::FORMAT_NO_COLSEP);
-    table.printstd();
+    print_table(&table);
     println!();
 
     if !validated {
@@ -113,9 +123,12 @@ pub fn txs(
     cur_height: u64,
     validated: bool,
     txs: Vec<(TxLogEntry, bool)>,
+    confirmations: &HashMap<u32, u64>,
+    confirmation_target: u64,
     include_status: bool,
     dark_background_color_scheme: bool,
     address_book: Option<Arc<Mutex<AddressBook>>>,
+    absolute_time: bool,
 ) -> Result<(), Error> {
     let title = format!(
         "Transaction Log - Account '{}' - Block Height: {}",
@@ -139,6 +152,8 @@ pub fn txs(
         bMG->"Confirmation Time",
         bMG->"Net \nDifference",
         bMG->"Proof?",
+        bMG->"Messages",
+        bMG->"Note",
     ]);
 
     for (t, has_proof) in txs {
@@ -164,27 +179,36 @@ pub fn txs(
             None => String::from(""),
         };
         let entry_type = format!("{}", t.tx_type);
-        let creation_ts = format!("{}", t.creation_ts.format("%Y-%m-%d %H:%M:%S"));
-        let confirmation_ts = match t.confirmation_ts {
-            Some(m) => format!("{}", m.format("%Y-%m-%d %H:%M:%S")),
-            None => String::from(""),
+        let creation_ts = format_time(&t.creation_ts, absolute_time);
+        let confirmation_ts = match (t.confirmation_ts, t.expires_at) {
+            (Some(m), _) => format_time(&m, absolute_time),
+            (None, Some(expires_at)) if !t.confirmed => {
+                format!("expires {}", format_time(&expires_at, absolute_time))
+            }
+            _ => String::from(""),
         };
-        let confirmed = match t.confirmed {
-            true => "yes",
-            false => "",
+        let confirmed = match confirmations.get(&t.id) {
+            Some(count) => format!("{}/{}", count, confirmation_target),
+            None if t.confirmed => String::from("yes"),
+            None => String::new(),
         };
         let net_diff = if t.amount_credited >= t.amount_debited {
-            core::amount_to_hr_string(t.amount_credited - t.amount_debited, true)
+            amount_to_string(t.amount_credited - t.amount_debited, true)
         } else {
             format!(
                 "-{}",
-                core::amount_to_hr_string(t.amount_debited - t.amount_credited, true)
+                amount_to_string(t.amount_debited - t.amount_credited, true)
             )
         };
         let proof = match has_proof {
             true => "yes",
             false => "",
         };
+        let messages = match t.messages {
+            Some(ref m) => m.join("\n"),
+            None => String::from(""),
+        };
+        let note = t.note.clone().unwrap_or(String::new());
         if dark_background_color_scheme {
             table.add_row(row![
                 bFC->id,
@@ -196,6 +220,8 @@ pub fn txs(
                 bFB->confirmation_ts,
                 bFY->net_diff,
                 bFG->proof,
+                bFW->messages,
+                bFW->note,
             ]);
         } else {
             if t.confirmed {
@@ -209,6 +235,8 @@ pub fn txs(
                     bFB->confirmation_ts,
                     bFG->net_diff,
                     bFg->proof,
+                    bFD->messages,
+                    bFD->note,
                 ]);
             } else {
                 table.add_row(row![
@@ -221,13 +249,15 @@ pub fn txs(
                     bFB->confirmation_ts,
                     bFG->net_diff,
                     bFR->proof,
+                    bFD->messages,
+                    bFD->note,
                 ]);
             }
         }
     }
 
     table.set_format(*prettytable::format::consts::FORMAT_NO_COLSEP);
-    table.printstd();
+    print_table(&table);
     println!();
 
     if !validated && include_status {
@@ -240,6 +270,119 @@ pub fn txs(
     Ok(())
 }
 
+/// Display the full details of a single transaction in a pretty way
+pub fn tx(
+    account: &str,
+    cur_height: u64,
+    validated: bool,
+    entry: &TxLogEntry,
+    stored_tx: Option<&Transaction>,
+    absolute_time: bool,
+    log: bool,
+) {
+    println!(
+        "\n____ Transaction {} - Account '{}' - Block Height: {} ____\n",
+        entry.id, account, cur_height,
+    );
+
+    let mut table = table!();
+    table.add_row(row![bFC->"Type", FC->format!("{}", entry.tx_type)]);
+    if let Some(slate_id) = entry.tx_slate_id {
+        table.add_row(row![bFC->"Slate Id", FB->slate_id.to_string()]);
+    }
+    if let Some(ref address) = entry.address {
+        table.add_row(row![bFC->"Address", FC->address]);
+    }
+    table.add_row(row![
+        bFC->"Creation Time",
+        FB->format_time(&entry.creation_ts, absolute_time)
+    ]);
+    let confirmed = match entry.confirmation_ts {
+        Some(ts) if entry.confirmed => format!("yes ({})", format_time(&ts, absolute_time)),
+        _ if entry.confirmed => String::from("yes"),
+        _ => String::from("no"),
+    };
+    table.add_row(row![bFC->"Confirmed?", FG->confirmed]);
+    if let Some(expires_at) = entry.expires_at {
+        if !entry.confirmed {
+            table.add_row(row![bFC->"Expires", FY->format_time(&expires_at, absolute_time)]);
+        }
+    }
+    table.add_row(row![
+        bFC->"Amount Credited",
+        FG->amount_to_string(entry.amount_credited, false)
+    ]);
+    table.add_row(row![
+        bFC->"Amount Debited",
+        FY->amount_to_string(entry.amount_debited, false)
+    ]);
+    if let Some(fee) = entry.fee {
+        table.add_row(row![bFC->"Fee", FY->amount_to_string(fee, false)]);
+    }
+    if let Some(ref messages) = entry.messages {
+        table.add_row(row![bFC->"Messages", FW->messages.join("\n")]);
+    }
+    if let Some(ref note) = entry.note {
+        table.add_row(row![bFC->"Note", FW->note]);
+    }
+
+    if let Some(tx) = stored_tx {
+        for kernel in tx.kernels() {
+            table.add_row(row![
+                bFC->"Kernel Excess",
+                FM->grin_util::to_hex(kernel.excess.0.to_vec())
+            ]);
+            table.add_row(row![bFC->"Kernel Fee", FY->amount_to_string(kernel.fee, false)]);
+            table.add_row(row![bFC->"Lock Height", FB->kernel.lock_height.to_string()]);
+        }
+        let inputs: Vec<String> = tx
+            .inputs()
+            .iter()
+            .map(|i| grin_util::to_hex(i.commit.0.to_vec()))
+            .collect();
+        table.add_row(row![bFC->"Inputs", FM->inputs.join("\n")]);
+        let outputs: Vec<String> = tx
+            .outputs()
+            .iter()
+            .map(|o| grin_util::to_hex(o.commit.0.to_vec()))
+            .collect();
+        table.add_row(row![bFC->"Outputs", FM->outputs.join("\n")]);
+    }
+
+    table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    print_table(&table);
+    println!();
+
+    if stored_tx.is_none() {
+        println!("NOTE: no transaction data stored for this transaction, kernel details are unavailable.");
+    }
+
+    if log {
+        if entry.state_log.is_empty() {
+            println!("NOTE: no states recorded for this transaction, it predates the state log.");
+        } else {
+            let mut table = table!();
+            table.set_titles(row![bMG->"State", bMG->"Time"]);
+            for change in &entry.state_log {
+                table.add_row(row![
+                    FC->change.state.to_string(),
+                    FB->format_time(&change.at, absolute_time)
+                ]);
+            }
+            table.set_format(*prettytable::format::consts::FORMAT_NO_COLSEP);
+            print_table(&table);
+            println!();
+        }
+    }
+    if !validated {
+        println!(
+            "\nWARNING: Wallet failed to verify data. \
+             The above is from local cache and possibly invalid! \
+             (is your `grin server` offline or broken?)"
+        );
+    }
+}
+
 /// Display summary info in a pretty way
 pub fn info(
     account: &str,
@@ -257,23 +400,23 @@ pub fn info(
     if dark_background_color_scheme {
         table.add_row(row![
             bFG->"Total",
-            FG->amount_to_hr_string(wallet_info.total, false)
+            FG->amount_to_string(wallet_info.total, false)
         ]);
         // Only dispay "Immature Coinbase" if we have related outputs in the wallet.
         // This row just introduces confusion if the wallet does not receive coinbase rewards.
         if wallet_info.amount_immature > 0 {
             table.add_row(row![
                 bFY->format!("Immature Coinbase (< {})", global::coinbase_maturity()),
-                FY->amount_to_hr_string(wallet_info.amount_immature, false)
+                FY->amount_to_string(wallet_info.amount_immature, false)
             ]);
         }
         table.add_row(row![
             bFY->format!("Awaiting Confirmation (< {})", wallet_info.minimum_confirmations),
-            FY->amount_to_hr_string(wallet_info.amount_awaiting_confirmation, false)
+            FY->amount_to_string(wallet_info.amount_awaiting_confirmation, false)
         ]);
         table.add_row(row![
             Fr->"Locked by previous transaction",
-            Fr->amount_to_hr_string(wallet_info.amount_locked, false)
+            Fr->amount_to_string(wallet_info.amount_locked, false)
         ]);
         table.add_row(row![
             Fw->"--------------------------------",
@@ -281,28 +424,28 @@ pub fn info(
         ]);
         table.add_row(row![
             bFG->"Currently Spendable",
-            FG->amount_to_hr_string(wallet_info.amount_currently_spendable, false)
+            FG->amount_to_string(wallet_info.amount_currently_spendable, false)
         ]);
     } else {
         table.add_row(row![
             bFG->"Total",
-            FG->amount_to_hr_string(wallet_info.total, false)
+            FG->amount_to_string(wallet_info.total, false)
         ]);
         // Only dispay "Immature Coinbase" if we have related outputs in the wallet.
         // This row just introduces confusion if the wallet does not receive coinbase rewards.
         if wallet_info.amount_immature > 0 {
             table.add_row(row![
                 bFB->format!("Immature Coinbase (< {})", global::coinbase_maturity()),
-                FB->amount_to_hr_string(wallet_info.amount_immature, false)
+                FB->amount_to_string(wallet_info.amount_immature, false)
             ]);
         }
         table.add_row(row![
             bFB->format!("Awaiting Confirmation (< {})", wallet_info.minimum_confirmations),
-            FB->amount_to_hr_string(wallet_info.amount_awaiting_confirmation, false)
+            FB->amount_to_string(wallet_info.amount_awaiting_confirmation, false)
         ]);
         table.add_row(row![
             Fr->"Locked by previous transaction",
-            Fr->amount_to_hr_string(wallet_info.amount_locked, false)
+            Fr->amount_to_string(wallet_info.amount_locked, false)
         ]);
         table.add_row(row![
             Fw->"--------------------------------",
@@ -310,11 +453,11 @@ pub fn info(
         ]);
         table.add_row(row![
             bFG->"Currently Spendable",
-            FG->amount_to_hr_string(wallet_info.amount_currently_spendable, false)
+            FG->amount_to_string(wallet_info.amount_currently_spendable, false)
         ]);
     };
     table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-    table.printstd();
+    print_table(&table);
     println!();
     if !validated {
         println!(
@@ -325,6 +468,151 @@ pub fn info(
     }
 }
 
+/// Display a cached summary, noting how old it is
+pub fn cached_info(cached: &CachedBalance, dark_background_color_scheme: bool) {
+    info(
+        &cached.account,
+        &cached.info,
+        cached.validated,
+        dark_background_color_scheme,
+    );
+    println!(
+        "\nNOTE: cached balance from {}, not refreshed from the node. run `info` for live data.",
+        format_time(&cached.updated_at, false)
+    );
+}
+
+/// Display the summary info of several accounts side by side, with their sum
+pub fn accounts_info(
+    infos: &Vec<(String, WalletInfo)>,
+    validated: bool,
+    dark_background_color_scheme: bool,
+) {
+    let height = infos
+        .iter()
+        .map(|(_, info)| info.last_confirmed_height)
+        .max()
+        .unwrap_or(0);
+    println!(
+        "\n____ Wallet Summary Info - All Accounts as of height {} ____\n",
+        height
+    );
+
+    let mut table = table!();
+    table.set_titles(row![
+        bMG->"Account",
+        bMG->"Total",
+        bMG->"Awaiting Confirmation",
+        bMG->"Immature Coinbase",
+        bMG->"Locked",
+        bMG->"Currently Spendable",
+    ]);
+
+    let mut sum = vec![0u64; 5];
+    for (account, info) in infos {
+        let amounts = vec![
+            info.total,
+            info.amount_awaiting_confirmation,
+            info.amount_immature,
+            info.amount_locked,
+            info.amount_currently_spendable,
+        ];
+        for (sum, amount) in sum.iter_mut().zip(amounts.iter()) {
+            *sum += amount;
+        }
+        add_accounts_info_row(&mut table, account, &amounts, dark_background_color_scheme);
+    }
+    if infos.len() > 1 {
+        add_accounts_info_row(&mut table, "(all)", &sum, dark_background_color_scheme);
+    }
+
+    table.set_format(*prettytable::format::consts::FORMAT_NO_COLSEP);
+    print_table(&table);
+    println!();
+    if !validated {
+        println!(
+            "\nWARNING: Wallet failed to verify data against a live chain. \
+             The above is from local cache and only valid up to the given height! \
+             (is your `grin server` offline or broken?)"
+        );
+    }
+}
+
+fn add_accounts_info_row(
+    table: &mut prettytable::Table,
+    account: &str,
+    amounts: &Vec<u64>,
+    dark_background_color_scheme: bool,
+) {
+    let amounts: Vec<String> = amounts
+        .iter()
+        .map(|a| amount_to_string(*a, false))
+        .collect();
+    if dark_background_color_scheme {
+        table.add_row(row![
+            bFC->account,
+            FG->amounts[0],
+            FY->amounts[1],
+            FY->amounts[2],
+            Fr->amounts[3],
+            bFG->amounts[4],
+        ]);
+    } else {
+        table.add_row(row![
+            bFD->account,
+            FG->amounts[0],
+            FB->amounts[1],
+            FB->amounts[2],
+            Fr->amounts[3],
+            bFG->amounts[4],
+        ]);
+    }
+}
+
+/// Display watch-only summary info in a pretty way
+pub fn watch_info(account: &str, xpub: &str, cur_height: u64, total: u64, unspent: usize) {
+    println!(
+        "\n____ Watch-only Summary Info - Account '{}' as of height {} ____\n",
+        account, cur_height,
+    );
+
+    let mut table = table!();
+    table.add_row(row![
+        bFC->"Account xpub",
+        FC->xpub
+    ]);
+    table.add_row(row![
+        bFG->"Total",
+        FG->amount_to_string(total, false)
+    ]);
+    table.add_row(row![
+        bFB->"Unspent outputs",
+        FB->unspent.to_string()
+    ]);
+    table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    print_table(&table);
+    println!();
+    println!("NOTE: this is a watch-only view. it can't be used to spend from the wallet.");
+}
+
+/// Display the public keys of the wallet and its active account
+pub fn public_keys(account: &str, path: &str, root: &PublicKey, xpub: &ExtendedPubKey) {
+    println!("\n____ Wallet Public Keys - Account '{}' ____\n", account);
+    let mut table = table!();
+    table.add_row(row![
+        bFC->"Root public key",
+        FC->root.to_hex()
+    ]);
+    table.add_row(row![
+        bFC->format!("Account xpub ({})", path),
+        FC->xpub.to_string()
+    ]);
+    table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    print_table(&table);
+    println!();
+    println!("NOTE: these keys are public, they can't be used to spend from the wallet.");
+}
+
 /// Display list of wallet accounts in a pretty way
 pub fn accounts(acct_mappings: Vec<AcctPathMapping>) {
     println!("\n____ Wallet Accounts ____\n",);
@@ -341,6 +629,66 @@ pub fn accounts(acct_mappings: Vec<AcctPathMapping>) {
         ]);
     }
     table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-    table.printstd();
+    print_table(&table);
     println!();
 }
+
+/// Display the slates waiting for `accept` or `reject` in a pretty way
+pub fn pending_slates(
+    pending: Vec<PendingSlate>,
+    address_book: Option<Arc<Mutex<AddressBook>>>,
+    absolute_time: bool,
+) {
+    println!("\n____ Pending Slates ____\n",);
+    if pending.len() == 0 {
+        println!("no slates are waiting for approval.");
+        println!();
+        return;
+    }
+
+    let mut table = table!();
+
+    table.set_titles(row![
+        bMG->"Slate Id",
+        bMG->"Type",
+        bMG->"From",
+        bMG->"Amount",
+        bMG->"Received",
+    ]);
+    for p in pending {
+        let entry_type = if p.is_response() {
+            "finalize"
+        } else if p.slate.tx.inputs().len() == 0 {
+            "invoice"
+        } else {
+            "receive"
+        };
+        let from = match address_book {
+            Some(ref address_book) => match address_book.lock().get_contact_by_address(&p.from) {
+                Ok(contact) => format!("@{}", contact.get_name()),
+                Err(_) => p.from.clone(),
+            },
+            None => p.from.clone(),
+        };
+        table.add_row(row![
+            bFC->p.slate.id.to_string(),
+            bFC->entry_type,
+            bFC->from,
+            bFG->amount_to_string(p.slate.amount, false),
+            bFB->format_time(&p.received_at, absolute_time),
+        ]);
+    }
+    table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    print_table(&table);
+    println!();
+}
+
+/// Prints a table to stdout, colored only when colored output is on
+fn print_table(table: &prettytable::Table) {
+    match unsafe { use_color() } {
+        true => table.print_tty(true),
+        false => {
+            let _ = table.print(&mut std::io::stdout());
+        }
+    }
+}
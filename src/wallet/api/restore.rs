@@ -1,4 +1,7 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
 
 use grin_core::global;
 use grin_core::libtx::proof;
@@ -6,11 +9,26 @@ use grin_util::secp::pedersen;
 
 use super::keys;
 use super::types::{
-    ErrorKind, ExtKeychain, Identifier, Keychain, NodeClient, OutputData, OutputStatus, Result,
-    SecretKey, TxLogEntry, TxLogEntryType, WalletBackend,
+    Error, ErrorKind, ExtKeychain, Identifier, Keychain, NodeClient, OutputData, OutputStatus,
+    RestoreEnd, Result, SecretKey, TxLogEntry, TxLogEntryType, WalletBackend,
 };
 use super::updater;
 
+static RESTORE_RUNNING: AtomicBool = AtomicBool::new(false);
+static RESTORE_INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// Retries back off up to 2^5 = 32 seconds between attempts
+const MAX_BACKOFF_EXPONENT: u32 = 5;
+
+/// Asks a running restore to stop after the current batch.
+/// Returns false if there is no restore in progress.
+pub fn interrupt_restore() -> bool {
+    if !RESTORE_RUNNING.load(Ordering::SeqCst) {
+        return false;
+    }
+    RESTORE_INTERRUPTED.store(true, Ordering::SeqCst);
+    true
+}
+
 #[derive(Clone)]
 struct OutputResult {
     pub commit: pedersen::Commitment,
@@ -87,19 +105,65 @@ where
     Ok(wallet_outputs)
 }
 
-fn collect_chain_outputs<T, C, K>(wallet: &mut T) -> Result<Vec<OutputResult>>
+/// Why a scan of the chain stopped before its end
+enum ScanStop {
+    /// The scan was asked to stop, and can resume from this index
+    Interrupted(u64),
+    /// The node kept failing at this index, with the last error it gave
+    NodeFailed(u64, Error),
+}
+
+/// Scans the chain from the given pmmr index. A batch the node fails to return is
+/// retried up to `max_retries` times, waiting longer each time. If interrupted or
+/// out of retries, also returns why it stopped and the index to resume from.
+fn collect_chain_outputs<T, C, K>(
+    wallet: &mut T,
+    mut start_index: u64,
+    max_retries: u32,
+) -> Result<(Vec<OutputResult>, Option<ScanStop>)>
 where
     T: WalletBackend<C, K>,
     C: NodeClient,
     K: Keychain,
 {
     let batch_size = 1000;
-    let mut start_index = 1;
     let mut result_vec: Vec<OutputResult> = vec![];
+    let mut retries = 0;
     loop {
-        let (highest_index, last_retrieved_index, outputs) = wallet
+        let fetched = wallet
             .w2n_client()
-            .get_outputs_by_pmmr_index(start_index, batch_size)?;
+            .get_outputs_by_pmmr_index(start_index, batch_size);
+        let (highest_index, last_retrieved_index, outputs) = match fetched {
+            Ok(fetched) => {
+                retries = 0;
+                fetched
+            }
+            Err(e) => {
+                if RESTORE_INTERRUPTED.load(Ordering::SeqCst) {
+                    return Ok((result_vec, Some(ScanStop::Interrupted(start_index))));
+                }
+                if retries >= max_retries {
+                    warn!(
+                        "Node failed {} retries, next index to scan: {}",
+                        retries, start_index
+                    );
+                    let stop = ScanStop::NodeFailed(start_index, e.into());
+                    return Ok((result_vec, Some(stop)));
+                }
+                retries += 1;
+                let secs = 2u64.pow(std::cmp::min(retries - 1, MAX_BACKOFF_EXPONENT));
+                cli_message!(
+                    "node failed to return outputs from index {}, retry {}/{} in {}s: {}",
+                    start_index,
+                    retries,
+                    max_retries,
+                    secs,
+                    e
+                );
+                thread::sleep(Duration::from_secs(secs));
+                continue;
+            }
+        };
         warn!(
             "Checking {} outputs, up to index {}. (Highest index: {})",
             outputs.len(),
@@ -113,14 +177,44 @@ where
             break;
         }
         start_index = last_retrieved_index + 1;
+
+        if RESTORE_INTERRUPTED.load(Ordering::SeqCst) {
+            warn!("Restore interrupted, next index to scan: {}", start_index);
+            return Ok((result_vec, Some(ScanStop::Interrupted(start_index))));
+        }
     }
-    Ok(result_vec)
+    Ok((result_vec, None))
+}
+
+/// Entries of the transactions the wallet has stored, by the commitments of the
+/// outputs each created, so the outputs found on chain can be matched back to them
+fn stored_tx_entries<T, C, K>(wallet: &T) -> HashMap<pedersen::Commitment, TxLogEntry>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let mut entries = HashMap::new();
+    for entry in wallet.tx_logs() {
+        let tx = match entry.tx_slate_id {
+            Some(id) => match wallet.get_stored_tx(&id.to_string()) {
+                Ok(tx) => tx,
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+        for output in tx.outputs() {
+            entries.insert(output.commit, entry.clone());
+        }
+    }
+    entries
 }
 
 ///
 fn restore_missing_output<T, C, K>(
     wallet: &mut T,
     output: OutputResult,
+    known_entries: &HashMap<pedersen::Commitment, TxLogEntry>,
     found_parents: &mut HashMap<Identifier, u32>,
     tx_stats: &mut Option<&mut HashMap<Identifier, RestoredTxStats>>,
 ) -> Result<()>
@@ -147,7 +241,20 @@ where
         }
     }
 
-    let log_id = if tx_stats.is_none() || output.is_coinbase {
+    // linking the output back to its transaction keeps the note of the entry, and
+    // doesn't list the same funds twice
+    let known_entry = known_entries
+        .get(&output.commit)
+        .filter(|t| t.parent_key_id == parent_key_id);
+    let log_id = if let Some(entry) = known_entry {
+        if !entry.confirmed {
+            let mut entry = entry.clone();
+            entry.confirmed = true;
+            entry.update_confirmation_ts();
+            batch.save_tx_log_entry(&entry)?;
+        }
+        entry.id
+    } else if tx_stats.is_none() || output.is_coinbase {
         let log_id = batch.next_tx_log_id(&parent_key_id)?;
         let entry_type = match output.is_coinbase {
             true => TxLogEntryType::ConfirmedCoinbase,
@@ -249,7 +356,11 @@ where
 {
     // First, get a definitive list of outputs we own from the chain
     warn!("Starting wallet check.");
-    let chain_outs = collect_chain_outputs(wallet)?;
+    // a check has no progress to save, so it fails on the first node error
+    let (chain_outs, stop) = collect_chain_outputs(wallet, 1, 0)?;
+    if let Some(ScanStop::NodeFailed(_, e)) = stop {
+        return Err(e);
+    }
     warn!(
         "Identified {} wallet_outputs as belonging to this wallet",
         chain_outs.len(),
@@ -298,6 +409,7 @@ where
     }
 
     let mut found_parents: HashMap<Identifier, u32> = HashMap::new();
+    let known_entries = stored_tx_entries(wallet);
 
     // Restore missing outputs, adding transaction for it back to the log
     for m in missing_outs.into_iter() {
@@ -306,7 +418,7 @@ where
              Restoring.",
             m.value, m.key_id, m.commit,
         );
-        restore_missing_output(wallet, m, &mut found_parents, &mut None)?;
+        restore_missing_output(wallet, m, &known_entries, &mut found_parents, &mut None)?;
     }
 
     // Unlock locked outputs
@@ -361,21 +473,35 @@ where
 }
 
 /// Restore a wallet
-pub fn restore<T, C, K>(wallet: &mut T) -> Result<()>
+pub fn restore<T, C, K>(wallet: &mut T, max_retries: u32) -> Result<RestoreEnd>
 where
     T: WalletBackend<C, K>,
     C: NodeClient,
     K: Keychain,
 {
-    // Don't proceed if wallet_data has anything in it
+    // A previously interrupted restore is resumed where it stopped,
+    // otherwise don't proceed if wallet_data has anything in it
+    let resume_index = wallet.get_restore_index()?;
     let is_empty = wallet.outputs().next().is_none();
-    if !is_empty {
+    if resume_index.is_none() && !is_empty {
         return Err(ErrorKind::WalletShouldBeEmpty.into());
     }
 
     warn!("Starting restore.");
 
-    let result_vec = collect_chain_outputs(wallet)?;
+    RESTORE_INTERRUPTED.store(false, Ordering::SeqCst);
+    RESTORE_RUNNING.store(true, Ordering::SeqCst);
+    let collected = collect_chain_outputs(wallet, resume_index.unwrap_or(1), max_retries);
+    RESTORE_RUNNING.store(false, Ordering::SeqCst);
+    let (result_vec, stop) = collected?;
+    let (next_index, end) = match stop {
+        None => (None, RestoreEnd::Complete),
+        Some(ScanStop::Interrupted(index)) => (Some(index), RestoreEnd::Interrupted),
+        Some(ScanStop::NodeFailed(index, e)) => {
+            warn!("Restore gave up on the node: {}", e);
+            (Some(index), RestoreEnd::GaveUp)
+        }
+    };
 
     warn!(
         "Identified {} wallet_outputs as belonging to this wallet",
@@ -385,11 +511,21 @@ where
     let mut found_parents: HashMap<Identifier, u32> = HashMap::new();
     let mut restore_stats = HashMap::new();
 
+    // keep the child indices found by the earlier part of the scan
+    for output in wallet.outputs() {
+        let max_child_index = found_parents.entry(output.root_key_id.clone()).or_insert(0);
+        if output.n_child > *max_child_index {
+            *max_child_index = output.n_child;
+        }
+    }
+
     // Now save what we have
+    let known_entries = stored_tx_entries(wallet);
     for output in result_vec {
         restore_missing_output(
             wallet,
             output,
+            &known_entries,
             &mut found_parents,
             &mut Some(&mut restore_stats),
         )?;
@@ -397,16 +533,17 @@ where
 
     // restore labels, account paths and child derivation indices
     let label_base = "account";
-    let mut acct_index = 1;
+    let known_paths: Vec<Identifier> = wallet.accounts().map(|a| a.path).collect();
+    let mut acct_index = known_paths.len();
     for (path, max_child_index) in found_parents.iter() {
-        // default path already exists
-        if *path != ExtKeychain::derive_key_id(2, 0, 0, 0, 0) {
+        // default path and accounts restored earlier already exist
+        if !known_paths.contains(path) && *path != ExtKeychain::derive_key_id(2, 0, 0, 0, 0) {
             let label = format!("{}_{}", label_base, acct_index);
             keys::set_acct_path(wallet, &label, path)?;
             acct_index += 1;
         }
-        // restore tx log entry for non-coinbase outputs
-        if let Some(s) = restore_stats.get(path) {
+        // restore tx log entry for non-coinbase outputs not linked to one already
+        if let Some(s) = restore_stats.get(path).filter(|s| s.num_outputs > 0) {
             let mut batch = wallet.batch()?;
             let mut t = TxLogEntry::new(path.clone(), TxLogEntryType::TxReceived, s.log_id);
             t.confirmed = true;
@@ -421,5 +558,97 @@ where
         debug!("Next child for account {} is {}", path, max_child_index + 1);
         batch.commit()?;
     }
-    Ok(())
+
+    let mut batch = wallet.batch()?;
+    match next_index {
+        Some(index) => batch.save_restore_index(index)?,
+        None => batch.delete_restore_index()?,
+    }
+    batch.commit()?;
+    Ok(end)
+}
+
+/// Import outputs from a backup, saving only the ones that are unspent on chain.
+/// Returns the number of imported and rejected outputs.
+pub fn import_outputs<T: ?Sized, C, K>(
+    wallet: &mut T,
+    outputs: Vec<OutputData>,
+) -> Result<(usize, usize)>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let mut candidates = vec![];
+    for output in outputs {
+        // outputs we already know about don't need importing
+        if wallet.get_output(&output.key_id, &output.mmr_index).is_ok() {
+            continue;
+        }
+        let commit = wallet.keychain().commit(output.value, &output.key_id)?;
+        candidates.push((commit, output));
+    }
+
+    let commits = candidates
+        .iter()
+        .map(|(commit, _)| commit.clone())
+        .collect();
+    let api_outputs = wallet.w2n_client().get_outputs_from_node(commits)?;
+
+    let mut found_parents: HashMap<Identifier, u32> = HashMap::new();
+    let mut imported = 0;
+    let mut rejected = 0;
+    for (commit, mut output) in candidates {
+        let (height, mmr_index) = match api_outputs.get(&commit) {
+            Some((_, height, mmr_index)) => (*height, *mmr_index),
+            None => {
+                warn!("Rejecting output {:?}, it is not unspent on chain", commit,);
+                rejected += 1;
+                continue;
+            }
+        };
+
+        let cached_commit = wallet.calc_commit_for_cache(output.value, &output.key_id)?;
+        let mut batch = wallet.batch()?;
+        let log_id = batch.next_tx_log_id(&output.root_key_id)?;
+        let entry_type = match output.is_coinbase {
+            true => TxLogEntryType::ConfirmedCoinbase,
+            false => TxLogEntryType::TxReceived,
+        };
+        let mut t = TxLogEntry::new(output.root_key_id.clone(), entry_type, log_id);
+        t.confirmed = true;
+        t.amount_credited = output.value;
+        t.num_outputs = 1;
+        t.update_confirmation_ts();
+        batch.save_tx_log_entry(&t)?;
+
+        output.commit = cached_commit;
+        output.mmr_index = Some(mmr_index);
+        output.height = height;
+        output.status = OutputStatus::Unspent;
+        output.tx_log_entry = Some(log_id);
+        batch.save_output(&output)?;
+        batch.commit()?;
+
+        let max_child_index = found_parents.entry(output.root_key_id.clone()).or_insert(0);
+        if output.n_child > *max_child_index {
+            *max_child_index = output.n_child;
+        }
+        imported += 1;
+    }
+
+    // make sure newly derived keys don't collide with the imported ones
+    for output in wallet.outputs() {
+        if let Some(max_child_index) = found_parents.get_mut(&output.root_key_id) {
+            if output.n_child > *max_child_index {
+                *max_child_index = output.n_child;
+            }
+        }
+    }
+    let mut batch = wallet.batch()?;
+    for (path, max_child_index) in found_parents.iter() {
+        batch.save_child_index(path, max_child_index + 1)?;
+    }
+    batch.commit()?;
+    Ok((imported, rejected))
 }
@@ -1,13 +1,15 @@
-use grin_core::core::amount_to_hr_string;
-use grin_core::libtx::{build, tx_fee};
+use grin_core::libtx::build;
+use grin_util::to_hex;
 use grin_wallet::Slate;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
 
 use super::keys;
 use super::types::{
-    Context, ContextType, Error, ErrorKind, Identifier, Keychain, NodeClient, OutputData,
-    OutputStatus, Transaction, TxLogEntry, TxLogEntryType, WalletBackend,
+    Context, ContextType, Error, ErrorKind, FeeOverrides, Identifier, Keychain, NodeClient,
+    OutputData, OutputStatus, Transaction, TxLogEntry, TxLogEntryType, TxState, WalletBackend,
 };
+use crate::common::amount_to_string;
 
 pub fn build_send_tx_slate<T: ?Sized, C, K>(
     wallet: &mut T,
@@ -20,7 +22,12 @@ pub fn build_send_tx_slate<T: ?Sized, C, K>(
     max_outputs: usize,
     change_outputs: usize,
     selection_strategy_is_use_all: bool,
+    use_outputs: Option<&Vec<String>>,
+    replaces: Option<u32>,
     parent_key_id: Identifier,
+    change_parent_key_id: Option<Identifier>,
+    allow_unconfirmed_change: bool,
+    fee_overrides: &FeeOverrides,
 ) -> Result<
     (
         Slate,
@@ -34,6 +41,7 @@ where
     C: NodeClient,
     K: Keychain,
 {
+    let change_parent_key_id = change_parent_key_id.unwrap_or(parent_key_id.clone());
     let (elems, inputs, change_amounts_derivations, amount, fee) = select_send_tx(
         wallet,
         amount,
@@ -44,8 +52,18 @@ where
         max_outputs,
         change_outputs,
         selection_strategy_is_use_all,
+        use_outputs,
+        replaces,
         &parent_key_id,
+        &change_parent_key_id,
+        fee_overrides,
     )?;
+    fee_overrides.check_fee(fee, inputs.len(), change_amounts_derivations.len() + 1, 1)?;
+
+    let unconfirmed = unconfirmed_change(wallet, &inputs);
+    if unconfirmed > 0 && !allow_unconfirmed_change {
+        return Err(ErrorKind::UnconfirmedChange(unconfirmed))?;
+    }
 
     // Create public slate
     let mut slate = Slate::blank(num_participants);
@@ -97,6 +115,8 @@ where
         t.tx_slate_id = Some(slate_id);
         t.address = address;
         t.fee = Some(fee);
+        t.replaces = replaces;
+        t.log_state(TxState::Created);
         let mut amount_debited = 0;
         t.num_inputs = lock_inputs.len();
         for id in lock_inputs {
@@ -105,16 +125,35 @@ where
             amount_debited = amount_debited + coin.value;
             batch.lock_output(&mut coin)?;
         }
+        t.log_state(TxState::OutputsLocked);
 
         t.amount_debited = amount_debited;
 
+        // change going to another account is logged there, as received from this send
+        let mut change_entry = None;
+        if change_parent_key_id != parent_key_id && !change_amounts_derivations.is_empty() {
+            let change_log_id = batch.next_tx_log_id(&change_parent_key_id)?;
+            let mut entry = TxLogEntry::new(
+                change_parent_key_id.clone(),
+                TxLogEntryType::TxReceived,
+                change_log_id,
+            );
+            entry.tx_slate_id = Some(slate_id);
+            entry.log_state(TxState::Created);
+            change_entry = Some(entry);
+        }
+
         // write the output representing our change
         for (change_amount, id, _) in &change_amounts_derivations {
-            t.num_outputs += 1;
-            t.amount_credited += change_amount;
+            let entry = match change_entry {
+                Some(ref mut entry) => entry,
+                None => &mut t,
+            };
+            entry.num_outputs += 1;
+            entry.amount_credited += change_amount;
             let commit = commits.get(&id).unwrap().clone();
             batch.save_output(&OutputData {
-                root_key_id: parent_key_id.clone(),
+                root_key_id: change_parent_key_id.clone(),
                 key_id: id.clone(),
                 n_child: id.to_path().last_path_index(),
                 commit,
@@ -124,10 +163,13 @@ where
                 height: current_height,
                 lock_height: 0,
                 is_coinbase: false,
-                tx_log_entry: Some(log_id),
+                tx_log_entry: Some(entry.id),
             })?;
         }
         batch.save_tx_log_entry(&t)?;
+        if let Some(entry) = change_entry {
+            batch.save_tx_log_entry(&entry)?;
+        }
         batch.store_tx(&slate_id.to_string(), &tx)?;
         batch.commit()?;
         Ok(())
@@ -188,6 +230,7 @@ where
         t.address = address;
         t.amount_credited = amount;
         t.num_outputs = 1;
+        t.log_state(TxState::Received);
         batch.save_output(&OutputData {
             root_key_id: parent_key_id.clone(),
             key_id: key_id_inner.clone(),
@@ -208,6 +251,41 @@ where
     Ok((key_id, context, wallet_add_fn))
 }
 
+/// How many of `inputs` are change of a send that isn't in a block yet, which goes
+/// away with the send on a reorg or if the node never accepts it. Change sent to
+/// another account is logged there as received, under the slate id of the send.
+fn unconfirmed_change<T: ?Sized, C, K>(wallet: &T, inputs: &[OutputData]) -> usize
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let txs: Vec<TxLogEntry> = wallet.tx_logs().collect();
+    let sent_slates: HashSet<Uuid> = txs
+        .iter()
+        .filter(|tx| tx.tx_type == TxLogEntryType::TxSent)
+        .filter_map(|tx| tx.tx_slate_id)
+        .collect();
+    inputs
+        .iter()
+        .filter(|input| input.status == OutputStatus::Unconfirmed)
+        .filter(|input| {
+            txs.iter().any(|tx| {
+                Some(tx.id) == input.tx_log_entry
+                    && tx.parent_key_id == input.root_key_id
+                    && match tx.tx_type {
+                        TxLogEntryType::TxSent => true,
+                        TxLogEntryType::TxReceived => match tx.tx_slate_id {
+                            Some(ref slate_id) => sent_slates.contains(slate_id),
+                            None => false,
+                        },
+                        _ => false,
+                    }
+            })
+        })
+        .count()
+}
+
 fn select_send_tx<T: ?Sized, C, K>(
     wallet: &mut T,
     amount: u64,
@@ -218,7 +296,11 @@ fn select_send_tx<T: ?Sized, C, K>(
     max_outputs: usize,
     change_outputs: usize,
     selection_strategy_is_use_all: bool,
+    use_outputs: Option<&Vec<String>>,
+    replaces: Option<u32>,
     parent_key_id: &Identifier,
+    change_parent_key_id: &Identifier,
+    fee_overrides: &FeeOverrides,
 ) -> Result<
     (
         Vec<Box<build::Append<K>>>,
@@ -234,7 +316,45 @@ where
     C: NodeClient,
     K: Keychain,
 {
+    // spend exactly the outputs we were asked to, if any
+    if let Some(commits) = use_outputs {
+        let coins = select_coins_by_commit(
+            wallet,
+            commits,
+            replaces,
+            current_height,
+            minimum_confirmations,
+            parent_key_id,
+        )?;
+        let total: u64 = coins.iter().map(|c| c.value).sum();
+        let mut fee = fee_overrides.tx_fee(coins.len(), num_outputs, 1);
+        if total != amount_with_fee(amount, fee)? {
+            fee = fee_overrides.tx_fee(coins.len(), num_outputs + change_outputs, 1);
+        }
+        let needed = amount_with_fee(amount, fee)?;
+        if total < needed {
+            return Err(ErrorKind::NotEnoughFunds {
+                available: total,
+                available_disp: amount_to_string(total, false),
+                needed,
+                needed_disp: amount_to_string(needed, false),
+            })?;
+        }
+
+        let (mut parts, change_amounts_derivations) = inputs_and_change(
+            &coins,
+            wallet,
+            amount,
+            fee,
+            change_outputs,
+            change_parent_key_id,
+        )?;
+        parts.push(build::with_lock_height(lock_height));
+        return Ok((parts, coins, change_amounts_derivations, amount, fee));
+    }
+
     // select some spendable coins from the wallet
+    let max_selectable = max_outputs;
     let (max_outputs, coins) = select_coins(
         wallet,
         amount,
@@ -253,16 +373,16 @@ where
     // TODO - Does this not potentially reveal the senders private key?
     //
     // First attempt to spend without change
-    let mut fee = tx_fee(coins.len(), num_outputs, 1, None);
+    let mut fee = fee_overrides.tx_fee(coins.len(), num_outputs, 1);
     let mut total: u64 = coins.iter().map(|c| c.value).sum();
-    let mut amount_with_fee = amount + fee;
+    let mut amount_with_fee = self::amount_with_fee(amount, fee)?;
 
     if total == 0 {
         return Err(ErrorKind::NotEnoughFunds {
             available: 0,
-            available_disp: amount_to_hr_string(0, false),
+            available_disp: amount_to_string(0, false),
             needed: amount_with_fee as u64,
-            needed_disp: amount_to_hr_string(amount_with_fee as u64, false),
+            needed_disp: amount_to_string(amount_with_fee as u64, false),
         })?;
     }
 
@@ -270,9 +390,9 @@ where
     if total < amount_with_fee && coins.len() == max_outputs {
         return Err(ErrorKind::NotEnoughFunds {
             available: total,
-            available_disp: amount_to_hr_string(total, false),
+            available_disp: amount_to_string(total, false),
             needed: amount_with_fee as u64,
-            needed_disp: amount_to_hr_string(amount_with_fee as u64, false),
+            needed_disp: amount_to_string(amount_with_fee as u64, false),
         })?;
     }
 
@@ -280,8 +400,8 @@ where
 
     // We need to add a change address or amount with fee is more than total
     if total != amount_with_fee {
-        fee = tx_fee(coins.len(), num_outputs, 1, None);
-        amount_with_fee = amount + fee;
+        fee = fee_overrides.tx_fee(coins.len(), num_outputs, 1);
+        amount_with_fee = self::amount_with_fee(amount, fee)?;
 
         // Here check if we have enough outputs for the amount including fee otherwise
         // look for other outputs and check again
@@ -290,9 +410,9 @@ where
             if coins.len() == max_outputs {
                 return Err(ErrorKind::NotEnoughFunds {
                     available: total as u64,
-                    available_disp: amount_to_hr_string(total, false),
+                    available_disp: amount_to_string(total, false),
                     needed: amount_with_fee as u64,
-                    needed_disp: amount_to_hr_string(amount_with_fee as u64, false),
+                    needed_disp: amount_to_string(amount_with_fee as u64, false),
                 })?;
             }
 
@@ -306,15 +426,30 @@ where
                 selection_strategy_is_use_all,
                 parent_key_id,
             );
-            fee = tx_fee(coins.len(), num_outputs, 1, None);
+            fee = fee_overrides.tx_fee(coins.len(), num_outputs, 1);
             total = coins.iter().map(|c| c.value).sum();
-            amount_with_fee = amount + fee;
+            amount_with_fee = self::amount_with_fee(amount, fee)?;
         }
     }
 
+    // selection treats the cap as soft to cover the amount, but a send that
+    // large bloats the transaction, so rather ask for a consolidation
+    if coins.len() > max_selectable {
+        return Err(ErrorKind::TooManyOutputs {
+            needed: coins.len(),
+            max: max_selectable,
+        })?;
+    }
+
     // build transaction skeleton with inputs and change
-    let (mut parts, change_amounts_derivations) =
-        inputs_and_change(&coins, wallet, amount, fee, change_outputs)?;
+    let (mut parts, change_amounts_derivations) = inputs_and_change(
+        &coins,
+        wallet,
+        amount,
+        fee,
+        change_outputs,
+        change_parent_key_id,
+    )?;
 
     // This is more proof of concept than anything but here we set lock_height
     // on tx being sent (based on current chain height via api).
@@ -323,12 +458,19 @@ where
     Ok((parts, coins, change_amounts_derivations, amount, fee))
 }
 
+/// The amount plus the fee, refusing amounts so large the sum doesn't fit. Amounts
+/// can come from a slate someone else made, so they are not trusted to be sane.
+fn amount_with_fee(amount: u64, fee: u64) -> Result<u64, Error> {
+    Ok(amount.checked_add(fee).ok_or(ErrorKind::AmountOverflow)?)
+}
+
 pub fn inputs_and_change<T: ?Sized, C, K>(
     coins: &Vec<OutputData>,
     wallet: &mut T,
     amount: u64,
     fee: u64,
     num_change_outputs: usize,
+    change_parent_key_id: &Identifier,
 ) -> Result<
     (
         Vec<Box<build::Append<K>>>,
@@ -376,7 +518,7 @@ where
                 part_change
             };
 
-            let change_key = wallet.derive_next().unwrap();
+            let change_key = derive_next_in(wallet, change_parent_key_id)?;
 
             change_amounts_derivations.push((change_amount, change_key.clone(), None));
             parts.push(build::output(change_amount, change_key));
@@ -386,6 +528,23 @@ where
     Ok((parts, change_amounts_derivations))
 }
 
+/// Derives the next key of an account, which doesn't have to be the active one
+fn derive_next_in<T: ?Sized, C, K>(
+    wallet: &mut T,
+    parent_key_id: &Identifier,
+) -> Result<Identifier, Error>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let active_key_id = wallet.get_parent_key_id();
+    wallet.set_parent_key_id(parent_key_id);
+    let key_id = wallet.derive_next();
+    wallet.set_parent_key_id(&active_key_id);
+    key_id
+}
+
 pub fn select_coins<T: ?Sized, C, K>(
     wallet: &mut T,
     amount: u64,
@@ -452,6 +611,59 @@ where
     )
 }
 
+/// Looks up the outputs with the given commitments, which must all be spendable
+pub fn select_coins_by_commit<T: ?Sized, C, K>(
+    wallet: &mut T,
+    commits: &Vec<String>,
+    replaces: Option<u32>,
+    current_height: u64,
+    minimum_confirmations: u64,
+    parent_key_id: &Identifier,
+) -> Result<Vec<OutputData>, Error>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let keychain = wallet.keychain().clone();
+    let outputs: Vec<OutputData> = wallet
+        .outputs()
+        .filter(|out| out.root_key_id == *parent_key_id)
+        .collect();
+
+    let mut coins: Vec<OutputData> = vec![];
+    for commit in commits {
+        let commit = commit.to_lowercase();
+        let mut found = None;
+        for out in &outputs {
+            let out_commit = match out.commit.clone() {
+                Some(c) => c,
+                None => to_hex(keychain.commit(out.value, &out.key_id)?.0.to_vec()),
+            };
+            if out_commit == commit {
+                found = Some(out.clone());
+                break;
+            }
+        }
+
+        let out = found.ok_or_else(|| ErrorKind::OutputNotFound(commit.clone()))?;
+        // a replacement spends the inputs the send it replaces still has locked
+        let replaced_input = replaces.is_some()
+            && out.status == OutputStatus::Locked
+            && out.tx_log_entry == replaces;
+        if !replaced_input && !out.eligible_to_spend(current_height, minimum_confirmations) {
+            return Err(ErrorKind::OutputNotSpendable(commit))?;
+        }
+        if !coins
+            .iter()
+            .any(|c| c.key_id == out.key_id && c.mmr_index == out.mmr_index)
+        {
+            coins.push(out);
+        }
+    }
+    Ok(coins)
+}
+
 fn select_from(amount: u64, select_all: bool, outputs: Vec<OutputData>) -> Option<Vec<OutputData>> {
     let total = outputs.iter().fold(0, |acc, x| acc + x.value);
     if total >= amount {
@@ -546,6 +758,7 @@ where
         t.tx_slate_id = Some(slate_id);
         t.amount_credited = amount;
         t.num_outputs = num_outputs;
+        t.log_state(TxState::Created);
         for (id, amount) in key_ids_and_amounts_inner {
             let commit = commits.get(&id).unwrap().clone();
             batch.save_output(&OutputData {
@@ -578,6 +791,7 @@ pub fn build_recipient_input_with_slate<T: ?Sized, C, K>(
     num_change_outputs: usize,
     selection_strategy_is_use_all: bool,
     parent_key_id: Identifier,
+    fee_overrides: &FeeOverrides,
 ) -> Result<
     (
         Context,
@@ -606,7 +820,16 @@ where
         max_outputs,
         num_change_outputs,
         selection_strategy_is_use_all,
+        None,
         &parent_key_id,
+        &parent_key_id,
+        fee_overrides,
+    )?;
+    fee_overrides.check_fee(
+        fee,
+        inputs.len(),
+        num_outputs + change_amounts_derivations.len(),
+        1,
     )?;
 
     slate.fee = fee;
@@ -645,6 +868,7 @@ where
         let mut t = TxLogEntry::new(parent_key_id.clone(), TxLogEntryType::TxReceived, log_id);
         t.tx_slate_id = Some(slate_id);
         t.fee = Some(fee);
+        t.log_state(TxState::Received);
         let mut amount_debited = 0;
         t.num_inputs = lock_inputs.len();
         for id in lock_inputs {
@@ -653,6 +877,7 @@ where
             amount_debited = amount_debited + coin.value;
             batch.lock_output(&mut coin)?;
         }
+        t.log_state(TxState::OutputsLocked);
 
         t.amount_debited = amount_debited;
 
@@ -681,3 +906,102 @@ where
     };
     Ok((context, update_sender_wallet_fn))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::backend::{MemoryBackend, MemoryStore};
+    use crate::wallet::node_client::TimeoutNodeClient;
+    use grin_wallet::WalletConfig;
+
+    use super::super::types::ExtKeychain;
+
+    #[test]
+    fn skips_outputs_without_enough_confirmations() {
+        let client = TimeoutNodeClient::new("http://127.0.0.1:13413", None, 1);
+        let mut wallet: MemoryBackend<TimeoutNodeClient, ExtKeychain> =
+            MemoryBackend::new(&WalletConfig::default(), "", client, MemoryStore::default())
+                .unwrap();
+        let parent_key_id = wallet.get_parent_key_id();
+        {
+            let mut batch = wallet.batch().unwrap();
+            // at height 100 these have 10, 6 and no confirmations
+            for (n_child, height, status) in vec![
+                (0, 91, OutputStatus::Unspent),
+                (1, 95, OutputStatus::Unspent),
+                (2, 0, OutputStatus::Unconfirmed),
+            ] {
+                batch
+                    .save_output(&OutputData {
+                        root_key_id: parent_key_id.clone(),
+                        key_id: ExtKeychain::derive_key_id(3, 2, 0, 0, n_child),
+                        n_child,
+                        commit: None,
+                        mmr_index: None,
+                        value: 1_000_000_000,
+                        status,
+                        height,
+                        lock_height: 0,
+                        is_coinbase: false,
+                        tx_log_entry: None,
+                    })
+                    .unwrap();
+            }
+            batch.commit().unwrap();
+        }
+
+        let (max_available, coins) =
+            select_coins(&mut wallet, 1, 100, 10, 500, true, &parent_key_id);
+        assert_eq!(max_available, 1);
+        assert_eq!(coins.iter().map(|c| c.n_child).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn only_counts_the_unconfirmed_change_of_sends() {
+        let client = TimeoutNodeClient::new("http://127.0.0.1:13413", None, 1);
+        let mut wallet: MemoryBackend<TimeoutNodeClient, ExtKeychain> =
+            MemoryBackend::new(&WalletConfig::default(), "", client, MemoryStore::default())
+                .unwrap();
+        let parent_key_id = wallet.get_parent_key_id();
+        let inputs: Vec<OutputData> =
+            vec![(0, TxLogEntryType::TxSent), (1, TxLogEntryType::TxReceived)]
+                .into_iter()
+                .map(|(id, tx_type)| {
+                    let mut batch = wallet.batch().unwrap();
+                    batch
+                        .save_tx_log_entry(&TxLogEntry::new(parent_key_id.clone(), tx_type, id))
+                        .unwrap();
+                    batch.commit().unwrap();
+                    OutputData {
+                        root_key_id: parent_key_id.clone(),
+                        key_id: ExtKeychain::derive_key_id(3, 2, 0, 0, id),
+                        n_child: id,
+                        commit: None,
+                        mmr_index: None,
+                        value: 1_000_000_000,
+                        status: OutputStatus::Unconfirmed,
+                        height: 0,
+                        lock_height: 0,
+                        is_coinbase: false,
+                        tx_log_entry: Some(id),
+                    }
+                })
+                .collect();
+
+        // the unconfirmed payment received isn't change
+        assert_eq!(unconfirmed_change(&wallet, &inputs), 1);
+    }
+
+    #[test]
+    fn refuses_fees_that_overflow() {
+        assert_eq!(
+            amount_with_fee(u64::max_value() - 1, 1).unwrap(),
+            u64::max_value()
+        );
+        let err = amount_with_fee(u64::max_value(), 1).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::AmountOverflow)
+        );
+    }
+}
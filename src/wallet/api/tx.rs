@@ -1,9 +1,11 @@
+use chrono::{DateTime, Utc};
+use grin_core::libtx::aggsig;
 use uuid::Uuid;
 
 use super::selection;
 use super::types::{
-    Context, Error, ErrorKind, Identifier, Keychain, NodeClient, Slate, Transaction,
-    TxLogEntryType, WalletBackend,
+    Context, Error, ErrorKind, FeeOverrides, Identifier, Keychain, NodeClient, OutputData,
+    OutputStatus, SigningKey, Slate, Transaction, TxLogEntryType, TxState, WalletBackend,
 };
 use super::updater;
 
@@ -44,9 +46,43 @@ where
     // Save output in wallet
     let _ = receiver_create_fn(wallet);
 
+    // Keep the sender's message alongside our record of the transaction
+    update_tx_messages(wallet, slate, TxLogEntryType::TxReceived)?;
+
     Ok(())
 }
 
+/// Moves our signing secrets for a slate into a key to be kept outside of the wallet,
+/// leaving random ones in the context. The slate then has to come back signed with it
+pub fn export_signing_key<T: ?Sized, C, K>(
+    wallet: &mut T,
+    slate_id: &Uuid,
+) -> Result<SigningKey, Error>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let mut context = wallet.get_private_context(&slate_id.to_string())?;
+    if context.external_signer {
+        return Err(ErrorKind::SigningKeyExported)?;
+    }
+    let key = SigningKey {
+        slate_id: slate_id.clone(),
+        sec_key: context.sec_key.clone(),
+        sec_nonce: Some(context.sec_nonce.clone()),
+    };
+
+    let keychain = wallet.keychain().clone();
+    context.sec_key = aggsig::create_secnonce(keychain.secp())?;
+    context.sec_nonce = aggsig::create_secnonce(keychain.secp())?;
+    context.external_signer = true;
+    let mut batch = wallet.batch()?;
+    batch.save_private_context(&slate_id.to_string(), &context)?;
+    batch.commit()?;
+    Ok(key)
+}
+
 pub fn create_send_tx<T: ?Sized, C, K>(
     wallet: &mut T,
     address: Option<String>,
@@ -55,8 +91,14 @@ pub fn create_send_tx<T: ?Sized, C, K>(
     max_outputs: usize,
     num_change_outputs: usize,
     selection_strategy_is_use_all: bool,
+    use_outputs: Option<&Vec<String>>,
+    replaces: Option<u32>,
+    lock_height: Option<u64>,
     parent_key_id: &Identifier,
+    change_parent_key_id: Option<Identifier>,
+    allow_unconfirmed_change: bool,
     message: Option<String>,
+    fee_overrides: &FeeOverrides,
 ) -> Result<
     (
         Slate,
@@ -75,7 +117,14 @@ where
     // ensure outputs we're selecting are up to date
     updater::refresh_outputs(wallet, parent_key_id, false)?;
 
-    let lock_height = current_height;
+    // a requested lock height keeps the kernel out of the chain until that block
+    let lock_height = match lock_height {
+        Some(h) if h <= current_height => {
+            return Err(ErrorKind::LockHeightTooLow(h, current_height))?;
+        }
+        Some(h) => h,
+        None => current_height,
+    };
 
     // Sender selects outputs into a new slate and save our corresponding keys in
     // a transaction context. The secret key in our transaction context will be
@@ -95,7 +144,12 @@ where
         max_outputs,
         num_change_outputs,
         selection_strategy_is_use_all,
+        use_outputs,
+        replaces,
         parent_key_id.clone(),
+        change_parent_key_id,
+        allow_unconfirmed_change,
+        fee_overrides,
     )?;
 
     // Generate a kernel offset and subtract from our context's secret key. Store
@@ -122,7 +176,13 @@ where
     C: NodeClient,
     K: Keychain,
 {
-    let _ = slate.fill_round_2(wallet.keychain(), &context.sec_key, &context.sec_nonce, 0)?;
+    if context.external_signer {
+        if slate.participant_data[0].part_sig.is_none() {
+            return Err(ErrorKind::SlateNotSigned)?;
+        }
+    } else {
+        let _ = slate.fill_round_2(wallet.keychain(), &context.sec_key, &context.sec_nonce, 0)?;
+    }
     // Final transaction can be built by anyone at this stage
     let res = slate.finalize(wallet.keychain());
     if let Err(e) = res {
@@ -161,8 +221,138 @@ where
     }
     // get outputs associated with tx
     let res = updater::retrieve_outputs(wallet, false, Some(tx.id), Some(&parent_key_id))?;
-    let outputs = res.iter().map(|(out, _)| out).cloned().collect();
+    let mut outputs: Vec<OutputData> = res.iter().map(|(out, _)| out).cloned().collect();
+    let change_slate_id = match tx.tx_type {
+        TxLogEntryType::TxSent => tx.tx_slate_id,
+        _ => None,
+    };
+
+    // the inputs of a cancelled replacement stay locked by the send it replaced, if that
+    // one can still confirm
+    let replaced = match tx.tx_type {
+        TxLogEntryType::TxSent => tx.replaces.and_then(|id| {
+            updater::retrieve_txs(wallet, Some(id), None, Some(&parent_key_id), false)
+                .ok()
+                .and_then(|txs| txs.into_iter().next())
+                .filter(|t| t.tx_type == TxLogEntryType::TxSent && !t.confirmed)
+        }),
+        _ => None,
+    };
+    if let Some(replaced) = replaced {
+        let (inputs, rest): (Vec<OutputData>, Vec<OutputData>) = outputs
+            .into_iter()
+            .partition(|out| out.status == OutputStatus::Locked);
+        outputs = rest;
+        let mut batch = wallet.batch()?;
+        for mut input in inputs {
+            input.tx_log_entry = Some(replaced.id);
+            batch.save_output(&input)?;
+        }
+        batch.commit()?;
+    }
     updater::cancel_tx_and_outputs(wallet, tx, outputs)?;
+
+    // change sent to another account was logged there, it goes away with the send
+    if let Some(slate_id) = change_slate_id {
+        updater::cancel_change_txs(wallet, parent_key_id, slate_id)?;
+    }
+    Ok(())
+}
+
+/// Sets the note of a transaction, or clears it when `note` is empty
+pub fn set_tx_note<T: ?Sized, C, K>(
+    wallet: &mut T,
+    parent_key_id: &Identifier,
+    tx_id: u32,
+    note: &str,
+) -> Result<(), Error>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let tx_vec = updater::retrieve_txs(wallet, Some(tx_id), None, Some(&parent_key_id), false)?;
+    if tx_vec.len() != 1 {
+        return Err(ErrorKind::TransactionDoesntExist(tx_id.to_string()))?;
+    }
+    let mut tx = tx_vec[0].clone();
+    tx.note = match note.trim() {
+        "" => None,
+        note => Some(note.to_string()),
+    };
+    let mut batch = wallet.batch()?;
+    batch.save_tx_log_entry(&tx)?;
+    batch.commit()?;
+    Ok(())
+}
+
+pub fn set_tx_expiry<T: ?Sized, C, K>(
+    wallet: &mut T,
+    parent_key_id: &Identifier,
+    tx_id: u32,
+    expires_at: DateTime<Utc>,
+) -> Result<(), Error>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let tx_vec = updater::retrieve_txs(wallet, Some(tx_id), None, Some(&parent_key_id), false)?;
+    if tx_vec.len() != 1 {
+        return Err(ErrorKind::TransactionDoesntExist(tx_id.to_string()))?;
+    }
+    let mut tx = tx_vec[0].clone();
+    tx.expires_at = Some(expires_at);
+    let mut batch = wallet.batch()?;
+    batch.save_tx_log_entry(&tx)?;
+    batch.commit()?;
+    Ok(())
+}
+
+/// Marks a send to be fluffed when it is posted after being finalized
+pub fn set_tx_fluff<T: ?Sized, C, K>(
+    wallet: &mut T,
+    parent_key_id: &Identifier,
+    tx_id: u32,
+) -> Result<(), Error>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let tx_vec = updater::retrieve_txs(wallet, Some(tx_id), None, Some(&parent_key_id), false)?;
+    if tx_vec.len() != 1 {
+        return Err(ErrorKind::TransactionDoesntExist(tx_id.to_string()))?;
+    }
+    let mut tx = tx_vec[0].clone();
+    tx.fluff = true;
+    let mut batch = wallet.batch()?;
+    batch.save_tx_log_entry(&tx)?;
+    batch.commit()?;
+    Ok(())
+}
+
+/// Records `state` for the live entries of a slate, e.g. those of both parties of a
+/// send to self
+pub fn log_tx_state<T: ?Sized, C, K>(
+    wallet: &mut T,
+    slate_id: &Uuid,
+    state: TxState,
+) -> Result<(), Error>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let tx_vec = updater::retrieve_txs(wallet, None, Some(*slate_id), None, false)?;
+    let mut batch = wallet.batch()?;
+    for mut tx in tx_vec {
+        if tx.tx_type == TxLogEntryType::TxSent || tx.tx_type == TxLogEntryType::TxReceived {
+            tx.log_state(state.clone());
+            batch.save_tx_log_entry(&tx)?;
+        }
+    }
+    batch.commit()?;
     Ok(())
 }
 
@@ -190,8 +380,17 @@ where
     if tx.is_none() {
         return Err(ErrorKind::TransactionDoesntExist(slate.id.to_string()).into());
     }
+    let mut tx = tx.unwrap();
 
     let mut batch = wallet.batch()?;
+    let messages = slate_messages(slate);
+    if messages.is_some() {
+        tx.messages = messages;
+    }
+    // the response of the other party is finalized as soon as it comes back
+    tx.log_state(TxState::Received);
+    tx.log_state(TxState::Finalized);
+    batch.save_tx_log_entry(&tx)?;
     batch.store_tx(&slate.id.to_string(), &slate.tx)?;
     if tx_proof.is_some() {
         batch.store_tx_proof(&slate.id.to_string(), tx_proof.unwrap())?;
@@ -210,6 +409,7 @@ pub fn invoice_tx<T: ?Sized, C, K>(
     selection_strategy_is_use_all: bool,
     parent_key_id: Identifier,
     message: Option<String>,
+    fee_overrides: &FeeOverrides,
 ) -> Result<(impl FnOnce(&mut T, &Transaction) -> Result<(), Error>), Error>
 where
     T: WalletBackend<C, K>,
@@ -226,6 +426,7 @@ where
         num_change_outputs,
         selection_strategy_is_use_all,
         parent_key_id,
+        fee_overrides,
     )?;
 
     slate.fill_round_1(
@@ -284,3 +485,43 @@ where
 
     Ok((slate, context, add_fn))
 }
+
+/// Collect the messages the participants attached to a slate, if any
+pub fn slate_messages(slate: &Slate) -> Option<Vec<String>> {
+    let messages: Vec<String> = slate
+        .participant_data
+        .iter()
+        .filter_map(|p| p.message.clone())
+        .collect();
+    match messages.is_empty() {
+        true => None,
+        false => Some(messages),
+    }
+}
+
+fn update_tx_messages<T: ?Sized, C, K>(
+    wallet: &mut T,
+    slate: &Slate,
+    tx_type: TxLogEntryType,
+) -> Result<(), Error>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let messages = slate_messages(slate);
+    if messages.is_none() {
+        return Ok(());
+    }
+
+    let tx_vec = updater::retrieve_txs(wallet, None, Some(slate.id), None, false)?;
+    let mut batch = wallet.batch()?;
+    for mut t in tx_vec {
+        if t.tx_type == tx_type {
+            t.messages = messages.clone();
+            batch.save_tx_log_entry(&t)?;
+        }
+    }
+    batch.commit()?;
+    Ok(())
+}
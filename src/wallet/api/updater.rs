@@ -1,5 +1,6 @@
+use chrono::{DateTime, Utc};
 use failure::ResultExt;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use super::keys;
@@ -12,7 +13,7 @@ use grin_util::{from_hex, to_hex};
 
 use super::types::{
     BlockFees, CbData, ErrorKind, Identifier, Keychain, NodeClient, OutputData, OutputStatus,
-    Result, TxLogEntry, TxLogEntryType, WalletBackend, WalletInfo,
+    Result, TxLogEntry, TxLogEntryType, TxState, WalletBackend, WalletInfo,
 };
 
 /// Retrieve all of the outputs (doesn't attempt to update from node)
@@ -123,6 +124,65 @@ where
 {
     let height = wallet.w2n_client().get_chain_height()?;
     refresh_output_state(wallet, height, parent_key_id, update_all)?;
+    cancel_replaced_txs(wallet, parent_key_id)?;
+    Ok(())
+}
+
+/// Once a send of a chain of fee replacements confirms, the others spending the same
+/// inputs can't make it into a block anymore. Their change is dropped and they are
+/// marked cancelled, while their inputs are left to the send that spent them
+fn cancel_replaced_txs<T: ?Sized, C, K>(wallet: &mut T, parent_key_id: &Identifier) -> Result<()>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let sends: Vec<TxLogEntry> = wallet
+        .tx_logs()
+        .filter(|tx| {
+            tx.parent_key_id == *parent_key_id
+                && (tx.tx_type == TxLogEntryType::TxSent
+                    || tx.tx_type == TxLogEntryType::TxSentCancelled)
+        })
+        .collect();
+    // the send a chain of replacements started from
+    let first_send = |tx: &TxLogEntry| {
+        let mut first = tx;
+        while let Some(replaced) = first
+            .replaces
+            .and_then(|id| sends.iter().find(|t| t.id == id))
+        {
+            first = replaced;
+        }
+        first.id
+    };
+    let confirmed: HashSet<u32> = sends
+        .iter()
+        .filter(|tx| tx.confirmed && tx.tx_type == TxLogEntryType::TxSent)
+        .map(|tx| first_send(tx))
+        .collect();
+    let replaced: Vec<TxLogEntry> = sends
+        .iter()
+        .filter(|tx| {
+            !tx.confirmed
+                && tx.tx_type == TxLogEntryType::TxSent
+                && confirmed.contains(&first_send(tx))
+        })
+        .cloned()
+        .collect();
+
+    for tx in replaced {
+        let outputs = retrieve_outputs(wallet, false, Some(tx.id), Some(parent_key_id))?
+            .into_iter()
+            .map(|(out, _)| out)
+            .filter(|out| out.status == OutputStatus::Unconfirmed)
+            .collect();
+        let slate_id = tx.tx_slate_id;
+        cancel_tx_and_outputs(wallet, tx, outputs)?;
+        if let Some(slate_id) = slate_id {
+            cancel_change_txs(wallet, parent_key_id, slate_id)?;
+        }
+    }
     Ok(())
 }
 
@@ -206,11 +266,95 @@ where
     if tx.tx_type == TxLogEntryType::TxReceived {
         tx.tx_type = TxLogEntryType::TxReceivedCancelled;
     }
+    tx.log_state(TxState::Cancelled);
     batch.save_tx_log_entry(&tx)?;
     batch.commit()?;
     Ok(())
 }
 
+/// Cancels the change a send logged in other accounts, which goes away with the send
+pub fn cancel_change_txs<T: ?Sized, C, K>(
+    wallet: &mut T,
+    parent_key_id: &Identifier,
+    slate_id: Uuid,
+) -> Result<()>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let change_txs = retrieve_txs(wallet, None, Some(slate_id), None, false)?;
+    for change_tx in change_txs {
+        if change_tx.parent_key_id == *parent_key_id
+            || change_tx.tx_type != TxLogEntryType::TxReceived
+            || change_tx.confirmed
+        {
+            continue;
+        }
+        let res = retrieve_outputs(
+            wallet,
+            false,
+            Some(change_tx.id),
+            Some(&change_tx.parent_key_id),
+        )?;
+        let outputs = res.iter().map(|(out, _)| out).cloned().collect();
+        cancel_tx_and_outputs(wallet, change_tx, outputs)?;
+    }
+    Ok(())
+}
+
+/// Removes the cancelled transactions created before `cutoff`, skipping any whose
+/// outputs are still locked or unconfirmed. Returns the number of transactions removed.
+pub fn prune_cancelled_txs<T: ?Sized, C, K>(
+    wallet: &mut T,
+    parent_key_id: &Identifier,
+    cutoff: DateTime<Utc>,
+) -> Result<usize>
+where
+    T: WalletBackend<C, K>,
+    C: NodeClient,
+    K: Keychain,
+{
+    let cancelled: Vec<TxLogEntry> = retrieve_txs(wallet, None, None, Some(parent_key_id), false)?
+        .into_iter()
+        .filter(|tx| {
+            tx.tx_type == TxLogEntryType::TxSentCancelled
+                || tx.tx_type == TxLogEntryType::TxReceivedCancelled
+        })
+        .filter(|tx| tx.creation_ts < cutoff)
+        .collect();
+
+    let prunable: Vec<TxLogEntry> = cancelled
+        .into_iter()
+        .filter(|tx| {
+            !wallet.outputs().any(|out| {
+                out.root_key_id == *parent_key_id
+                    && out.tx_log_entry == Some(tx.id)
+                    && (out.status == OutputStatus::Locked
+                        || out.status == OutputStatus::Unconfirmed)
+            })
+        })
+        .collect();
+
+    if prunable.is_empty() {
+        return Ok(0);
+    }
+
+    let pruned_count = wallet.get_pruned_tx_count()?;
+    let mut batch = wallet.batch()?;
+    for tx in &prunable {
+        batch.delete_tx_log_entry(tx)?;
+        if let Some(tx_slate_id) = tx.tx_slate_id {
+            batch
+                .delete_private_context(&tx_slate_id.to_string())
+                .is_ok();
+        }
+    }
+    batch.save_pruned_tx_count(pruned_count + prunable.len() as u64)?;
+    batch.commit()?;
+    Ok(prunable.len())
+}
+
 /// Apply refreshed API output data to the wallet
 pub fn apply_api_outputs<T: ?Sized, C, K>(
     wallet: &mut T,
@@ -257,6 +401,7 @@ where
                             t.amount_debited = 0;
                             t.num_outputs = 1;
                             t.update_confirmation_ts();
+                            t.log_state(TxState::Confirmed);
                             output.tx_log_entry = Some(log_id);
                             batch.save_tx_log_entry(&t)?;
                         }
@@ -271,6 +416,7 @@ where
                             if let Some(mut t) = tx {
                                 t.update_confirmation_ts();
                                 t.confirmed = true;
+                                t.log_state(TxState::Confirmed);
                                 batch.save_tx_log_entry(&t)?;
                             }
                         }
@@ -0,0 +1,81 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind as IoErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use super::types::{ErrorKind, Result};
+
+const LOCK_FILENAME: &'static str = ".wallet713.lock";
+
+/// A lock file in the wallet data directory, keeping a second wallet713 instance from
+/// opening the same data while this one runs. It holds the pid of the owning process.
+pub struct DataLock {
+    path: PathBuf,
+}
+
+impl DataLock {
+    /// Takes the lock, or fails with `ErrorKind::WalletLocked` if another instance
+    /// holds it. With `force` a lock left behind by a crashed instance is taken over.
+    pub fn acquire(data_path: &str, force: bool) -> Result<Self> {
+        fs::create_dir_all(data_path)?;
+        let path = Path::new(data_path).join(LOCK_FILENAME);
+        if force {
+            fs::remove_file(&path).is_ok();
+        }
+
+        let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == IoErrorKind::AlreadyExists => {
+                let owner = fs::read_to_string(&path).unwrap_or(String::new());
+                return Err(ErrorKind::WalletLocked(
+                    data_path.to_string(),
+                    owner.trim().to_string(),
+                ))?;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        file.write_all(process::id().to_string().as_bytes())?;
+        Ok(Self { path })
+    }
+
+    /// Removes a lock left behind by an instance that is no longer running, returning
+    /// its path if there was one. Fails with `ErrorKind::WalletLocked` if its owner
+    /// still runs.
+    pub fn remove_stale(data_path: &str) -> Result<Option<PathBuf>> {
+        let path = Path::new(data_path).join(LOCK_FILENAME);
+        let owner = match fs::read_to_string(&path) {
+            Ok(owner) => owner.trim().to_string(),
+            Err(_) => return Ok(None),
+        };
+        // a crash right after creating the lock leaves it without a pid
+        if owner.parse::<u32>().map(is_running).unwrap_or(false) {
+            return Err(ErrorKind::WalletLocked(data_path.to_string(), owner))?;
+        }
+        fs::remove_file(&path)?;
+        Ok(Some(path))
+    }
+
+    /// Removes the lock file. Called on exit, as `process::exit` skips destructors.
+    pub fn release(&self) {
+        fs::remove_file(&self.path).is_ok();
+    }
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // signal 0 only checks that the process exists
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a way to look the process up, the lock is always assumed to be held
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
+
+impl Drop for DataLock {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
@@ -7,22 +7,29 @@ use std::path::Path;
 use std::{fs, path};
 
 use grin_core::{global, ser};
+use grin_keychain::extkey_bip32::{BIP32GrinHasher, ExtendedPrivKey};
 use grin_store::{self, option_to_not_found, to_key, to_key_u64};
 use grin_util::secp::constants::SECRET_KEY_SIZE;
+use grin_util::secp::Secp256k1;
 use grin_util::ZeroingString;
 use grin_util::{from_hex, to_hex};
 use grin_wallet::WalletConfig;
 
 use super::api::restore;
 use super::types::{
-    AcctPathMapping, Arc, ChildNumber, Context, ErrorKind, ExtKeychain, Identifier, Keychain,
-    NodeClient, OutputData, Result, Transaction, TxLogEntry, TxProof, WalletBackend,
-    WalletBackendBatch, WalletSeed,
+    AcctPathMapping, Arc, ChildNumber, Context, ErrorKind, ExtKeychain, ExtendedPubKey, Identifier,
+    Keychain, NodeClient, OutputData, PendingSlate, PublicKey, RestoreEnd, Result, Slate,
+    Transaction, TxLogEntry, TxProof, WalletBackend, WalletBackendBatch, WalletSeed,
 };
 
 pub const DB_DIR: &'static str = "db";
 pub const TX_SAVE_DIR: &'static str = "saved_txs";
 pub const TX_PROOF_SAVE_DIR: &'static str = "saved_proofs";
+pub const SLATE_SAVE_DIR: &'static str = "saved_slates";
+pub const PENDING_SLATE_DIR: &'static str = "pending_slates";
+/// Where the store's environment keeps its files inside the database directory
+const LMDB_DIR: &'static str = "lmdb";
+const LMDB_DATA_FILE: &'static str = "data.mdb";
 
 const OUTPUT_PREFIX: u8 = 'o' as u8;
 const DERIV_PREFIX: u8 = 'd' as u8;
@@ -31,6 +38,109 @@ const PRIVATE_TX_CONTEXT_PREFIX: u8 = 'p' as u8;
 const TX_LOG_ENTRY_PREFIX: u8 = 't' as u8;
 const TX_LOG_ID_PREFIX: u8 = 'i' as u8;
 const ACCOUNT_PATH_MAPPING_PREFIX: u8 = 'a' as u8;
+const PRUNED_TX_COUNT_PREFIX: u8 = 'r' as u8;
+const RESTORE_INDEX_PREFIX: u8 = 's' as u8;
+const WRITE_CHECK_FILENAME: &'static str = ".write_check";
+const LMDB_ENV_DIR: &'static str = "lmdb";
+const LMDB_LOCK_FILENAME: &'static str = "lock.mdb";
+
+/// Makes sure we can write to the wallet data directory, creating it if needed
+pub fn check_writable(data_file_dir: &str) -> Result<()> {
+    create_dir(Path::new(data_file_dir))?;
+    let path = Path::new(data_file_dir).join(WRITE_CHECK_FILENAME);
+    write_file(&path, &[])?;
+    fs::remove_file(&path).is_ok();
+    Ok(())
+}
+
+/// Removes what an interrupted run can leave in the data directory: the write check,
+/// stored files that were created but never written, and the database lock file,
+/// which holds no data. Only safe while no instance uses the directory.
+pub fn remove_debris(data_file_dir: &str) -> Result<Vec<path::PathBuf>> {
+    let data_dir = Path::new(data_file_dir);
+    let mut candidates = vec![
+        data_dir.join(WRITE_CHECK_FILENAME),
+        data_dir
+            .join(DB_DIR)
+            .join(LMDB_ENV_DIR)
+            .join(LMDB_LOCK_FILENAME),
+    ];
+    for dir in &[
+        TX_SAVE_DIR,
+        TX_PROOF_SAVE_DIR,
+        SLATE_SAVE_DIR,
+        PENDING_SLATE_DIR,
+    ] {
+        let entries = match fs::read_dir(data_dir.join(dir)) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_file() && fs::metadata(&path)?.len() == 0 {
+                candidates.push(path);
+            }
+        }
+    }
+
+    let mut removed = vec![];
+    for path in candidates {
+        if path.is_file() {
+            fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+fn create_dir(path: &Path) -> Result<()> {
+    fs::create_dir_all(path).map_err(|e| {
+        ErrorKind::StorageUnwritable(path.to_string_lossy().to_string(), e.to_string())
+    })?;
+    Ok(())
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let write = || -> std::io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(contents)?;
+        file.sync_all()
+    };
+    write().map_err(|e| {
+        ErrorKind::StorageUnwritable(path.to_string_lossy().to_string(), e.to_string())
+    })?;
+    Ok(())
+}
+
+/// Opens an existing database read only before the store does, which panics on any
+/// failure, so a damaged database is told apart from one we can't access
+fn check_database(db_path: &Path) -> Result<()> {
+    let env_path = db_path.join(LMDB_DIR);
+    if !env_path.join(LMDB_DATA_FILE).is_file() {
+        return Ok(());
+    }
+    let probe = || -> lmdb_zero::Result<lmdb_zero::Environment> {
+        let builder = lmdb_zero::EnvBuilder::new()?;
+        unsafe { builder.open(&env_path.to_string_lossy(), lmdb_zero::open::RDONLY, 0o600) }
+    };
+    match probe() {
+        Ok(_) => Ok(()),
+        Err(lmdb_zero::Error::Code(code))
+            if code == lmdb_zero::error::INVALID
+                || code == lmdb_zero::error::CORRUPTED
+                || code == lmdb_zero::error::VERSION_MISMATCH
+                || code == lmdb_zero::error::PANIC =>
+        {
+            Err(ErrorKind::CorruptWallet(
+                db_path.to_string_lossy().to_string(),
+            ))?
+        }
+        Err(e) => Err(ErrorKind::StorageUnwritable(
+            db_path.to_string_lossy().to_string(),
+            e.to_string(),
+        ))?,
+    }
+}
 
 fn private_ctx_xor_keys<K>(
     keychain: &K,
@@ -63,6 +173,28 @@ where
     Ok((ret_blind, ret_nonce))
 }
 
+/// Public keys of the wallet root and of the account at `parent_key_id`. These are
+/// rebuilt from the seed, the keychain doesn't give access to the chain codes an xpub needs
+pub(super) fn seed_public_keys(
+    config: &WalletConfig,
+    passphrase: &str,
+    parent_key_id: &Identifier,
+) -> Result<(PublicKey, ExtendedPubKey)> {
+    let wallet_seed =
+        WalletSeed::from_file(config, passphrase).context(ErrorKind::OpenWalletError)?;
+    let seed = from_hex(wallet_seed.to_hex()).map_err(|_| ErrorKind::OpenWalletError)?;
+    let secp = Secp256k1::new();
+    let mut hasher = BIP32GrinHasher::new(global::is_floonet());
+    let master = ExtendedPrivKey::new_master(&secp, &mut hasher, &seed)?;
+    let path = parent_key_id.to_path();
+    let account = master.derive_priv(&secp, &mut hasher, &path.path[..path.depth as usize])?;
+    let root = PublicKey::from_secret_key(&secp, &master.secret_key)?;
+    Ok((
+        root,
+        ExtendedPubKey::from_private(&secp, &account, &mut hasher),
+    ))
+}
+
 pub struct Backend<C, K> {
     db: grin_store::Store,
     passphrase: ZeroingString,
@@ -75,16 +207,21 @@ pub struct Backend<C, K> {
 impl<C, K> Backend<C, K> {
     pub fn new(config: &WalletConfig, passphrase: &str, n_client: C) -> Result<Self> {
         let db_path = path::Path::new(&config.data_file_dir).join(DB_DIR);
-        fs::create_dir_all(&db_path).expect("Couldn't create wallet backend directory!");
+        create_dir(&db_path)?;
 
         let stored_tx_path = path::Path::new(&config.data_file_dir).join(TX_SAVE_DIR);
-        fs::create_dir_all(&stored_tx_path)
-            .expect("Couldn't create wallet backend tx storage directory!");
+        create_dir(&stored_tx_path)?;
 
         let stored_tx_proof_path = path::Path::new(&config.data_file_dir).join(TX_PROOF_SAVE_DIR);
-        fs::create_dir_all(&stored_tx_proof_path)
-            .expect("Couldn't create wallet backend tx proof storage directory!");
+        create_dir(&stored_tx_proof_path)?;
 
+        let stored_slate_path = path::Path::new(&config.data_file_dir).join(SLATE_SAVE_DIR);
+        create_dir(&stored_slate_path)?;
+
+        let pending_slate_path = path::Path::new(&config.data_file_dir).join(PENDING_SLATE_DIR);
+        create_dir(&pending_slate_path)?;
+
+        check_database(&db_path)?;
         let lmdb_env = Arc::new(grin_store::new_env(db_path.to_str().unwrap().to_string()));
         let store = grin_store::Store::open(lmdb_env, DB_DIR);
 
@@ -173,6 +310,10 @@ where
         self.parent_key_id.clone()
     }
 
+    fn public_keys(&self) -> Result<(PublicKey, ExtendedPubKey)> {
+        seed_public_keys(&self.config, &self.passphrase, &self.parent_key_id)
+    }
+
     fn get_output(&self, id: &Identifier, mmr_index: &Option<u64>) -> Result<OutputData> {
         let key = match mmr_index {
             Some(i) => to_key_u64(OUTPUT_PREFIX, &mut id.to_bytes().to_vec(), *i),
@@ -258,6 +399,49 @@ where
         Ok(serde_json::from_str(&content)?)
     }
 
+    fn get_stored_slate(&self, uuid: &str) -> Result<Slate> {
+        let filename = format!("{}.grinslate", uuid);
+        let path = path::Path::new(&self.config.data_file_dir)
+            .join(SLATE_SAVE_DIR)
+            .join(filename);
+        let slate_file = Path::new(&path).to_path_buf();
+        let mut slate_f = File::open(slate_file)?;
+        let mut content = String::new();
+        slate_f.read_to_string(&mut content)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn get_pending_slate(&self, uuid: &str) -> Result<PendingSlate> {
+        let filename = format!("{}.pending", uuid);
+        let path = path::Path::new(&self.config.data_file_dir)
+            .join(PENDING_SLATE_DIR)
+            .join(filename);
+        if !path.exists() {
+            return Err(ErrorKind::PendingSlateNotFound(uuid.to_string()).into());
+        }
+        let mut pending_f = File::open(path)?;
+        let mut content = String::new();
+        pending_f.read_to_string(&mut content)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn pending_slates(&self) -> Result<Vec<PendingSlate>> {
+        let dir = path::Path::new(&self.config.data_file_dir).join(PENDING_SLATE_DIR);
+        let mut pending = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("pending") {
+                continue;
+            }
+            let mut pending_f = File::open(path)?;
+            let mut content = String::new();
+            pending_f.read_to_string(&mut content)?;
+            pending.push(serde_json::from_str::<PendingSlate>(&content)?);
+        }
+        pending.sort_by_key(|p| p.received_at);
+        Ok(pending)
+    }
+
     fn batch<'a>(&'a self) -> Result<Box<dyn WalletBackendBatch<K> + 'a>> {
         Ok(Box::new(Batch {
             _store: self,
@@ -298,9 +482,27 @@ where
         Ok(last_confirmed_height)
     }
 
-    fn restore(&mut self) -> Result<()> {
-        restore::restore(self).context(ErrorKind::Restore)?;
-        Ok(())
+    fn get_pruned_tx_count(&self) -> Result<u64> {
+        let count_key = to_key(
+            PRUNED_TX_COUNT_PREFIX,
+            &mut self.parent_key_id.to_bytes().to_vec(),
+        );
+        let count = match self.db.get_ser(&count_key)? {
+            Some(c) => c,
+            None => 0,
+        };
+        Ok(count)
+    }
+
+    fn get_restore_index(&self) -> Result<Option<u64>> {
+        let index_key = to_key(RESTORE_INDEX_PREFIX, &mut Vec::new());
+        let index = self.db.get_ser(&index_key)?;
+        Ok(index)
+    }
+
+    fn restore(&mut self, max_retries: u32) -> Result<RestoreEnd> {
+        let end = restore::restore(self, max_retries).context(ErrorKind::Restore)?;
+        Ok(end)
     }
 
     fn check_repair(&mut self) -> Result<()> {
@@ -373,11 +575,8 @@ where
         let path = path::Path::new(&self._store.config.data_file_dir)
             .join(TX_SAVE_DIR)
             .join(filename);
-        let path_buf = Path::new(&path).to_path_buf();
-        let mut stored_tx = File::create(path_buf)?;
-        let tx_hex = to_hex(ser::ser_vec(tx).unwrap());;
-        stored_tx.write_all(&tx_hex.as_bytes())?;
-        stored_tx.sync_all()?;
+        let tx_hex = to_hex(ser::ser_vec(tx).unwrap());
+        write_file(&path, &tx_hex.as_bytes())?;
         Ok(())
     }
 
@@ -386,11 +585,40 @@ where
         let path = path::Path::new(&self._store.config.data_file_dir)
             .join(TX_PROOF_SAVE_DIR)
             .join(filename);
-        let path_buf = Path::new(&path).to_path_buf();
-        let mut stored_tx = File::create(path_buf)?;
         let proof_ser = serde_json::to_string(tx_proof)?;
-        stored_tx.write_all(&proof_ser.as_bytes())?;
-        stored_tx.sync_all()?;
+        write_file(&path, &proof_ser.as_bytes())?;
+        Ok(())
+    }
+
+    fn store_slate(&self, uuid: &str, slate: &Slate) -> Result<()> {
+        let filename = format!("{}.grinslate", uuid);
+        let path = path::Path::new(&self._store.config.data_file_dir)
+            .join(SLATE_SAVE_DIR)
+            .join(filename);
+        let slate_ser = serde_json::to_string(slate)?;
+        write_file(&path, &slate_ser.as_bytes())?;
+        Ok(())
+    }
+
+    fn store_pending_slate(&self, pending: &PendingSlate) -> Result<()> {
+        let filename = format!("{}.pending", pending.slate.id);
+        let path = path::Path::new(&self._store.config.data_file_dir)
+            .join(PENDING_SLATE_DIR)
+            .join(filename);
+        let pending_ser = serde_json::to_string(pending)?;
+        write_file(&path, &pending_ser.as_bytes())?;
+        Ok(())
+    }
+
+    fn delete_pending_slate(&self, uuid: &str) -> Result<()> {
+        let filename = format!("{}.pending", uuid);
+        let path = path::Path::new(&self._store.config.data_file_dir)
+            .join(PENDING_SLATE_DIR)
+            .join(filename);
+        if !path.exists() {
+            return Err(ErrorKind::PendingSlateNotFound(uuid.to_string()).into());
+        }
+        fs::remove_file(path)?;
         Ok(())
     }
 
@@ -445,6 +673,53 @@ where
         Ok(())
     }
 
+    fn delete_tx_log_entry(&mut self, t: &TxLogEntry) -> Result<()> {
+        let tx_log_key = to_key_u64(
+            TX_LOG_ENTRY_PREFIX,
+            &mut t.parent_key_id.to_bytes().to_vec(),
+            t.id as u64,
+        );
+        self.db
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .delete(&tx_log_key)
+            .map_err(|e| e.into())
+    }
+
+    fn save_pruned_tx_count(&mut self, count: u64) -> Result<()> {
+        let count_key = to_key(
+            PRUNED_TX_COUNT_PREFIX,
+            &mut self._store.get_parent_key_id().to_bytes().to_vec(),
+        );
+        self.db
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .put_ser(&count_key, &count)?;
+        Ok(())
+    }
+
+    fn save_restore_index(&mut self, index: u64) -> Result<()> {
+        let index_key = to_key(RESTORE_INDEX_PREFIX, &mut Vec::new());
+        self.db
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .put_ser(&index_key, &index)?;
+        Ok(())
+    }
+
+    fn delete_restore_index(&mut self) -> Result<()> {
+        let index_key = to_key(RESTORE_INDEX_PREFIX, &mut Vec::new());
+        self.db
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .delete(&index_key)
+            .map_err(|e| e.into())
+    }
+
     fn save_acct_path(&mut self, mapping: &AcctPathMapping) -> Result<()> {
         let acct_key = to_key(
             ACCOUNT_PATH_MAPPING_PREFIX,
@@ -494,7 +769,42 @@ where
 
     fn commit(&mut self) -> Result<()> {
         let db = self.db.replace(None);
-        db.unwrap().commit()?;
+        db.unwrap().commit().map_err(|e| {
+            let path = Path::new(&self._store.config.data_file_dir).join(DB_DIR);
+            ErrorKind::StorageUnwritable(path.to_string_lossy().to_string(), e.to_string())
+        })?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grin_wallet::HTTPNodeClient;
+    use std::env;
+    use uuid::Uuid;
+
+    #[test]
+    fn reports_a_damaged_database() {
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        let env_path = dir.join(DB_DIR).join(LMDB_DIR);
+        fs::create_dir_all(&env_path).unwrap();
+        write_file(&env_path.join(LMDB_DATA_FILE), &[0xab; 8192]).unwrap();
+
+        let config = WalletConfig {
+            data_file_dir: dir.to_string_lossy().to_string(),
+            ..WalletConfig::default()
+        };
+        let client = HTTPNodeClient::new("http://127.0.0.1:13413", None);
+        let err = Backend::<HTTPNodeClient, ExtKeychain>::new(&config, "", client)
+            .err()
+            .expect("a damaged database was opened");
+        assert_eq!(
+            err.downcast_ref::<ErrorKind>(),
+            Some(&ErrorKind::CorruptWallet(
+                dir.join(DB_DIR).to_string_lossy().to_string()
+            ))
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,539 @@
+use failure::ResultExt;
+use std::collections::{BTreeMap, HashMap};
+
+use grin_core::global;
+use grin_util::ZeroingString;
+use grin_wallet::WalletConfig;
+
+use super::api::restore;
+use super::lmdb_backend::seed_public_keys;
+use super::types::{
+    AcctPathMapping, Arc, ChildNumber, Context, ErrorKind, ExtKeychain, ExtendedPubKey, Identifier,
+    Keychain, Mutex, NodeClient, OutputData, PendingSlate, PublicKey, RestoreEnd, Result, Slate,
+    Transaction, TxLogEntry, TxProof, WalletBackend, WalletBackendBatch, WalletSeed,
+};
+
+/// What the lmdb backend keeps in its database, changed through batches
+#[derive(Clone, Default)]
+struct MemoryDb {
+    outputs: BTreeMap<(Identifier, Option<u64>), OutputData>,
+    child_indices: HashMap<Identifier, u32>,
+    confirmed_heights: HashMap<Identifier, u64>,
+    private_contexts: HashMap<String, Context>,
+    tx_logs: BTreeMap<(Identifier, u32), TxLogEntry>,
+    tx_log_ids: HashMap<Identifier, u32>,
+    accounts: BTreeMap<String, AcctPathMapping>,
+    pruned_tx_counts: HashMap<Identifier, u64>,
+    restore_index: Option<u64>,
+}
+
+/// What the lmdb backend keeps in files next to its database, written right away
+#[derive(Default)]
+struct MemoryFiles {
+    txs: HashMap<String, Transaction>,
+    tx_proofs: HashMap<String, TxProof>,
+    slates: HashMap<String, Slate>,
+    pending_slates: HashMap<String, PendingSlate>,
+}
+
+/// The data of a memory backend. It outlives the backends opened on it, so locking
+/// and unlocking the wallet or switching accounts keeps it until the process exits.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    db: Arc<Mutex<MemoryDb>>,
+    files: Arc<Mutex<MemoryFiles>>,
+}
+
+/// A backend that keeps all wallet data in memory. Only the seed is read from the
+/// data directory, nothing is written to disk.
+pub struct MemoryBackend<C, K> {
+    store: MemoryStore,
+    passphrase: ZeroingString,
+    pub keychain: Option<K>,
+    parent_key_id: Identifier,
+    config: WalletConfig,
+    w2n_client: C,
+}
+
+impl<C, K> MemoryBackend<C, K> {
+    pub fn new(
+        config: &WalletConfig,
+        passphrase: &str,
+        n_client: C,
+        store: MemoryStore,
+    ) -> Result<Self> {
+        let default_path = ExtKeychain::derive_key_id(2, 0, 0, 0, 0);
+        store
+            .db
+            .lock()
+            .accounts
+            .entry("default".to_string())
+            .or_insert_with(|| AcctPathMapping {
+                label: "default".to_string(),
+                path: default_path.clone(),
+            });
+
+        Ok(MemoryBackend {
+            store,
+            passphrase: ZeroingString::from(passphrase),
+            keychain: None,
+            parent_key_id: default_path,
+            config: config.clone(),
+            w2n_client: n_client,
+        })
+    }
+}
+
+impl<C, K> WalletBackend<C, K> for MemoryBackend<C, K>
+where
+    C: NodeClient,
+    K: Keychain,
+{
+    fn open_with_credentials(&mut self) -> Result<()> {
+        let wallet_seed = WalletSeed::from_file(&self.config, &self.passphrase)
+            .context(ErrorKind::OpenWalletError)?;
+        self.keychain = Some(
+            wallet_seed
+                .derive_keychain(global::is_floonet())
+                .context(ErrorKind::DeriveKeychainError)?,
+        );
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.keychain = None;
+        Ok(())
+    }
+
+    fn keychain(&mut self) -> &mut K {
+        self.keychain.as_mut().unwrap()
+    }
+
+    fn w2n_client(&mut self) -> &mut C {
+        &mut self.w2n_client
+    }
+
+    fn set_parent_key_id_by_name(&mut self, label: &str) -> Result<()> {
+        let account = self.store.db.lock().accounts.get(label).cloned();
+        match account {
+            Some(account) => {
+                self.set_parent_key_id(&account.path);
+                Ok(())
+            }
+            None => Err(ErrorKind::UnknownAccountLabel(label.to_string()).into()),
+        }
+    }
+
+    fn set_parent_key_id(&mut self, id: &Identifier) {
+        self.parent_key_id = id.clone();
+    }
+
+    fn get_parent_key_id(&self) -> Identifier {
+        self.parent_key_id.clone()
+    }
+
+    fn public_keys(&self) -> Result<(PublicKey, ExtendedPubKey)> {
+        seed_public_keys(&self.config, &self.passphrase, &self.parent_key_id)
+    }
+
+    fn get_output(&self, id: &Identifier, mmr_index: &Option<u64>) -> Result<OutputData> {
+        self.store
+            .db
+            .lock()
+            .outputs
+            .get(&(id.clone(), *mmr_index))
+            .cloned()
+            .ok_or(ErrorKind::ModelNotFound.into())
+    }
+
+    fn outputs<'a>(&'a self) -> Box<dyn Iterator<Item = OutputData> + 'a> {
+        let outputs: Vec<OutputData> = self.store.db.lock().outputs.values().cloned().collect();
+        Box::new(outputs.into_iter())
+    }
+
+    fn get_tx_log_by_slate_id(&self, slate_id: &str) -> Result<Option<TxLogEntry>> {
+        Ok(self
+            .tx_logs()
+            .find(|t| t.tx_slate_id.map(|id| id.to_string()) == Some(slate_id.to_string())))
+    }
+
+    fn tx_logs<'a>(&'a self) -> Box<dyn Iterator<Item = TxLogEntry> + 'a> {
+        let tx_logs: Vec<TxLogEntry> = self.store.db.lock().tx_logs.values().cloned().collect();
+        Box::new(tx_logs.into_iter())
+    }
+
+    fn get_private_context(&mut self, uuid: &str) -> Result<Context> {
+        self.store
+            .db
+            .lock()
+            .private_contexts
+            .get(uuid)
+            .cloned()
+            .ok_or(ErrorKind::ModelNotFound.into())
+    }
+
+    fn accounts<'a>(&'a self) -> Box<dyn Iterator<Item = AcctPathMapping> + 'a> {
+        let accounts: Vec<AcctPathMapping> =
+            self.store.db.lock().accounts.values().cloned().collect();
+        Box::new(accounts.into_iter())
+    }
+
+    fn get_acct_path(&self, label: &str) -> Result<AcctPathMapping> {
+        self.store
+            .db
+            .lock()
+            .accounts
+            .get(label)
+            .cloned()
+            .ok_or(ErrorKind::ModelNotFound.into())
+    }
+
+    fn get_stored_tx(&self, uuid: &str) -> Result<Transaction> {
+        self.store
+            .files
+            .lock()
+            .txs
+            .get(uuid)
+            .cloned()
+            .ok_or(ErrorKind::ModelNotFound.into())
+    }
+
+    fn has_stored_tx_proof(&self, uuid: &str) -> Result<bool> {
+        Ok(self.store.files.lock().tx_proofs.contains_key(uuid))
+    }
+
+    fn get_stored_tx_proof(&self, uuid: &str) -> Result<TxProof> {
+        self.store
+            .files
+            .lock()
+            .tx_proofs
+            .get(uuid)
+            .cloned()
+            .ok_or(ErrorKind::TransactionHasNoProof.into())
+    }
+
+    fn get_stored_slate(&self, uuid: &str) -> Result<Slate> {
+        self.store
+            .files
+            .lock()
+            .slates
+            .get(uuid)
+            .cloned()
+            .ok_or(ErrorKind::ModelNotFound.into())
+    }
+
+    fn get_pending_slate(&self, uuid: &str) -> Result<PendingSlate> {
+        self.store
+            .files
+            .lock()
+            .pending_slates
+            .get(uuid)
+            .cloned()
+            .ok_or(ErrorKind::PendingSlateNotFound(uuid.to_string()).into())
+    }
+
+    fn pending_slates(&self) -> Result<Vec<PendingSlate>> {
+        let mut pending: Vec<PendingSlate> = self
+            .store
+            .files
+            .lock()
+            .pending_slates
+            .values()
+            .cloned()
+            .collect();
+        pending.sort_by_key(|p| p.received_at);
+        Ok(pending)
+    }
+
+    fn batch<'a>(&'a self) -> Result<Box<dyn WalletBackendBatch<K> + 'a>> {
+        Ok(Box::new(MemoryBatch {
+            backend: self,
+            db: self.store.db.lock().clone(),
+            keychain: self.keychain.clone(),
+        }))
+    }
+
+    fn derive_next(&mut self) -> Result<Identifier> {
+        let mut deriv_idx = self
+            .store
+            .db
+            .lock()
+            .child_indices
+            .get(&self.parent_key_id)
+            .cloned()
+            .unwrap_or(0);
+        let mut return_path = self.parent_key_id.to_path();
+        return_path.depth = return_path.depth + 1;
+        return_path.path[return_path.depth as usize - 1] = ChildNumber::from(deriv_idx);
+        deriv_idx = deriv_idx + 1;
+        let mut batch = self.batch()?;
+        batch.save_child_index(&self.parent_key_id, deriv_idx)?;
+        batch.commit()?;
+        Ok(Identifier::from_path(&return_path))
+    }
+
+    fn get_last_confirmed_height(&self) -> Result<u64> {
+        Ok(self
+            .store
+            .db
+            .lock()
+            .confirmed_heights
+            .get(&self.parent_key_id)
+            .cloned()
+            .unwrap_or(0))
+    }
+
+    fn get_pruned_tx_count(&self) -> Result<u64> {
+        Ok(self
+            .store
+            .db
+            .lock()
+            .pruned_tx_counts
+            .get(&self.parent_key_id)
+            .cloned()
+            .unwrap_or(0))
+    }
+
+    fn get_restore_index(&self) -> Result<Option<u64>> {
+        Ok(self.store.db.lock().restore_index)
+    }
+
+    fn restore(&mut self, max_retries: u32) -> Result<RestoreEnd> {
+        let end = restore::restore(self, max_retries).context(ErrorKind::Restore)?;
+        Ok(end)
+    }
+
+    fn check_repair(&mut self) -> Result<()> {
+        restore::check_repair(self).context(ErrorKind::Restore)?;
+        Ok(())
+    }
+
+    fn calc_commit_for_cache(&mut self, amount: u64, id: &Identifier) -> Result<Option<String>> {
+        if self.config.no_commit_cache == Some(true) {
+            Ok(None)
+        } else {
+            Ok(Some(grin_util::to_hex(
+                self.keychain().commit(amount, &id)?.0.to_vec(),
+            )))
+        }
+    }
+}
+
+/// Changes to a copy of the database that replace it on commit. The backend is only
+/// used behind a lock, so no other batch can commit in between, like with lmdb's
+/// single writer.
+pub struct MemoryBatch<'a, C, K>
+where
+    C: NodeClient,
+    K: Keychain,
+{
+    backend: &'a MemoryBackend<C, K>,
+    db: MemoryDb,
+    keychain: Option<K>,
+}
+
+impl<'a, C, K> WalletBackendBatch<K> for MemoryBatch<'a, C, K>
+where
+    C: NodeClient,
+    K: Keychain,
+{
+    fn keychain(&mut self) -> &mut K {
+        self.keychain.as_mut().unwrap()
+    }
+
+    fn save_output(&mut self, out: &OutputData) -> Result<()> {
+        self.db
+            .outputs
+            .insert((out.key_id.clone(), out.mmr_index), out.clone());
+        Ok(())
+    }
+
+    fn delete_output(&mut self, id: &Identifier, mmr_index: &Option<u64>) -> Result<()> {
+        self.db.outputs.remove(&(id.clone(), *mmr_index));
+        Ok(())
+    }
+
+    fn store_tx(&self, uuid: &str, tx: &Transaction) -> Result<()> {
+        let mut files = self.backend.store.files.lock();
+        files.txs.insert(uuid.to_string(), tx.clone());
+        Ok(())
+    }
+
+    fn store_tx_proof(&self, uuid: &str, tx_proof: &TxProof) -> Result<()> {
+        let mut files = self.backend.store.files.lock();
+        files.tx_proofs.insert(uuid.to_string(), tx_proof.clone());
+        Ok(())
+    }
+
+    fn store_slate(&self, uuid: &str, slate: &Slate) -> Result<()> {
+        let mut files = self.backend.store.files.lock();
+        files.slates.insert(uuid.to_string(), slate.clone());
+        Ok(())
+    }
+
+    fn store_pending_slate(&self, pending: &PendingSlate) -> Result<()> {
+        let mut files = self.backend.store.files.lock();
+        files
+            .pending_slates
+            .insert(pending.slate.id.to_string(), pending.clone());
+        Ok(())
+    }
+
+    fn delete_pending_slate(&self, uuid: &str) -> Result<()> {
+        let mut files = self.backend.store.files.lock();
+        files
+            .pending_slates
+            .remove(uuid)
+            .ok_or(ErrorKind::PendingSlateNotFound(uuid.to_string()))?;
+        Ok(())
+    }
+
+    fn next_tx_log_id(&mut self, parent_key_id: &Identifier) -> Result<u32> {
+        let last_tx_log_id = self.db.tx_log_ids.entry(parent_key_id.clone()).or_insert(0);
+        *last_tx_log_id += 1;
+        Ok(*last_tx_log_id - 1)
+    }
+
+    fn save_last_confirmed_height(&mut self, height: u64) -> Result<()> {
+        self.db
+            .confirmed_heights
+            .insert(self.backend.parent_key_id.clone(), height);
+        Ok(())
+    }
+
+    fn save_child_index(&mut self, parent_key_id: &Identifier, index: u32) -> Result<()> {
+        self.db.child_indices.insert(parent_key_id.clone(), index);
+        Ok(())
+    }
+
+    fn save_tx_log_entry(&mut self, t: &TxLogEntry) -> Result<()> {
+        self.db
+            .tx_logs
+            .insert((t.parent_key_id.clone(), t.id), t.clone());
+        Ok(())
+    }
+
+    fn delete_tx_log_entry(&mut self, t: &TxLogEntry) -> Result<()> {
+        self.db.tx_logs.remove(&(t.parent_key_id.clone(), t.id));
+        Ok(())
+    }
+
+    fn save_pruned_tx_count(&mut self, count: u64) -> Result<()> {
+        self.db
+            .pruned_tx_counts
+            .insert(self.backend.parent_key_id.clone(), count);
+        Ok(())
+    }
+
+    fn save_restore_index(&mut self, index: u64) -> Result<()> {
+        self.db.restore_index = Some(index);
+        Ok(())
+    }
+
+    fn delete_restore_index(&mut self) -> Result<()> {
+        self.db.restore_index = None;
+        Ok(())
+    }
+
+    fn save_acct_path(&mut self, mapping: &AcctPathMapping) -> Result<()> {
+        self.db
+            .accounts
+            .insert(mapping.label.clone(), mapping.clone());
+        Ok(())
+    }
+
+    fn lock_output(&mut self, out: &mut OutputData) -> Result<()> {
+        out.lock();
+        self.save_output(out)
+    }
+
+    fn save_private_context(&mut self, uuid: &str, ctx: &Context) -> Result<()> {
+        self.db
+            .private_contexts
+            .insert(uuid.to_string(), ctx.clone());
+        Ok(())
+    }
+
+    fn delete_private_context(&mut self, uuid: &str) -> Result<()> {
+        self.db.private_contexts.remove(uuid);
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        *self.backend.store.db.lock() = self.db.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::wallet::node_client::TimeoutNodeClient;
+    use std::{env, fs};
+    use uuid::Uuid;
+
+    use super::super::types::OutputStatus;
+
+    /// A memory backend on a new seed in a fresh directory, with a client for a node
+    /// that isn't running
+    pub fn test_backend(store: MemoryStore) -> MemoryBackend<TimeoutNodeClient, ExtKeychain> {
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        let config = WalletConfig {
+            data_file_dir: dir.to_string_lossy().to_string(),
+            ..WalletConfig::default()
+        };
+        WalletSeed::init_file(&config, 32, None, "").unwrap();
+        let client = TimeoutNodeClient::new("http://127.0.0.1:13413", None, 1);
+        MemoryBackend::new(&config, "", client, store).unwrap()
+    }
+
+    fn output(backend: &MemoryBackend<TimeoutNodeClient, ExtKeychain>, n_child: u32) -> OutputData {
+        OutputData {
+            root_key_id: backend.get_parent_key_id(),
+            key_id: ExtKeychain::derive_key_id(3, 2, 0, 0, n_child),
+            n_child,
+            commit: None,
+            mmr_index: None,
+            value: 1_000_000_000,
+            status: OutputStatus::Unspent,
+            height: 1,
+            lock_height: 0,
+            is_coinbase: false,
+            tx_log_entry: None,
+        }
+    }
+
+    #[test]
+    fn keeps_data_across_backends() {
+        let store = MemoryStore::default();
+        let mut backend = test_backend(store.clone());
+        backend.open_with_credentials().unwrap();
+        let out = output(&backend, 0);
+        {
+            let mut batch = backend.batch().unwrap();
+            batch.save_output(&out).unwrap();
+            batch.commit().unwrap();
+        }
+        let key_id = backend.derive_next().unwrap();
+        backend.close().unwrap();
+
+        let mut reopened = test_backend(store);
+        reopened.open_with_credentials().unwrap();
+        assert_eq!(reopened.outputs().collect::<Vec<_>>(), vec![out]);
+        assert_ne!(reopened.derive_next().unwrap(), key_id);
+        assert!(reopened.get_acct_path("default").is_ok());
+    }
+
+    #[test]
+    fn discards_uncommitted_batches() {
+        let backend = test_backend(MemoryStore::default());
+        let out = output(&backend, 0);
+        {
+            let mut batch = backend.batch().unwrap();
+            batch.save_output(&out).unwrap();
+        }
+        assert_eq!(backend.outputs().count(), 0);
+        assert!(backend.get_output(&out.key_id, &out.mmr_index).is_err());
+    }
+}
@@ -1,6 +1,11 @@
+mod data_lock;
 mod lmdb_backend;
+mod memory_backend;
 
 use super::api;
 use super::types;
 
-pub use self::lmdb_backend::Backend;
+pub use self::data_lock::DataLock;
+pub use self::lmdb_backend::{check_writable, remove_debris, Backend};
+#[cfg(test)]
+pub use self::memory_backend::tests::test_backend;
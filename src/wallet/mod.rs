@@ -1,6 +1,9 @@
 mod api;
 mod backend;
+mod node_client;
 mod wallet;
 
 pub mod types;
+pub use self::api::restore::interrupt_restore;
+pub use self::backend::{check_writable, remove_debris, DataLock};
 pub use self::wallet::Wallet;
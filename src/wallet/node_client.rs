@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use grin_util::secp::pedersen;
+use grin_wallet::libwallet::types::TxWrapper;
+use grin_wallet::libwallet::{Error, ErrorKind};
+use grin_wallet::{HTTPNodeClient, NodeClient};
+
+/// A node client whose requests give up after `timeout_secs` seconds, so a node that
+/// hangs can't hang the wallet waiting on it. A request that timed out is left to
+/// finish in the background.
+#[derive(Clone)]
+pub struct TimeoutNodeClient {
+    inner: HTTPNodeClient,
+    timeout_secs: u64,
+}
+
+impl TimeoutNodeClient {
+    pub fn new(node_url: &str, node_api_secret: Option<String>, timeout_secs: u64) -> Self {
+        Self {
+            inner: HTTPNodeClient::new(node_url, node_api_secret),
+            timeout_secs,
+        }
+    }
+
+    fn call<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(HTTPNodeClient) -> Result<T, Error> + Send + 'static,
+    {
+        let client = self.inner.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            tx.send(f(client)).is_ok();
+        });
+        rx.recv_timeout(Duration::from_secs(self.timeout_secs))
+            .map_err(|_| {
+                ErrorKind::GenericError(format!(
+                    "node {} did not answer within {} seconds",
+                    self.inner.node_url(),
+                    self.timeout_secs
+                ))
+            })?
+    }
+}
+
+impl NodeClient for TimeoutNodeClient {
+    fn node_url(&self) -> &str {
+        self.inner.node_url()
+    }
+
+    fn set_node_url(&mut self, node_url: &str) {
+        self.inner.set_node_url(node_url);
+    }
+
+    fn node_api_secret(&self) -> Option<String> {
+        self.inner.node_api_secret()
+    }
+
+    fn set_node_api_secret(&mut self, node_api_secret: Option<String>) {
+        self.inner.set_node_api_secret(node_api_secret);
+    }
+
+    fn post_tx(&self, tx: &TxWrapper, fluff: bool) -> Result<(), Error> {
+        let tx = TxWrapper {
+            tx_hex: tx.tx_hex.clone(),
+        };
+        self.call(move |client| client.post_tx(&tx, fluff))
+    }
+
+    fn get_chain_height(&self) -> Result<u64, Error> {
+        self.call(|client| client.get_chain_height())
+    }
+
+    fn get_outputs_from_node(
+        &self,
+        wallet_outputs: Vec<pedersen::Commitment>,
+    ) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, Error> {
+        self.call(move |client| client.get_outputs_from_node(wallet_outputs))
+    }
+
+    fn get_outputs_by_pmmr_index(
+        &self,
+        start_height: u64,
+        max_outputs: u64,
+    ) -> Result<
+        (
+            u64,
+            u64,
+            Vec<(pedersen::Commitment, pedersen::RangeProof, bool, u64, u64)>,
+        ),
+        Error,
+    > {
+        self.call(move |client| client.get_outputs_by_pmmr_index(start_height, max_outputs))
+    }
+}
@@ -0,0 +1,27 @@
+use chrono::prelude::*;
+
+use super::WalletInfo;
+
+/// Balance of the active account as of the last refreshed `info`, kept next to the
+/// wallet so it can be shown without opening it or querying the node
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedBalance {
+    /// Account the balance belongs to
+    pub account: String,
+    pub info: WalletInfo,
+    /// Whether the balance was verified against the node when it was cached
+    pub validated: bool,
+    /// When the balance was cached
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CachedBalance {
+    pub fn new(account: &str, info: WalletInfo, validated: bool) -> Self {
+        Self {
+            account: account.to_string(),
+            info,
+            validated,
+            updated_at: Utc::now(),
+        }
+    }
+}
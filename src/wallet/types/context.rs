@@ -29,6 +29,10 @@ pub struct Context {
     /// Input commitments
     #[serde(default)]
     pub input_commits: Vec<Commitment>,
+    /// The secrets were moved to an external signing key, so the slate has to come
+    /// back signed with it
+    #[serde(default)]
+    pub external_signer: bool,
 }
 
 impl Context {
@@ -44,6 +48,7 @@ impl Context {
             context_type,
             output_commits: vec![],
             input_commits: vec![],
+            external_signer: false,
         }
     }
 }
@@ -0,0 +1,85 @@
+use grin_util::secp::pedersen;
+
+use super::{Identifier, OutputData, OutputStatus};
+
+/// An output as written by `outputs --export`, for analysis outside of the wallet and
+/// for `import-outputs` to read back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedOutput {
+    pub commitment: String,
+    /// Derivation path of the output's key, which also gives its account
+    pub key_id: Identifier,
+    /// Value in nanogrins
+    pub amount: u64,
+    pub height: u64,
+    pub lock_height: u64,
+    pub status: OutputStatus,
+    pub is_coinbase: bool,
+    /// Local id of the transaction that created or spent the output
+    pub tx_id: Option<u32>,
+}
+
+impl ExportedOutput {
+    pub fn new(output: &OutputData, commit: &pedersen::Commitment) -> Self {
+        Self {
+            commitment: grin_util::to_hex(commit.0.to_vec()),
+            key_id: output.key_id.clone(),
+            amount: output.value,
+            height: output.height,
+            lock_height: output.lock_height,
+            status: output.status.clone(),
+            is_coinbase: output.is_coinbase,
+            tx_id: output.tx_log_entry,
+        }
+    }
+
+    /// The output to import, its position and status are looked up on the node. The
+    /// transaction id is local to the exporting wallet and isn't kept.
+    pub fn to_output_data(&self) -> OutputData {
+        OutputData {
+            root_key_id: self.key_id.parent_path(),
+            key_id: self.key_id.clone(),
+            n_child: self.key_id.to_path().last_path_index(),
+            commit: None,
+            mmr_index: None,
+            value: self.amount,
+            status: self.status.clone(),
+            height: self.height,
+            lock_height: self.lock_height,
+            is_coinbase: self.is_coinbase,
+            tx_log_entry: None,
+        }
+    }
+
+    pub fn csv_header() -> &'static str {
+        "commitment,key_id,amount,height,lock_height,status,is_coinbase,tx_id"
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        let tx_id = self.tx_id.map(|id| id.to_string()).unwrap_or_default();
+        [
+            self.commitment.clone(),
+            self.key_id.to_hex(),
+            self.amount.to_string(),
+            self.height.to_string(),
+            self.lock_height.to_string(),
+            self.status.to_string(),
+            self.is_coinbase.to_string(),
+            tx_id,
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<String>>()
+        .join(",")
+    }
+}
+
+/// Quotes a field when it holds a separator, a quote or a line break, doubling any
+/// quotes inside it
+fn csv_field(field: &str) -> String {
+    if field.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace("\"", "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,108 @@
+use grin_core::consensus::GRIN_BASE;
+use grin_core::libtx::tx_fee;
+
+use common::{amount_to_string, ErrorKind, Result};
+
+/// Upper bound for the fee per unit of transaction weight
+const MAX_FEE_PER_WEIGHT: u64 = GRIN_BASE / 10;
+/// Upper bound for the minimum fee of a transaction
+const MAX_MIN_FEE: u64 = GRIN_BASE;
+/// Without a configured cap, fees above this multiple of the consensus fee are refused
+const DEFAULT_MAX_FEE_MULTIPLE: u64 = 10;
+
+/// Overrides of the default fee model, as set in the config
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeeOverrides {
+    /// base fee per unit of transaction weight, replacing the consensus default
+    pub fee_per_weight: Option<u64>,
+    /// lowest fee a transaction will be built with
+    pub min_fee: Option<u64>,
+    /// highest fee a transaction will be built or reposted with
+    pub max_fee: Option<u64>,
+    /// skip the max fee check for a single transaction
+    pub allow_high_fee: bool,
+}
+
+impl FeeOverrides {
+    pub fn new(fee_per_weight: Option<u64>, min_fee: Option<u64>, max_fee: Option<u64>) -> Self {
+        Self {
+            fee_per_weight,
+            min_fee,
+            max_fee,
+            allow_high_fee: false,
+        }
+    }
+
+    /// Make sure the overrides are within sane bounds
+    pub fn validate(&self) -> Result<()> {
+        if let Some(fee_per_weight) = self.fee_per_weight {
+            if fee_per_weight == 0 || fee_per_weight > MAX_FEE_PER_WEIGHT {
+                return Err(ErrorKind::InvalidFeeOverride(format!(
+                    "fee_per_weight must be between 1 and {}",
+                    MAX_FEE_PER_WEIGHT
+                )))?;
+            }
+        }
+        if let Some(min_fee) = self.min_fee {
+            if min_fee > MAX_MIN_FEE {
+                return Err(ErrorKind::InvalidFeeOverride(format!(
+                    "min_fee must be at most {}",
+                    MAX_MIN_FEE
+                )))?;
+            }
+        }
+        if let Some(max_fee) = self.max_fee {
+            if self.min_fee.unwrap_or(0) > max_fee {
+                return Err(ErrorKind::InvalidFeeOverride(format!(
+                    "min_fee must be at most max_fee ({})",
+                    max_fee
+                )))?;
+            }
+            // not even a transaction with a single input and output could be built
+            let smallest_fee = tx_fee(1, 1, 1, self.fee_per_weight);
+            if smallest_fee > max_fee {
+                return Err(ErrorKind::InvalidFeeOverride(format!(
+                    "fee_per_weight makes the smallest transaction cost {}, above max_fee ({})",
+                    smallest_fee, max_fee
+                )))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fee for a transaction with the given number of inputs, outputs and kernels
+    pub fn tx_fee(&self, num_inputs: usize, num_outputs: usize, num_kernels: usize) -> u64 {
+        let fee = tx_fee(num_inputs, num_outputs, num_kernels, self.fee_per_weight);
+        std::cmp::max(fee, self.min_fee.unwrap_or(0))
+    }
+
+    /// Refuse a fee above the configured cap, or above a multiple of the fee
+    /// the overrides give the same transaction if no cap is configured
+    pub fn check_fee(
+        &self,
+        fee: u64,
+        num_inputs: usize,
+        num_outputs: usize,
+        num_kernels: usize,
+    ) -> Result<()> {
+        if self.allow_high_fee {
+            return Ok(());
+        }
+        let cap = self.max_fee.unwrap_or_else(|| {
+            DEFAULT_MAX_FEE_MULTIPLE.saturating_mul(self.tx_fee(
+                num_inputs,
+                num_outputs,
+                num_kernels,
+            ))
+        });
+        if fee > cap {
+            return Err(ErrorKind::FeeTooHigh {
+                computed: fee,
+                computed_disp: amount_to_string(fee, false),
+                cap,
+                cap_disp: amount_to_string(cap, false),
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -1,41 +1,62 @@
 mod acct_path_mapping;
 mod block_fees;
 mod block_identifier;
+mod cached_balance;
 mod cb_data;
 mod context;
 mod context_type;
+mod exported_output;
+mod fee_overrides;
 mod output_data;
 mod output_status;
+mod pending_slate;
+mod proof_bundle;
+mod restore_end;
+mod send_result;
+mod signing_key;
 mod tx_log_entry;
 mod tx_log_entry_type;
 mod tx_proof;
+mod tx_state;
 mod wallet_backend;
 mod wallet_backend_batch;
 mod wallet_info;
 mod wallet_inst;
+mod watch_data;
 
 pub use grin_core::core::hash::Hash;
 pub use grin_core::core::{Output, Transaction, TxKernel};
+pub use grin_keychain::extkey_bip32::ExtendedPubKey;
 pub use grin_keychain::{ChildNumber, ExtKeychain, Identifier, Keychain};
 pub use grin_util::secp::key::{PublicKey, SecretKey};
-pub use grin_wallet::{EncryptedWalletSeed, Slate, WalletSeed};
 pub use grin_wallet::libwallet::types::{NodeClient, TxWrapper};
+pub use grin_wallet::{EncryptedWalletSeed, Slate, WalletSeed};
 
 pub use common::{Arc, Error, ErrorKind, Mutex, MutexGuard, Result};
 
 pub use self::acct_path_mapping::AcctPathMapping;
 pub use self::block_fees::BlockFees;
 pub use self::block_identifier::BlockIdentifier;
+pub use self::cached_balance::CachedBalance;
 pub use self::cb_data::CbData;
 pub use self::context::Context;
 pub use self::context_type::ContextType;
+pub use self::exported_output::ExportedOutput;
+pub use self::fee_overrides::FeeOverrides;
 pub use self::output_data::OutputData;
 pub use self::output_status::OutputStatus;
+pub use self::pending_slate::PendingSlate;
+pub use self::proof_bundle::{ProofBundle, ProofBundleContent};
+pub use self::restore_end::RestoreEnd;
+pub use self::send_result::SendResult;
+pub use self::signing_key::SigningKey;
 pub use self::tx_log_entry::TxLogEntry;
 pub use self::tx_log_entry_type::TxLogEntryType;
 pub use self::tx_proof::ErrorKind as TxProofErrorKind;
 pub use self::tx_proof::TxProof;
+pub use self::tx_state::{TxState, TxStateChange};
 pub use self::wallet_backend::WalletBackend;
 pub use self::wallet_backend_batch::WalletBackendBatch;
 pub use self::wallet_info::WalletInfo;
 pub use self::wallet_inst::WalletInst;
+pub use self::watch_data::{WatchData, WatchOutput};
@@ -0,0 +1,34 @@
+use chrono::prelude::*;
+use chrono::Duration;
+
+use super::{Slate, TxProof};
+
+/// A slate received while listening in manual mode, kept until it is accepted or rejected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingSlate {
+    pub from: String,
+    pub slate: Slate,
+    pub tx_proof: Option<TxProof>,
+    pub received_at: DateTime<Utc>,
+}
+
+impl PendingSlate {
+    pub fn new(from: &str, slate: &Slate, tx_proof: Option<&TxProof>) -> Self {
+        Self {
+            from: from.to_string(),
+            slate: slate.clone(),
+            tx_proof: tx_proof.cloned(),
+            received_at: Utc::now(),
+        }
+    }
+
+    /// Whether the slate is a response to a transaction we started, in which case it only
+    /// has to be finalized.
+    pub fn is_response(&self) -> bool {
+        self.slate.num_participants <= self.slate.participant_data.len()
+    }
+
+    pub fn is_expired(&self, ttl: Duration) -> bool {
+        Utc::now().signed_duration_since(self.received_at) > ttl
+    }
+}
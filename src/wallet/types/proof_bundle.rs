@@ -0,0 +1,139 @@
+use chrono::prelude::*;
+use grin_core::ser;
+use uuid::Uuid;
+
+use super::{Slate, Transaction, TxLogEntry, TxLogEntryType, TxProof};
+use crate::common::crypto::{
+    parse_public_key, sign_challenge, verify_signature, Hex, SecretKey, Signature,
+};
+use crate::common::{ErrorKind, Result};
+
+/// Everything known about a transaction, for settling a dispute about it outside of
+/// the wallet. Signed with the grinbox key of the wallet that exported it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProofBundle {
+    /// The serialized `ProofBundleContent`, kept as the exact text that was signed
+    pub content: String,
+    /// Grinbox public key of the wallet that signed the bundle
+    pub signer: String,
+    /// Signature of `content`
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProofBundleContent {
+    /// Local id of the transaction in the exporting wallet
+    pub tx_id: u32,
+    pub slate_id: Option<Uuid>,
+    pub tx_type: TxLogEntryType,
+    /// Address of the other party, if known
+    pub address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub amount_credited: u64,
+    pub amount_debited: u64,
+    pub fee: Option<u64>,
+    /// Excess of the transaction kernel, to look the transaction up on chain
+    pub kernel_excess: Option<String>,
+    /// The stored transaction, hex encoded
+    pub tx_hex: Option<String>,
+    pub slate: Option<Slate>,
+    /// Payment proof, only for transactions sent over grinbox
+    pub proof: Option<TxProof>,
+}
+
+impl ProofBundle {
+    pub fn new(
+        entry: &TxLogEntry,
+        tx: Option<Transaction>,
+        slate: Option<Slate>,
+        proof: Option<TxProof>,
+        signer: &str,
+        secret_key: &SecretKey,
+    ) -> Result<Self> {
+        let kernel_excess = tx.as_ref().and_then(ProofBundle::kernel_excess);
+        let tx_hex = tx.map(|tx| grin_util::to_hex(ser::ser_vec(&tx).unwrap()));
+        let content = ProofBundleContent {
+            tx_id: entry.id,
+            slate_id: entry.tx_slate_id,
+            tx_type: entry.tx_type.clone(),
+            address: entry.address.clone(),
+            created_at: entry.creation_ts,
+            confirmed_at: entry.confirmation_ts,
+            amount_credited: entry.amount_credited,
+            amount_debited: entry.amount_debited,
+            fee: entry.fee,
+            kernel_excess,
+            tx_hex,
+            slate,
+            proof,
+        };
+        let content = serde_json::to_string(&content)?;
+        let signature = sign_challenge(&content, secret_key)?;
+        Ok(Self {
+            content,
+            signer: signer.to_string(),
+            signature: signature.to_hex(),
+        })
+    }
+
+    /// Checks that the bundle was signed by `signer` and that its parts describe the
+    /// same transaction, returning its content. Says nothing about whether it made it to
+    /// the chain.
+    pub fn verify(&self) -> Result<ProofBundleContent> {
+        let invalid = |reason: &str| ErrorKind::InvalidProofBundle(reason.to_string());
+        let public_key = parse_public_key(&self.signer).map_err(|_| invalid("bad signer key"))?;
+        let signature =
+            Signature::from_hex(&self.signature).map_err(|_| invalid("bad signature encoding"))?;
+        verify_signature(&self.content, &signature, &public_key)
+            .map_err(|_| invalid("the signature does not match the content"))?;
+
+        let content = self.content()?;
+        if let Some(ref tx_hex) = content.tx_hex {
+            let tx_bin = grin_util::from_hex(tx_hex.clone())
+                .map_err(|_| invalid("bad transaction encoding"))?;
+            let tx: Transaction = ser::deserialize(&mut &tx_bin[..])
+                .map_err(|_| invalid("bad transaction encoding"))?;
+            if ProofBundle::kernel_excess(&tx) != content.kernel_excess {
+                Err(invalid(
+                    "the kernel excess is not the one of the transaction",
+                ))?;
+            }
+        }
+
+        if let Some(ref slate) = content.slate {
+            if Some(slate.id) != content.slate_id {
+                Err(invalid("the slate belongs to another transaction"))?;
+            }
+        }
+
+        if let Some(ref proof) = content.proof {
+            let (_, slate) = proof
+                .verify_extract(None)
+                .map_err(|_| invalid("the payment proof does not verify"))?;
+            if Some(slate.id) != content.slate_id {
+                Err(invalid("the payment proof belongs to another transaction"))?;
+            }
+            // the proof is kept by the sender, who paid the amount and the fee
+            let spent = content
+                .amount_debited
+                .saturating_sub(content.amount_credited);
+            if proof.amount.checked_add(proof.fee) != Some(spent) {
+                Err(invalid("the payment proof is for another amount"))?;
+            }
+        }
+        Ok(content)
+    }
+
+    /// The content of the bundle, without checking its signature
+    pub fn content(&self) -> Result<ProofBundleContent> {
+        serde_json::from_str(&self.content)
+            .map_err(|_| ErrorKind::InvalidProofBundle("bad content encoding".to_string()).into())
+    }
+
+    fn kernel_excess(tx: &Transaction) -> Option<String> {
+        tx.kernels()
+            .first()
+            .map(|kernel| grin_util::to_hex(kernel.excess.0.to_vec()))
+    }
+}
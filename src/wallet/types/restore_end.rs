@@ -0,0 +1,11 @@
+/// How a restore ended. A restore that stopped early saved its progress, and
+/// running it again resumes where it stopped
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RestoreEnd {
+    /// The whole chain was scanned
+    Complete,
+    /// The restore was asked to stop
+    Interrupted,
+    /// The node kept failing until the restore ran out of retries
+    GaveUp,
+}
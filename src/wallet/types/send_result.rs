@@ -0,0 +1,25 @@
+use uuid::Uuid;
+
+use super::Slate;
+
+/// The outcome of a send, for scripts consuming the command output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendResult {
+    pub slate_id: Uuid,
+    pub amount: u64,
+    pub fee: u64,
+    pub total: u64,
+    pub num_inputs: usize,
+}
+
+impl SendResult {
+    pub fn from_slate(slate: &Slate) -> Self {
+        Self {
+            slate_id: slate.id.clone(),
+            amount: slate.amount,
+            fee: slate.fee,
+            total: slate.amount + slate.fee,
+            num_inputs: slate.tx.inputs().len(),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use uuid::Uuid;
+
+use super::{ExtKeychain, Keychain, PublicKey, SecretKey, Slate};
+use crate::common::{ErrorKind, Result};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+/// A participant's signing secrets for one slate, moved out of the wallet to be kept
+/// elsewhere, e.g. on an offline machine
+pub struct SigningKey {
+    /// Slate the key signs
+    pub slate_id: Uuid,
+    /// Secret key (of which the public blind excess is in the slate)
+    pub sec_key: SecretKey,
+    /// Secret nonce (of which the public nonce is in the slate). Dropped once it was
+    /// used, since signing twice with the same nonce gives the secret key away
+    pub sec_nonce: Option<SecretKey>,
+}
+
+impl SigningKey {
+    /// Adds the partial signature of the participant this key belongs to. Only needs
+    /// the key itself, not a wallet
+    pub fn sign(&mut self, slate: &mut Slate) -> Result<()> {
+        if slate.id != self.slate_id {
+            return Err(ErrorKind::KeyNotInSlate)?;
+        }
+        let sec_nonce = self.sec_nonce.clone().ok_or(ErrorKind::SigningKeyUsed)?;
+
+        let keychain = ExtKeychain::from_random_seed()?;
+        let public_excess = PublicKey::from_secret_key(keychain.secp(), &self.sec_key)?;
+        let public_nonce = PublicKey::from_secret_key(keychain.secp(), &sec_nonce)?;
+        let participant_id = slate
+            .participant_data
+            .iter()
+            .find(|p| p.public_blind_excess == public_excess && p.public_nonce == public_nonce)
+            .map(|p| p.id as usize)
+            .ok_or(ErrorKind::KeyNotInSlate)?;
+
+        slate.fill_round_2(&keychain, &self.sec_key, &sec_nonce, participant_id)?;
+        self.sec_nonce = None;
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use chrono::prelude::*;
 use grin_core::ser;
 use uuid::Uuid;
 
-use super::{Identifier, TxLogEntryType};
+use super::{Identifier, TxLogEntryType, TxState, TxStateChange};
 
 /// Optional transaction information, recorded when an event happens
 /// to add or remove funds from a wallet. One Transaction log entry
@@ -40,6 +40,26 @@ pub struct TxLogEntry {
     pub amount_debited: u64,
     /// Fee
     pub fee: Option<u64>,
+    /// Messages attached to the slate by the participants
+    #[serde(default)]
+    pub messages: Option<Vec<String>>,
+    /// A note of our own, kept only in this wallet
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Time after which a send that wasn't finalized yet is cancelled by the listener
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// States the transaction went through, oldest first
+    #[serde(default)]
+    pub state_log: Vec<TxStateChange>,
+    /// Send this one was built to replace with a higher fee. Both spend the same inputs
+    /// and are kept until one of them confirms
+    #[serde(default)]
+    pub replaces: Option<u32>,
+    /// Whether the send is fluffed to the network once finalized, skipping the
+    /// dandelion stem phase
+    #[serde(default)]
+    pub fluff: bool,
 }
 
 impl TxLogEntry {
@@ -59,6 +79,12 @@ impl TxLogEntry {
             num_inputs: 0,
             num_outputs: 0,
             fee: None,
+            messages: None,
+            note: None,
+            expires_at: None,
+            state_log: vec![],
+            replaces: None,
+            fluff: false,
         }
     }
 
@@ -66,6 +92,14 @@ impl TxLogEntry {
     pub fn update_confirmation_ts(&mut self) {
         self.confirmation_ts = Some(Utc::now());
     }
+
+    /// Record that the transaction went into `state` now
+    pub fn log_state(&mut self, state: TxState) {
+        self.state_log.push(TxStateChange {
+            state,
+            at: Utc::now(),
+        });
+    }
 }
 
 impl ser::Writeable for TxLogEntry {
@@ -21,7 +21,7 @@ pub enum ErrorKind {
     ParseSlate,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxProof {
     pub address: GrinboxAddress,
     pub message: String,
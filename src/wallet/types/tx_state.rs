@@ -0,0 +1,45 @@
+use chrono::prelude::*;
+use std::fmt;
+
+/// Steps in the life of a transaction, recorded in the state log of its entry
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub enum TxState {
+    /// The slate was built
+    Created,
+    /// Inputs spent by the transaction were locked
+    OutputsLocked,
+    /// The slate was delivered to the other party
+    Sent,
+    /// The slate of the other party was received
+    Received,
+    /// The transaction was finalized
+    Finalized,
+    /// The transaction was posted to the node
+    Posted,
+    /// The outputs of the transaction were found on chain
+    Confirmed,
+    /// The transaction was cancelled
+    Cancelled,
+}
+
+impl fmt::Display for TxState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TxState::Created => write!(f, "created"),
+            TxState::OutputsLocked => write!(f, "outputs-locked"),
+            TxState::Sent => write!(f, "sent"),
+            TxState::Received => write!(f, "received"),
+            TxState::Finalized => write!(f, "finalized"),
+            TxState::Posted => write!(f, "posted"),
+            TxState::Confirmed => write!(f, "confirmed"),
+            TxState::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// A state a transaction went into, and when
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TxStateChange {
+    pub state: TxState,
+    pub at: DateTime<Utc>,
+}
@@ -1,6 +1,7 @@
 use super::{
-    AcctPathMapping, Context, Identifier, Keychain, NodeClient, OutputData, Result, Transaction,
-    TxLogEntry, TxProof, WalletBackendBatch,
+    AcctPathMapping, Context, ExtendedPubKey, Identifier, Keychain, NodeClient, OutputData,
+    PendingSlate, PublicKey, RestoreEnd, Result, Slate, Transaction, TxLogEntry, TxProof,
+    WalletBackendBatch,
 };
 
 pub trait WalletBackend<C, K>
@@ -10,9 +11,10 @@ where
 {
     fn open_with_credentials(&mut self) -> Result<()>;
     fn close(&mut self) -> Result<()>;
-    fn restore(&mut self) -> Result<()>;
+    fn restore(&mut self, max_retries: u32) -> Result<RestoreEnd>;
     fn check_repair(&mut self) -> Result<()>;
     fn get_parent_key_id(&self) -> Identifier;
+    fn public_keys(&self) -> Result<(PublicKey, ExtendedPubKey)>;
     fn set_parent_key_id(&mut self, id: &Identifier);
     fn set_parent_key_id_by_name(&mut self, label: &str) -> Result<()>;
     fn w2n_client(&mut self) -> &mut C;
@@ -23,9 +25,14 @@ where
     fn get_private_context(&mut self, uuid: &str) -> Result<Context>;
     fn get_acct_path(&self, label: &str) -> Result<AcctPathMapping>;
     fn get_last_confirmed_height(&self) -> Result<u64>;
+    fn get_pruned_tx_count(&self) -> Result<u64>;
+    fn get_restore_index(&self) -> Result<Option<u64>>;
     fn get_stored_tx(&self, uuid: &str) -> Result<Transaction>;
     fn has_stored_tx_proof(&self, uuid: &str) -> Result<bool>;
     fn get_stored_tx_proof(&self, uuid: &str) -> Result<TxProof>;
+    fn get_stored_slate(&self, uuid: &str) -> Result<Slate>;
+    fn get_pending_slate(&self, uuid: &str) -> Result<PendingSlate>;
+    fn pending_slates(&self) -> Result<Vec<PendingSlate>>;
     fn get_tx_log_by_slate_id(&self, slate_id: &str) -> Result<Option<TxLogEntry>>;
     fn outputs<'a>(&'a self) -> Box<dyn Iterator<Item = OutputData> + 'a>;
     fn tx_logs<'a>(&'a self) -> Box<dyn Iterator<Item = TxLogEntry> + 'a>;
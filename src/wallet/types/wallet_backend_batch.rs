@@ -1,7 +1,8 @@
 use crate::wallet::types::TxProof;
 
 use super::{
-    AcctPathMapping, Context, Identifier, Keychain, OutputData, Result, Transaction, TxLogEntry,
+    AcctPathMapping, Context, Identifier, Keychain, OutputData, PendingSlate, Result, Slate,
+    Transaction, TxLogEntry,
 };
 
 pub trait WalletBackendBatch<K>
@@ -16,10 +17,17 @@ where
     fn save_last_confirmed_height(&mut self, height: u64) -> Result<()>;
     fn next_tx_log_id(&mut self, parent_key_id: &Identifier) -> Result<u32>;
     fn save_tx_log_entry(&mut self, t: &TxLogEntry) -> Result<()>;
+    fn delete_tx_log_entry(&mut self, t: &TxLogEntry) -> Result<()>;
+    fn save_pruned_tx_count(&mut self, count: u64) -> Result<()>;
+    fn save_restore_index(&mut self, index: u64) -> Result<()>;
+    fn delete_restore_index(&mut self) -> Result<()>;
     fn save_acct_path(&mut self, mapping: &AcctPathMapping) -> Result<()>;
     fn save_private_context(&mut self, uuid: &str, ctx: &Context) -> Result<()>;
     fn delete_private_context(&mut self, uuid: &str) -> Result<()>;
     fn store_tx(&self, uuid: &str, tx: &Transaction) -> Result<()>;
     fn store_tx_proof(&self, uuid: &str, tx_proof: &TxProof) -> Result<()>;
+    fn store_slate(&self, uuid: &str, slate: &Slate) -> Result<()>;
+    fn store_pending_slate(&self, pending: &PendingSlate) -> Result<()>;
+    fn delete_pending_slate(&self, uuid: &str) -> Result<()>;
     fn commit(&mut self) -> Result<()>;
 }
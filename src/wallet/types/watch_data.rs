@@ -0,0 +1,112 @@
+use blake2_rfc::blake2b::blake2b;
+use grin_core::global;
+use grin_util::secp::pedersen::{Commitment, RangeProof};
+use grin_util::secp::Secp256k1;
+
+use super::{ExtendedPubKey, Identifier, OutputData, SecretKey};
+
+/// Public view of an account, enough to find its outputs on chain and track its
+/// balance against a node but not to spend any of the outputs
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WatchData {
+    /// Account the data was exported from
+    pub account: String,
+    /// Extended public key of the account
+    pub xpub: String,
+    /// Derivation path of the account, outputs of other accounts are skipped
+    pub parent_key_id: Identifier,
+    /// Hex of the key the wallet's range proofs are rewound with. It gives the value
+    /// and key id of every output of the wallet, in any account, but not their
+    /// blinding factors, so it can't spend them
+    pub rewind_key: String,
+    /// Output index to resume the chain scan from
+    pub next_index: u64,
+    /// Unspent outputs found so far
+    pub outputs: Vec<WatchOutput>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WatchOutput {
+    /// The output commitment
+    pub commit: Commitment,
+    /// Value of the output
+    pub value: u64,
+    /// Height of the output
+    pub height: u64,
+    /// Height the output is locked until
+    pub lock_height: u64,
+    /// Is this a coinbase output?
+    pub is_coinbase: bool,
+}
+
+impl WatchData {
+    pub fn new(
+        account: &str,
+        xpub: &ExtendedPubKey,
+        parent_key_id: Identifier,
+        rewind_key: &SecretKey,
+        outputs: Vec<(OutputData, Commitment)>,
+    ) -> Self {
+        // the exported outputs are known, the scan picks up from the newest on chain
+        let next_index = outputs
+            .iter()
+            .filter_map(|(out, _)| out.mmr_index)
+            .max()
+            .map(|index| index + 1)
+            .unwrap_or(1);
+        let outputs = outputs
+            .into_iter()
+            .map(|(out, commit)| WatchOutput {
+                commit,
+                value: out.value,
+                height: out.height,
+                lock_height: out.lock_height,
+                is_coinbase: out.is_coinbase,
+            })
+            .collect();
+
+        Self {
+            account: account.to_string(),
+            xpub: xpub.to_string(),
+            parent_key_id,
+            rewind_key: grin_util::to_hex(rewind_key.0.to_vec()),
+            next_index,
+            outputs,
+        }
+    }
+
+    /// Rewinds the range proof of an output on chain, the same way the wallet does
+    /// on restore. Returns the output if it belongs to the account.
+    pub fn identify(
+        &self,
+        secp: &Secp256k1,
+        rewind_key: &SecretKey,
+        output: (Commitment, RangeProof, bool, u64, u64),
+    ) -> Option<WatchOutput> {
+        let (commit, proof, is_coinbase, height, _) = output;
+        // hash(commit|rewind key) is the nonce the proof was built with
+        let nonce = blake2b(32, &commit.0, &rewind_key.0[..]);
+        let nonce = SecretKey::from_slice(secp, nonce.as_bytes()).ok()?;
+        let info = secp.rewind_bullet_proof(commit, nonce, None, proof).ok()?;
+        if !info.success {
+            return None;
+        }
+
+        let key_id = Identifier::from_serialized_path(3u8, &info.message.as_bytes());
+        if key_id.parent_path() != self.parent_key_id {
+            return None;
+        }
+
+        let lock_height = match is_coinbase {
+            true => height + global::coinbase_maturity(),
+            false => height,
+        };
+        Some(WatchOutput {
+            commit,
+            value: info.value,
+            height,
+            lock_height,
+            is_coinbase,
+        })
+    }
+}
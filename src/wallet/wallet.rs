@@ -1,27 +1,96 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use grin_api::{client, BlockHeaderPrintable};
+use grin_core::core::hash::Hashed;
+use grin_core::genesis;
+use grin_core::global::ChainTypes;
+use grin_core::ser;
+use grin_util::from_hex;
 use grin_util::secp::pedersen;
-use grin_wallet::{HTTPNodeClient, NodeClient, WalletConfig};
+use grin_util::secp::{ContextFlag, Secp256k1};
+use grin_util::ZeroingString;
+use grin_wallet::{NodeClient, WalletConfig};
+use serde::de::DeserializeOwned;
 use uuid::Uuid;
 
 use common::config::Wallet713Config;
+use common::time;
 use common::{ErrorKind, Result};
 
 use super::api::{controller, display};
-use super::backend::Backend;
+use super::backend::{check_writable, Backend, MemoryBackend, MemoryStore};
+use super::node_client::TimeoutNodeClient;
 use super::types::{
-    Arc, BlockFees, CbData, ExtKeychain, Mutex, OutputData, SecretKey, Slate, Transaction,
-    TxLogEntry, WalletBackend, WalletInfo, WalletInst, WalletSeed,
+    Arc, BlockFees, CachedBalance, CbData, ExportedOutput, ExtKeychain, FeeOverrides, Keychain,
+    Mutex, OutputData, OutputStatus, PendingSlate, ProofBundle, RestoreEnd, SecretKey, SendResult,
+    SigningKey, Slate, Transaction, TxLogEntry, TxLogEntryType, TxState, TxWrapper, WalletBackend,
+    WalletInfo, WalletInst, WalletSeed, WatchData,
 };
 
-use crate::common::crypto::Hex;
+use crate::common::amount_to_string;
+use crate::common::crypto::{Hex, PassphraseEncrypted};
 use crate::common::hasher::derive_address_key;
-use crate::contacts::AddressBook;
+use crate::contacts::{Address, AddressBook};
 use crate::wallet::api::Wallet713OwnerAPI;
 use crate::wallet::types::TxProof;
 
+const SEED_FILE: &str = "wallet.seed";
+const WATCH_DATA_FILENAME: &str = "watch.json";
+/// Outputs asked from the node at a time when a watch-only wallet scans the chain
+const WATCH_SCAN_BATCH_SIZE: u64 = 1000;
+const CACHED_BALANCE_FILENAME: &str = "balance_cache.json";
+
+/// The fields of a seed file, read to check their shape without the passphrase
+#[derive(Deserialize)]
+struct SeedFile {
+    encrypted_seed: String,
+    salt: String,
+    nonce: String,
+}
+
+/// The part of the node's answer to a kernel lookup we need
+#[derive(Deserialize)]
+struct LocatedKernel {
+    height: u64,
+}
+
+/// The parts of the node's status we need
+#[derive(Deserialize)]
+struct NodeStatus {
+    tip: NodeTip,
+    sync_status: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NodeTip {
+    height: u64,
+}
+
+/// Shows that a slow call is in flight until the value it returns is dropped, e.g. with
+/// a spinner on a terminal
+pub type Progress = fn(&str) -> Box<dyn Send>;
+
 pub struct Wallet {
     active_account: String,
-    backend: Option<Arc<Mutex<Backend<HTTPNodeClient, ExtKeychain>>>>,
+    backend: Option<Arc<Mutex<dyn WalletInst<TimeoutNodeClient, ExtKeychain>>>>,
+    memory_store: MemoryStore,
     max_auto_accept_invoice: Option<u64>,
+    active_node_uri: Option<String>,
+    node_timeout: Option<u64>,
+    fee_overrides: FeeOverrides,
+    spend_confirmations: u64,
+    max_selectable_outputs: usize,
+    pending_slate_ttl_hours: u64,
+    restore_max_retries: u32,
+    progress: Option<Progress>,
 }
 
 impl Wallet {
@@ -29,10 +98,27 @@ impl Wallet {
         Self {
             active_account: "default".to_string(),
             backend: None,
+            memory_store: MemoryStore::default(),
             max_auto_accept_invoice,
+            active_node_uri: None,
+            node_timeout: None,
+            fee_overrides: FeeOverrides::default(),
+            spend_confirmations: 10,
+            max_selectable_outputs: 500,
+            pending_slate_ttl_hours: 24,
+            restore_max_retries: 5,
+            progress: None,
         }
     }
 
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    fn progress(&self, message: &str) -> Option<Box<dyn Send>> {
+        self.progress.map(|start| start(message))
+    }
+
     pub fn unlock(
         &mut self,
         config: &Wallet713Config,
@@ -43,16 +129,70 @@ impl Wallet {
         self.create_wallet_instance(config, account, passphrase)
             .map_err(|_| ErrorKind::WalletUnlockFailed)?;
         self.active_account = account.to_string();
+
+        if let Some(days) = config.prune_cancelled_after_days {
+            match self.prune(days) {
+                Ok(0) => {}
+                Ok(pruned) => cli_message!("pruned {} cancelled transaction(s)", pruned),
+                Err(e) => cli_message!("could not prune cancelled transactions: {}", e),
+            }
+        }
         Ok(())
     }
 
     pub fn show_mnemonic(&self, config: &Wallet713Config, passphrase: &str) -> Result<()> {
         let wallet_config = config.as_wallet_config()?;
+        Wallet::check_seed_file(&wallet_config)?;
         let seed = WalletSeed::from_file(&wallet_config, passphrase)?;
         seed.show_recovery_phrase()?;
         Ok(())
     }
 
+    /// Writes the seed mnemonic to a file, encrypted with `password`, for an offsite backup
+    pub fn export_seed(
+        &self,
+        config: &Wallet713Config,
+        passphrase: &str,
+        path: &str,
+        password: &str,
+        force: bool,
+    ) -> Result<()> {
+        if Path::new(path).exists() && !force {
+            return Err(ErrorKind::FileExists(path.to_string()).into());
+        }
+        let wallet_config = config.as_wallet_config()?;
+        Wallet::check_seed_file(&wallet_config)?;
+        let seed = WalletSeed::from_file(&wallet_config, passphrase)?;
+        let mnemonic = ZeroingString::from(seed.to_mnemonic()?);
+        let backup = PassphraseEncrypted::seal(mnemonic.as_bytes(), password)?;
+        let mut file = File::create(path)?;
+        file.write_all(serde_json::to_string(&backup)?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Recovers the seed file from a backup written by `export_seed`, protecting it with
+    /// `passphrase`
+    pub fn import_seed(
+        &self,
+        config: &Wallet713Config,
+        path: &str,
+        password: &str,
+        passphrase: &str,
+    ) -> Result<()> {
+        if !Path::new(path).exists() {
+            return Err(ErrorKind::FileNotFound(path.to_string()).into());
+        }
+        let mut content = String::new();
+        File::open(path)?.read_to_string(&mut content)?;
+        let backup: PassphraseEncrypted = serde_json::from_str(&content)?;
+        let mnemonic = String::from_utf8(backup.open(password)?)
+            .map_err(|_| ErrorKind::WrongBackupPassword)?;
+        let mnemonic = ZeroingString::from(mnemonic);
+        let wallet_config = config.as_wallet_config()?;
+        WalletSeed::recover_from_phrase(&wallet_config, &mnemonic, passphrase)?;
+        Ok(())
+    }
+
     pub fn lock(&mut self) {
         self.backend = None;
     }
@@ -67,11 +207,26 @@ impl Wallet {
         account: &str,
         passphrase: &str,
         create_new: bool,
+        force: bool,
+        seed_words: usize,
     ) -> Result<()> {
+        let seed_length = Wallet::seed_length(seed_words)?;
         let wallet_config = config.as_wallet_config()?;
-        self.init_seed(&wallet_config, passphrase, create_new)?;
-        self.init_backend(&wallet_config, &config, passphrase)?;
-        self.unlock(config, account, passphrase)?;
+        if create_new && !force && Wallet::seed_file_exists(&wallet_config) {
+            return Err(ErrorKind::WalletExists.into());
+        }
+        if create_new {
+            check_writable(&wallet_config.data_file_dir)?;
+        }
+        let node_secret = node_secret(config)?;
+        let node_uri = self.select_node_uri(config, &node_secret)?;
+        self.init_seed(&wallet_config, passphrase, create_new, seed_length)?;
+        self.init_backend(config, &wallet_config, &node_uri, node_secret, passphrase)?;
+        self.unlock(config, "default", passphrase)?;
+        if account != "default" {
+            self.create_account(account)?;
+            self.unlock(config, account, passphrase)?;
+        }
         Ok(())
     }
 
@@ -81,6 +236,7 @@ impl Wallet {
         words: &Vec<&str>,
         passphrase: &str,
     ) -> Result<()> {
+        Wallet::seed_length(words.len())?;
         let wallet_config = config.as_wallet_config()?;
         WalletSeed::recover_from_phrase(&wallet_config, &words.join(" "), passphrase)?;
         Ok(())
@@ -96,6 +252,17 @@ impl Wallet {
         Ok(())
     }
 
+    /// Public keys of the wallet root and the active account, for tools that track the
+    /// wallet without being able to spend from it
+    pub fn show_public_keys(&self) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        let w = wallet.lock();
+        let (root, xpub) = w.public_keys()?;
+        let path = w.get_parent_key_id().to_bip_32_string();
+        display::public_keys(&self.active_account, &path, &root, &xpub);
+        Ok(())
+    }
+
     pub fn create_account(&self, name: &str) -> Result<()> {
         let wallet = self.get_wallet_instance()?;
         controller::owner_single_use(wallet.clone(), |api| {
@@ -105,59 +272,327 @@ impl Wallet {
         Ok(())
     }
 
-    pub fn info(&self) -> Result<()> {
+    pub fn info(&self, config: &Wallet713Config) -> Result<()> {
         let wallet = self.get_wallet_instance()?;
+        let mut cached = None;
         controller::owner_single_use(wallet.clone(), |api| {
-            let (validated, wallet_info) = api.retrieve_summary_info(true, 10)?;
+            let (validated, wallet_info) = {
+                let _progress = self.progress("querying node...");
+                api.retrieve_summary_info(true, 10)?
+            };
             display::info(&self.active_account, &wallet_info, validated, true);
+            cached = Some(CachedBalance::new(
+                &self.active_account,
+                wallet_info,
+                validated,
+            ));
+            Ok(())
+        })?;
+        // the cache is only a convenience, failing to write it shouldn't fail `info`
+        if let Some(cached) = cached {
+            Wallet::write_cached_balance(config, &cached).ok();
+        }
+        Ok(())
+    }
+
+    /// Shows the balance stored by the last `info`, without opening the wallet
+    pub fn cached_info(config: &Wallet713Config) -> Result<()> {
+        let path = Wallet::cached_balance_path(config)?;
+        if !path.exists() {
+            return Err(ErrorKind::NoCachedBalance.into());
+        }
+        let mut file = File::open(path)?;
+        let mut cached = String::new();
+        file.read_to_string(&mut cached)?;
+        let cached: CachedBalance = serde_json::from_str(&cached)?;
+        display::cached_info(&cached, true);
+        Ok(())
+    }
+
+    pub fn info_all_accounts(&self) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        controller::owner_single_use(wallet.clone(), |api| {
+            let (validated, infos) = {
+                let _progress = self.progress("querying node...");
+                api.retrieve_summary_info_all_accounts(true, 10)?
+            };
+            display::accounts_info(&infos, validated, true);
             Ok(())
         })?;
         Ok(())
     }
 
-    pub fn txs(&self, address_book: Option<Arc<Mutex<AddressBook>>>) -> Result<()> {
+    pub fn txs(
+        &self,
+        address_book: Option<Arc<Mutex<AddressBook>>>,
+        include_pruned: bool,
+        absolute_time: bool,
+    ) -> Result<()> {
         let wallet = self.get_wallet_instance()?;
         controller::owner_single_use(wallet.clone(), |api| {
-            let (height, _) = api.node_height()?;
-            let (validated, txs) = api.retrieve_txs_with_proof_flag(true, None, None)?;
+            let (height, validated, txs, confirmations) = {
+                let _progress = self.progress("querying node...");
+                let (height, _) = api.node_height()?;
+                let (validated, txs) = api.retrieve_txs_with_proof_flag(true, None, None)?;
+                let (_, outputs) = api.retrieve_outputs(true, false, None)?;
+
+                // how far each transaction still is from the confirmation target
+                let mut confirmations = HashMap::new();
+                for &(ref t, _) in &txs {
+                    match t.tx_type {
+                        TxLogEntryType::TxReceivedCancelled | TxLogEntryType::TxSentCancelled => {
+                            continue
+                        }
+                        _ => {}
+                    }
+                    let tx_outputs: Vec<&OutputData> = outputs
+                        .iter()
+                        .map(|(out, _)| out)
+                        .filter(|out| out.tx_log_entry == Some(t.id))
+                        .collect();
+                    let count = if t.confirmed {
+                        tx_outputs
+                            .iter()
+                            .map(|out| out.num_confirmations(height))
+                            .filter(|count| *count > 0)
+                            .max()
+                    } else {
+                        // the wallet hasn't seen the outputs yet, but the kernel may
+                        // already be in a block. Unconfirmed outputs carry the height
+                        // the transaction was built at, it can't be in an earlier block
+                        let min_height = tx_outputs.iter().map(|out| out.height).min();
+                        let kernel_height = t
+                            .tx_slate_id
+                            .and_then(|id| api.get_stored_tx(&id.to_string()).ok())
+                            .and_then(|tx| {
+                                tx.kernels()
+                                    .first()
+                                    .map(|kernel| grin_util::to_hex(kernel.excess.0.to_vec()))
+                            })
+                            .and_then(|excess| self.kernel_height(&excess, min_height));
+                        match kernel_height {
+                            Some(kernel_height) => Some((height + 1).saturating_sub(kernel_height)),
+                            None => Some(0),
+                        }
+                    };
+                    if let Some(count) = count {
+                        if count < self.spend_confirmations {
+                            confirmations.insert(t.id, count);
+                        }
+                    }
+                }
+                (height, validated, txs, confirmations)
+            };
             display::txs(
                 &self.active_account,
                 height,
                 validated,
                 txs,
+                &confirmations,
+                self.spend_confirmations,
                 true,
                 true,
                 address_book,
+                absolute_time,
             )?;
+            if include_pruned {
+                println!(
+                    "{} cancelled transaction(s) were pruned",
+                    api.pruned_tx_count()?
+                );
+            }
             Ok(())
         })?;
 
         Ok(())
     }
 
-    pub fn outputs(&self, show_spent: bool) -> Result<()> {
+    pub fn show_tx(&self, id: u32, absolute_time: bool, log: bool) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        controller::owner_single_use(wallet.clone(), |api| {
+            let (height, validated, txs) = {
+                let _progress = self.progress("querying node...");
+                let (height, _) = api.node_height()?;
+                let (validated, txs) = api.retrieve_txs(true, Some(id), None)?;
+                (height, validated, txs)
+            };
+            if txs.len() == 0 {
+                return Err(grin_wallet::libwallet::ErrorKind::GenericError(format!(
+                    "could not find transaction with id {}!",
+                    id
+                )))?;
+            }
+            let stored_tx = match txs[0].tx_slate_id {
+                Some(slate_id) => api.get_stored_tx(&slate_id.to_string()).ok(),
+                None => None,
+            };
+            display::tx(
+                &self.active_account,
+                height,
+                validated,
+                &txs[0],
+                stored_tx.as_ref(),
+                absolute_time,
+                log,
+            );
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    pub fn outputs(&self, show_spent: bool, coinbase_only: bool) -> Result<()> {
         let wallet = self.get_wallet_instance()?;
         let result = controller::owner_single_use(wallet.clone(), |api| {
-            let (height, _) = api.node_height()?;
-            let (validated, outputs) = api.retrieve_outputs(show_spent, true, None)?;
+            let (height, validated, outputs) = {
+                let _progress = self.progress("querying node...");
+                let (height, _) = api.node_height()?;
+                let (validated, mut outputs) = api.retrieve_outputs(show_spent, true, None)?;
+                if coinbase_only {
+                    outputs.retain(|(out, _)| out.is_coinbase);
+                }
+                (height, validated, outputs)
+            };
             display::outputs(&self.active_account, height, validated, outputs, true)?;
             Ok(())
         })?;
         Ok(result)
     }
 
-    pub fn initiate_send_tx(
+    /// The outputs `outputs` would list with the same filters, for writing to a file
+    pub fn export_outputs(
         &self,
+        show_spent: bool,
+        coinbase_only: bool,
+    ) -> Result<Vec<ExportedOutput>> {
+        let (_, mut outputs) = {
+            let _progress = self.progress("querying node...");
+            self.retrieve_outputs(show_spent, true, None)?
+        };
+        if coinbase_only {
+            outputs.retain(|(out, _)| out.is_coinbase);
+        }
+        Ok(outputs
+            .iter()
+            .map(|(out, commit)| ExportedOutput::new(out, commit))
+            .collect())
+    }
+
+    /// The data a watch-only wallet needs to follow the active account, including
+    /// outputs it receives after the export
+    pub fn export_watch(&self) -> Result<WatchData> {
+        let (_, outputs) = self.retrieve_outputs(false, true, None)?;
+        let wallet = self.get_wallet_instance()?;
+        let mut w = wallet.lock();
+        w.open_with_credentials()?;
+        let (_, xpub) = w.public_keys()?;
+        // the key range proofs are rewound with, see `proof::rewind`
+        let rewind_key = w.keychain().derive_key(0, &ExtKeychain::root_key_id())?;
+        Ok(WatchData::new(
+            &self.active_account,
+            &xpub,
+            w.get_parent_key_id(),
+            &rewind_key,
+            outputs,
+        ))
+    }
+
+    pub fn init_watch(&self, config: &Wallet713Config, watch_data: &WatchData) -> Result<()> {
+        // a damaged file fails here rather than on every `info`
+        SecretKey::from_hex(&watch_data.rewind_key)?;
+        Wallet::write_watch_data(config, watch_data)
+    }
+
+    pub fn has_watch_data(config: &Wallet713Config) -> bool {
+        Wallet::watch_data_path(config)
+            .map(|path| path.exists())
+            .unwrap_or(false)
+    }
+
+    /// Scans the chain for outputs of the watched account received since the last
+    /// scan, drops the spent ones and displays the balance of the rest
+    pub fn watch_info(&mut self, config: &Wallet713Config) -> Result<()> {
+        let mut file = File::open(Wallet::watch_data_path(config)?)?;
+        let mut watch_data = String::new();
+        file.read_to_string(&mut watch_data)?;
+        let mut watch_data: WatchData = serde_json::from_str(&watch_data)?;
+        let rewind_key = SecretKey::from_hex(&watch_data.rewind_key)?;
+
+        let node_secret = node_secret(config)?;
+        let node_uri = self.select_node_uri(config, &node_secret)?;
+        let node_client = TimeoutNodeClient::new(&node_uri, node_secret, config.timeout());
+        let height = node_client.get_chain_height()?;
+
+        let secp = Secp256k1::with_caps(ContextFlag::Commit);
+        {
+            let _progress = self.progress("scanning chain...");
+            loop {
+                let (highest_index, last_retrieved_index, outputs) = node_client
+                    .get_outputs_by_pmmr_index(watch_data.next_index, WATCH_SCAN_BATCH_SIZE)?;
+                if outputs.is_empty() {
+                    break;
+                }
+                for output in outputs {
+                    if watch_data.outputs.iter().any(|o| o.commit == output.0) {
+                        continue;
+                    }
+                    if let Some(found) = watch_data.identify(&secp, &rewind_key, output) {
+                        watch_data.outputs.push(found);
+                    }
+                }
+                watch_data.next_index = last_retrieved_index + 1;
+                if highest_index == last_retrieved_index {
+                    break;
+                }
+            }
+        }
+
+        let commits = watch_data
+            .outputs
+            .iter()
+            .map(|o| o.commit.clone())
+            .collect();
+        let api_outputs = node_client.get_outputs_from_node(commits)?;
+        // outputs only show up once confirmed, so a spent one never comes back
+        watch_data
+            .outputs
+            .retain(|o| api_outputs.contains_key(&o.commit));
+        Wallet::write_watch_data(config, &watch_data)?;
+
+        let total = watch_data.outputs.iter().map(|o| o.value).sum();
+        display::watch_info(
+            &watch_data.account,
+            &watch_data.xpub,
+            height,
+            total,
+            watch_data.outputs.len(),
+        );
+        Ok(())
+    }
+
+    pub fn initiate_send_tx(
+        &mut self,
         address: Option<String>,
         amount: u64,
         minimum_confirmations: u64,
         selection_strategy: &str,
         change_outputs: usize,
         max_outputs: usize,
+        use_outputs: Option<Vec<String>>,
+        lock_height: Option<u64>,
         message: Option<String>,
+        change_account: Option<&str>,
+        fluff: bool,
+        allow_high_fee: bool,
+        allow_unconfirmed_change: bool,
     ) -> Result<Slate> {
         let wallet = self.get_wallet_instance()?;
+        self.fee_overrides.validate()?;
+        let fee_overrides = FeeOverrides {
+            allow_high_fee,
+            ..self.fee_overrides
+        };
         let mut s: Slate = Slate::blank(0);
+        let _progress = self.progress("building transaction...");
         controller::owner_single_use(wallet.clone(), |api| {
             let (slate, lock_fn) = api.initiate_tx(
                 address,
@@ -166,15 +601,101 @@ impl Wallet {
                 max_outputs,
                 change_outputs,
                 selection_strategy == "all",
+                use_outputs.as_ref(),
+                None,
+                lock_height,
                 message,
+                change_account,
+                allow_unconfirmed_change,
+                &fee_overrides,
             )?;
             api.tx_lock_outputs(&slate.tx, lock_fn)?;
             s = slate;
             Ok(())
         })?;
+        // remember the propagation choice for when the slate comes back to be finalized
+        if fluff {
+            let id = self.tx_id_by_slate_id(&s.id)?;
+            controller::owner_single_use(wallet.clone(), |api| api.set_tx_fluff(id))?;
+        }
         Ok(s)
     }
 
+    /// Builds a send like `initiate_send_tx` and gives it `expires_in` minutes to be
+    /// finalized, returning the slate to deliver along with what was sent
+    pub fn send(
+        &mut self,
+        address: Option<String>,
+        amount: u64,
+        minimum_confirmations: u64,
+        selection_strategy: &str,
+        change_outputs: usize,
+        max_outputs: usize,
+        use_outputs: Option<Vec<String>>,
+        lock_height: Option<u64>,
+        message: Option<String>,
+        change_account: Option<&str>,
+        fluff: bool,
+        allow_high_fee: bool,
+        allow_unconfirmed_change: bool,
+        expires_in: Option<u64>,
+    ) -> Result<(Slate, SendResult)> {
+        let slate = self.initiate_send_tx(
+            address,
+            amount,
+            minimum_confirmations,
+            selection_strategy,
+            change_outputs,
+            max_outputs,
+            use_outputs,
+            lock_height,
+            message,
+            change_account,
+            fluff,
+            allow_high_fee,
+            allow_unconfirmed_change,
+        )?;
+        if let Some(minutes) = expires_in {
+            self.set_send_expiry(&slate.id, minutes)?;
+        }
+        let result = SendResult::from_slate(&slate);
+        Ok((slate, result))
+    }
+
+    /// The given percentage of the spendable balance, rounded down. What is left has to
+    /// cover the fee of a transaction spending every eligible output, so the amount can
+    /// always be sent whichever outputs get selected.
+    pub fn amount_for_percent(
+        &self,
+        percent: u64,
+        minimum_confirmations: u64,
+        change_outputs: usize,
+    ) -> Result<u64> {
+        let (current_height, _) = self.node_height()?;
+        let (_, outputs) = self.retrieve_outputs(false, true, None)?;
+        let eligible: Vec<u64> = outputs
+            .iter()
+            .filter(|(output, _)| output.eligible_to_spend(current_height, minimum_confirmations))
+            .map(|(output, _)| output.value)
+            .collect();
+        let spendable: u64 = eligible.iter().sum();
+        let max_fee = self
+            .fee_overrides
+            .tx_fee(eligible.len(), 1 + change_outputs, 1);
+
+        let amount = (spendable as u128 * percent as u128 / 100) as u64;
+        let amount = std::cmp::min(amount, spendable.saturating_sub(max_fee));
+        if amount == 0 {
+            return Err(ErrorKind::NotEnoughFunds {
+                available: spendable,
+                available_disp: amount_to_string(spendable, false),
+                needed: max_fee + 1,
+                needed_disp: amount_to_string(max_fee + 1, false),
+            })?;
+        }
+        Ok(amount)
+    }
+
     pub fn initiate_receive_tx(&self, amount: u64, num_outputs: usize) -> Result<Slate> {
         let wallet = self.get_wallet_instance()?;
         let mut s: Slate = Slate::blank(0);
@@ -187,10 +708,45 @@ impl Wallet {
         Ok(s)
     }
 
-    pub fn repost(&self, id: u32, fluff: bool) -> Result<()> {
+    pub fn repost(&self, id: u32, fluff: bool, allow_high_fee: bool) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        let stored_tx = self.stored_tx_for_id(id, true)?;
+        controller::owner_single_use(wallet.clone(), |api| {
+            if !allow_high_fee {
+                self.fee_overrides.check_fee(
+                    stored_tx.fee(),
+                    stored_tx.inputs().len(),
+                    stored_tx.outputs().len(),
+                    stored_tx.kernels().len(),
+                )?;
+            }
+            api.post_tx(&stored_tx, fluff)?;
+            let (_, txs) = api.retrieve_txs(false, Some(id), None)?;
+            if let Some(slate_id) = txs.first().and_then(|tx| tx.tx_slate_id) {
+                api.log_tx_state(&slate_id, TxState::Posted)?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// The finalized transaction stored for the given tx, serialized the way the node's
+    /// push api expects it, to broadcast it without going through the configured node
+    pub fn dump_tx(&self, id: u32) -> Result<TxWrapper> {
+        // no refresh, the node may well be the reason for dumping the tx
+        let stored_tx = self.stored_tx_for_id(id, false)?;
+        let tx_bin = ser::ser_vec(&stored_tx).map_err(|e| {
+            ErrorKind::GenericError(format!("could not serialize tx {}: {}", id, e))
+        })?;
+        let tx_hex = grin_util::to_hex(tx_bin);
+        Ok(TxWrapper { tx_hex })
+    }
+
+    fn stored_tx_for_id(&self, id: u32, refresh_from_node: bool) -> Result<Transaction> {
         let wallet = self.get_wallet_instance()?;
+        let mut stored_tx = None;
         controller::owner_single_use(wallet.clone(), |api| {
-            let (_, txs) = api.retrieve_txs(true, Some(id), None)?;
+            let (_, txs) = api.retrieve_txs(refresh_from_node, Some(id), None)?;
             if txs.len() == 0 {
                 return Err(grin_wallet::libwallet::ErrorKind::GenericError(format!(
                     "could not find transaction with id {}!",
@@ -199,8 +755,7 @@ impl Wallet {
             }
             let slate_id = txs[0].tx_slate_id;
             if let Some(slate_id) = slate_id {
-                let stored_tx = api.get_stored_tx(&slate_id.to_string())?;
-                api.post_tx(&stored_tx, fluff)?;
+                stored_tx = Some(api.get_stored_tx(&slate_id.to_string())?);
                 Ok(())
             } else {
                 Err(grin_wallet::libwallet::ErrorKind::GenericError(format!(
@@ -209,27 +764,301 @@ impl Wallet {
                 )))?
             }
         })?;
+        Ok(stored_tx.unwrap())
+    }
+
+    pub fn get_stored_slate(&self, id: u32) -> Result<Slate> {
+        let wallet = self.get_wallet_instance()?;
+        let mut slate = None;
+        controller::owner_single_use(wallet.clone(), |api| {
+            let (_, txs) = api.retrieve_txs(false, Some(id), None)?;
+            if txs.len() == 0 {
+                return Err(grin_wallet::libwallet::ErrorKind::GenericError(format!(
+                    "could not find transaction with id {}!",
+                    id
+                )))?;
+            }
+            if txs[0].tx_type != TxLogEntryType::TxSent || txs[0].confirmed {
+                return Err(grin_wallet::libwallet::ErrorKind::GenericError(format!(
+                    "transaction with id {} is not a pending send, can not resend!",
+                    id
+                )))?;
+            }
+            let stored_slate = txs[0]
+                .tx_slate_id
+                .and_then(|slate_id| api.get_stored_slate(&slate_id.to_string()).ok());
+            match stored_slate {
+                Some(stored_slate) => {
+                    slate = Some(stored_slate);
+                    Ok(())
+                }
+                None => Err(grin_wallet::libwallet::ErrorKind::GenericError(format!(
+                    "no slate stored for id {}, can not resend!",
+                    id
+                )))?,
+            }
+        })?;
+        Ok(slate.unwrap())
+    }
+
+    /// How deep the outputs a confirmed transaction created are in the chain, `None`
+    /// when none of them is unspent any more
+    pub fn tx_confirmations(&self, tx_id: u32) -> Result<Option<u64>> {
+        let (height, _) = self.node_height()?;
+        let (_, outputs) = self.retrieve_outputs(false, false, Some(tx_id))?;
+        Ok(outputs
+            .iter()
+            .filter(|(out, _)| out.status == OutputStatus::Unspent)
+            .map(|(out, _)| out.num_confirmations(height))
+            .min())
+    }
+
+    /// Gives a send `minutes` to be finalized, after which the listener cancels it
+    pub fn set_send_expiry(&self, slate_id: &Uuid, minutes: u64) -> Result<()> {
+        let id = self.tx_id_by_slate_id(slate_id)?;
+        let expires_at = time::minutes_from_now(minutes)?;
+        let wallet = self.get_wallet_instance()?;
+        controller::owner_single_use(wallet.clone(), |api| api.set_tx_expiry(id, expires_at))?;
         Ok(())
     }
 
+    /// Sends of the active account past their expiry that weren't finalized
+    pub fn expired_sends(&self) -> Result<Vec<TxLogEntry>> {
+        let wallet = self.get_wallet_instance()?;
+        let now = Utc::now();
+        let mut expired = vec![];
+        controller::owner_single_use(wallet.clone(), |api| {
+            let (_, txs) = api.retrieve_txs(false, None, None)?;
+            for tx in txs {
+                let is_expired = match tx.expires_at {
+                    Some(expires_at) => expires_at < now,
+                    None => false,
+                };
+                if tx.tx_type != TxLogEntryType::TxSent || tx.confirmed || !is_expired {
+                    continue;
+                }
+                if let Some(slate_id) = tx.tx_slate_id {
+                    if !api.is_finalized(&slate_id)? {
+                        expired.push(tx);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok(expired)
+    }
+
+    /// Finalized sends of the active account that are still unconfirmed `minutes` after
+    /// they were created. A send that was already replaced with a higher fee isn't stuck
+    pub fn stuck_sends(&self, minutes: u64) -> Result<Vec<TxLogEntry>> {
+        let wallet = self.get_wallet_instance()?;
+        let stuck_since = time::minutes_ago(minutes)?;
+        let mut stuck = vec![];
+        controller::owner_single_use(wallet.clone(), |api| {
+            let (_, txs) = api.retrieve_txs(false, None, None)?;
+            let replaced: HashSet<u32> = txs
+                .iter()
+                .filter(|tx| tx.tx_type == TxLogEntryType::TxSent)
+                .filter_map(|tx| tx.replaces)
+                .collect();
+            for tx in txs {
+                if tx.tx_type != TxLogEntryType::TxSent
+                    || tx.confirmed
+                    || tx.creation_ts > stuck_since
+                    || replaced.contains(&tx.id)
+                {
+                    continue;
+                }
+                // a send that never came back from the recipient isn't waiting on its fee
+                if let Some(slate_id) = tx.tx_slate_id {
+                    if api.is_finalized(&slate_id)? {
+                        stuck.push(tx);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok(stuck)
+    }
+
+    /// Builds a stuck send again with twice the fee, spending the same inputs. The new
+    /// slate has to go through the recipient again. The original keeps its outputs and
+    /// log entry, and whichever of the two doesn't confirm is cancelled once the other
+    /// one does
+    pub fn bump_fee(&self, id: u32) -> Result<Slate> {
+        let original = self.get_stored_slate(id)?;
+        let stored_tx = self.get_stored_tx(&original.id.to_string())?;
+        let (_, txs) = self.retrieve_txs(false, Some(id), None)?;
+        let tx = txs
+            .into_iter()
+            .next()
+            .ok_or(ErrorKind::TransactionDoesntExist(id.to_string()))?;
+        let inputs: Vec<String> = stored_tx
+            .inputs()
+            .iter()
+            .map(|input| grin_util::to_hex(input.commit.0.to_vec()))
+            .collect();
+        let change_outputs = std::cmp::max(tx.num_outputs, 1);
+
+        let fee = original
+            .fee
+            .checked_mul(2)
+            .ok_or(ErrorKind::AmountOverflow)?;
+        let fee_overrides = FeeOverrides {
+            min_fee: Some(fee),
+            ..self.fee_overrides
+        };
+        fee_overrides.check_fee(fee, inputs.len(), change_outputs + 1, 1)?;
+
+        let wallet = self.get_wallet_instance()?;
+        let mut bumped = Slate::blank(0);
+        controller::owner_single_use(wallet.clone(), |api| {
+            let (slate, lock_fn) = api.initiate_tx(
+                tx.address.clone(),
+                original.amount,
+                1,
+                self.max_selectable_outputs,
+                change_outputs,
+                false,
+                Some(&inputs),
+                Some(id),
+                None,
+                None,
+                None,
+                false,
+                &fee_overrides,
+            )?;
+            api.tx_lock_outputs(&slate.tx, lock_fn)?;
+            bumped = slate;
+            Ok(())
+        })?;
+        Ok(bumped)
+    }
+
     pub fn cancel(&self, id: u32) -> Result<()> {
         let wallet = self.get_wallet_instance()?;
         controller::owner_single_use(wallet.clone(), |api| api.cancel_tx(Some(id), None))?;
         Ok(())
     }
 
-    pub fn restore_state(&self) -> Result<()> {
+    /// Cancels the transaction a slate belongs to, unlocking its outputs
+    pub fn cancel_by_slate_id(&self, slate_id: &Uuid) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        controller::owner_single_use(wallet.clone(), |api| {
+            api.cancel_tx(None, Some(slate_id.clone()))
+        })?;
+        Ok(())
+    }
+
+    /// Queues a received slate until it is accepted or rejected by the user
+    pub fn queue_slate(
+        &self,
+        from: &str,
+        slate: &Slate,
+        tx_proof: Option<&TxProof>,
+    ) -> Result<PendingSlate> {
+        let wallet = self.get_wallet_instance()?;
+        let pending = PendingSlate::new(from, slate, tx_proof);
+        controller::owner_single_use(wallet.clone(), |api| api.store_pending_slate(&pending))?;
+        Ok(pending)
+    }
+
+    pub fn get_pending_slate(&self, slate_id: &Uuid) -> Result<PendingSlate> {
+        let wallet = self.get_wallet_instance()?;
+        let mut result = None;
+        controller::owner_single_use(wallet.clone(), |api| {
+            api.expire_pending_slates(self.pending_slate_ttl())?;
+            result = Some(api.get_pending_slate(&slate_id.to_string())?);
+            Ok(())
+        })?;
+        Ok(result.unwrap())
+    }
+
+    pub fn remove_pending_slate(&self, slate_id: &Uuid) -> Result<()> {
         let wallet = self.get_wallet_instance()?;
-        controller::owner_single_use(wallet.clone(), |api| api.restore())?;
+        controller::owner_single_use(wallet.clone(), |api| {
+            api.delete_pending_slate(&slate_id.to_string())
+        })?;
+        Ok(())
+    }
+
+    pub fn pending_slates(
+        &self,
+        address_book: Option<Arc<Mutex<AddressBook>>>,
+        absolute_time: bool,
+    ) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        controller::owner_single_use(wallet.clone(), |api| {
+            api.expire_pending_slates(self.pending_slate_ttl())?;
+            let pending = api.pending_slates()?;
+            display::pending_slates(pending, address_book, absolute_time);
+            Ok(())
+        })?;
         Ok(())
     }
 
+    fn pending_slate_ttl(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.pending_slate_ttl_hours as i64)
+    }
+
+    pub fn tx_log_count(&self) -> Result<usize> {
+        let wallet = self.get_wallet_instance()?;
+        let mut result = 0;
+        controller::owner_single_use(wallet.clone(), |api| {
+            result = api.tx_log_count()?;
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    pub fn restore_state(&self) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        let _progress = self.progress("restoring...");
+        let max_retries = self.restore_max_retries;
+        let mut end = RestoreEnd::Complete;
+        controller::owner_single_use(wallet.clone(), |api| {
+            end = api.restore(max_retries)?;
+            Ok(())
+        })?;
+        match end {
+            RestoreEnd::Complete => Ok(()),
+            RestoreEnd::Interrupted => Err(ErrorKind::RestoreInterrupted.into()),
+            RestoreEnd::GaveUp => Err(ErrorKind::RestoreNodeFailed(max_retries).into()),
+        }
+    }
+
+    /// Removes the cancelled transactions older than the given number of days
+    pub fn prune(&self, older_than_days: u64) -> Result<usize> {
+        let wallet = self.get_wallet_instance()?;
+        let cutoff = time::minutes_ago(older_than_days.saturating_mul(time::MINUTES_PER_DAY))?;
+        let mut pruned = 0;
+        controller::owner_single_use(wallet.clone(), |api| {
+            pruned = api.prune_cancelled_txs(cutoff)?;
+            Ok(())
+        })?;
+        Ok(pruned)
+    }
+
     pub fn check_repair(&self) -> Result<()> {
         let wallet = self.get_wallet_instance()?;
+        let _progress = self.progress("checking...");
         controller::owner_single_use(wallet.clone(), |api| api.check_repair())?;
         Ok(())
     }
 
+    /// Imports outputs written by `export_outputs`, keeping those unspent on chain
+    pub fn import_outputs(&self, outputs: Vec<ExportedOutput>) -> Result<(usize, usize)> {
+        let outputs = outputs.iter().map(|out| out.to_output_data()).collect();
+        let wallet = self.get_wallet_instance()?;
+        let mut result = (0, 0);
+        let _progress = self.progress("verifying outputs...");
+        controller::owner_single_use(wallet.clone(), |api| {
+            result = api.import_outputs(outputs)?;
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
     pub fn build_coinbase(&self, block_fees: &BlockFees) -> Result<CbData> {
         let wallet = self.get_wallet_instance()?;
         let mut cb_data = None;
@@ -265,18 +1094,60 @@ impl Wallet {
         }
 
         let wallet = self.get_wallet_instance()?;
+        self.fee_overrides.validate()?;
 
         controller::owner_single_use(wallet.clone(), |api| {
-            let lock_fn = api.invoice_tx(slate, 10, 500, 1, false, None)?;
+            let lock_fn = api.invoice_tx(
+                slate,
+                self.spend_confirmations,
+                self.max_selectable_outputs,
+                1,
+                false,
+                None,
+                &self.fee_overrides,
+            )?;
             api.tx_lock_outputs(&slate.tx, lock_fn)?;
             Ok(())
         })?;
         Ok(())
     }
 
-    pub fn finalize_slate(&self, slate: &mut Slate, tx_proof: Option<&mut TxProof>) -> Result<()> {
+    /// Moves the signing secrets of a send that wasn't finalized yet out of the wallet,
+    /// so its slate can only be finalized after it was signed with the returned key
+    pub fn export_signing_key(&self, id: u32) -> Result<SigningKey> {
+        let (_, txs) = self.retrieve_txs(false, Some(id), None)?;
+        let slate_id = txs
+            .into_iter()
+            .find(|tx| tx.tx_type == TxLogEntryType::TxSent)
+            .and_then(|tx| tx.tx_slate_id)
+            .ok_or(ErrorKind::TransactionDoesntExist(id.to_string()))?;
+        let wallet = self.get_wallet_instance()?;
+        let mut key = None;
+        controller::owner_single_use(wallet.clone(), |api| {
+            key = Some(api.export_signing_key(&slate_id)?);
+            Ok(())
+        })?;
+        Ok(key.unwrap())
+    }
+
+    pub fn finalize_slate(
+        &mut self,
+        slate: &mut Slate,
+        tx_proof: Option<&mut TxProof>,
+    ) -> Result<()> {
         let wallet = self.get_wallet_instance()?;
         let mut should_post: bool = false;
+
+        // refuse to finalize the same slate twice, e.g. when it was received again
+        let mut is_finalized = false;
+        controller::owner_single_use(wallet.clone(), |api| {
+            is_finalized = api.is_finalized(&slate.id)?;
+            Ok(())
+        })?;
+        if is_finalized {
+            return Err(ErrorKind::AlreadyFinalized(slate.id.to_string()).into());
+        }
+
         controller::owner_single_use(wallet.clone(), |api| {
             api.verify_slate_messages(&slate)?;
             Ok(())
@@ -287,16 +1158,30 @@ impl Wallet {
             Ok(())
         })
         .map_err(|_| ErrorKind::GrinWalletFinalizeError)?;
+        let mut fluff = false;
+        controller::owner_single_use(wallet.clone(), |api| {
+            fluff = api.is_fluff(&slate.id)?;
+            Ok(())
+        })?;
         if should_post {
             controller::owner_single_use(wallet.clone(), |api| {
-                api.post_tx(&slate.tx, false)?;
+                api.post_tx(&slate.tx, fluff)?;
                 Ok(())
             })
             .map_err(|_| ErrorKind::GrinWalletPostError)?;
+            self.log_tx_state(&slate.id, TxState::Posted)?;
         }
         Ok(())
     }
 
+    /// Records in the state log of a transaction a step that happens outside of the
+    /// owner api, like delivering its slate
+    pub fn log_tx_state(&self, slate_id: &Uuid, state: TxState) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        controller::owner_single_use(wallet.clone(), |api| api.log_tx_state(slate_id, state))?;
+        Ok(())
+    }
+
     pub fn retrieve_summary_info(&self, refresh: bool) -> Result<WalletInfo> {
         let wallet = self.get_wallet_instance()?;
         let mut info = None;
@@ -338,6 +1223,45 @@ impl Wallet {
         Ok(result)
     }
 
+    pub fn set_tx_note(&self, id: u32, note: &str) -> Result<()> {
+        let wallet = self.get_wallet_instance()?;
+        controller::owner_single_use(wallet.clone(), |api| api.set_tx_note(id, note))?;
+        Ok(())
+    }
+
+    /// Ids of the transactions of the active account still pending with `address` as
+    /// their counterparty
+    pub fn pending_tx_ids_with(&self, address: &Box<Address>) -> Result<Vec<u32>> {
+        let (_, txs) = self.retrieve_txs(true, None, None)?;
+        let ids = txs
+            .iter()
+            .filter(|tx| !tx.confirmed)
+            .filter(|tx| match tx.tx_type {
+                TxLogEntryType::TxSent | TxLogEntryType::TxReceived => true,
+                _ => false,
+            })
+            .filter(
+                |tx| match tx.address.as_ref().and_then(|a| Address::parse(a).ok()) {
+                    Some(other) => {
+                        other.address_type() == address.address_type()
+                            && other.stripped() == address.stripped()
+                    }
+                    None => false,
+                },
+            )
+            .map(|tx| tx.id)
+            .collect();
+        Ok(ids)
+    }
+
+    pub fn tx_id_by_slate_id(&self, slate_id: &Uuid) -> Result<u32> {
+        let (_, txs) = self.retrieve_txs(false, None, Some(slate_id.clone()))?;
+        match txs.first() {
+            Some(tx) => Ok(tx.id),
+            None => Err(ErrorKind::TransactionDoesntExist(slate_id.to_string()))?,
+        }
+    }
+
     pub fn get_stored_tx(&self, uuid: &str) -> Result<Transaction> {
         let wallet = self.get_wallet_instance()?;
         let mut result = Transaction::default();
@@ -380,6 +1304,39 @@ impl Wallet {
         api.get_stored_tx_proof(id)
     }
 
+    /// Gathers what is stored about a transaction into a bundle signed with `secret_key`
+    pub fn proof_bundle(
+        &self,
+        id: u32,
+        signer: &str,
+        secret_key: &SecretKey,
+    ) -> Result<ProofBundle> {
+        let wallet = self.get_wallet_instance()?;
+        let mut parts = None;
+        controller::owner_single_use(wallet.clone(), |api| {
+            let (_, txs) = api.retrieve_txs(false, Some(id), None)?;
+            if txs.len() == 0 {
+                return Err(grin_wallet::libwallet::ErrorKind::GenericError(format!(
+                    "could not find transaction with id {}!",
+                    id
+                )))?;
+            }
+            let entry = txs[0].clone();
+            let (tx, slate) = match entry.tx_slate_id {
+                Some(slate_id) => (
+                    api.get_stored_tx(&slate_id.to_string()).ok(),
+                    api.get_stored_slate(&slate_id.to_string()).ok(),
+                ),
+                None => (None, None),
+            };
+            parts = Some((entry, tx, slate));
+            Ok(())
+        })?;
+        let (entry, tx, slate) = parts.unwrap();
+        let proof = self.get_tx_proof(id).ok();
+        ProofBundle::new(&entry, tx, slate, proof, signer, secret_key)
+    }
+
     pub fn verify_tx_proof(
         &self,
         tx_proof: &TxProof,
@@ -402,11 +1359,248 @@ impl Wallet {
         ))
     }
 
+    /// Height of the block a kernel was included in, `None` if the node doesn't know
+    /// the kernel or can't be reached. The search goes no lower than `min_height`
+    fn kernel_height(&self, excess: &str, min_height: Option<u64>) -> Option<u64> {
+        let path = match min_height {
+            Some(min_height) => format!("chain/kernels/{}?min_height={}", excess, min_height),
+            None => format!("chain/kernels/{}", excess),
+        };
+        let located: LocatedKernel = self.node_get(&path)?;
+        Some(located.height)
+    }
+
+    /// Tip height and sync state reported by the node, `None` if it can't be reached.
+    /// Older nodes don't report their sync state
+    pub fn node_status(&self) -> Option<(u64, Option<String>)> {
+        let status: NodeStatus = self.node_get("status")?;
+        Some((status.tip.height, status.sync_status))
+    }
+
+    /// The wallet, node and listener status as a json object for health checks. It is
+    /// only `healthy` if the wallet is unlocked, the node responds and isn't syncing, and
+    /// the grinbox listener, if one was started, is still connected
+    pub fn status_json(
+        &self,
+        grinbox_running: Option<bool>,
+        keybase_running: bool,
+    ) -> serde_json::Value {
+        let unlocked = !self.is_locked();
+        let node_status = match unlocked {
+            true => self.node_status(),
+            false => None,
+        };
+        let last_scanned_height = self.last_scanned_height().ok();
+
+        // a node that doesn't report its sync state is taken as synced
+        let node_synced = match node_status {
+            Some((_, Some(ref sync_status))) => sync_status == "no_sync",
+            Some((_, None)) => true,
+            None => false,
+        };
+        let healthy = unlocked && node_synced && grinbox_running != Some(false);
+
+        json!({
+            "healthy": healthy,
+            "wallet": {
+                "unlocked": unlocked,
+                "last_scanned_height": last_scanned_height,
+            },
+            "node": {
+                "uri": self.active_node_uri(),
+                "reachable": node_status.is_some(),
+                "height": node_status.as_ref().map(|(height, _)| *height),
+                "sync_status": node_status.as_ref().and_then(|(_, sync_status)| sync_status.clone()),
+            },
+            "grinbox": {
+                "started": grinbox_running.is_some(),
+                "connected": grinbox_running.unwrap_or(false),
+            },
+            "keybase": {
+                "listening": keybase_running,
+            },
+        })
+    }
+
+    /// Height at which the wallet last checked its outputs against the chain
+    pub fn last_scanned_height(&self) -> Result<u64> {
+        let wallet = self.get_wallet_instance()?;
+        let height = wallet.lock().get_last_confirmed_height()?;
+        Ok(height)
+    }
+
+    /// The node the wallet talks to, to query once the wallet is no longer locked.
+    /// `None` while no wallet is open
+    pub fn node_api(&self) -> Option<NodeApi> {
+        let mut backend = self.backend.as_ref()?.lock();
+        let node_client = backend.w2n_client();
+        Some(NodeApi {
+            url: node_client.node_url().to_string(),
+            secret: node_client.node_api_secret(),
+            timeout: self.node_timeout,
+        })
+    }
+
+    /// Queries the node's api at `/v1/<path>`, `None` on any failure
+    fn node_get<T>(&self, path: &str) -> Option<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.node_api()?.get(path)
+    }
+
+    /// Fetches the price of one grin in `currency` from a price api. `{currency}` in the
+    /// url is replaced by the currency code, and the answer is expected in the format of
+    /// coingecko's simple price api, as in `{"grin": {"usd": 0.05}}`.
+    pub fn grin_price(&self, url: &str, currency: &str) -> Result<f64> {
+        let failed = |reason: String| ErrorKind::PriceFetchFailed(reason);
+        let url = url.replace("{currency}", currency);
+        let get = {
+            let url = url.clone();
+            move || client::get::<serde_json::Value>(url.as_str(), None)
+        };
+        let response = match self.node_timeout {
+            Some(timeout) => with_timeout(timeout, get).map_err(|_| {
+                failed(format!("{} did not answer within {} seconds", url, timeout))
+            })?,
+            None => get(),
+        }
+        .map_err(|e| failed(format!("{} did not answer: {}", url, e)))?;
+        let price = response["grin"][currency]
+            .as_f64()
+            .filter(|price| *price > 0.0)
+            .ok_or_else(|| failed(format!("{} gave no grin price in {}", url, currency)))?;
+        Ok(price)
+    }
+
+    pub fn active_account(&self) -> &str {
+        &self.active_account
+    }
+
+    /// Applies the settings of a re-read config that can change while the wallet runs:
+    /// the nodes to use, the node timeout, fee and confirmation defaults
+    pub fn reload_config(&mut self, config: &Wallet713Config) -> Result<()> {
+        self.max_auto_accept_invoice = config.max_auto_accept_invoice;
+        self.node_timeout = Some(config.timeout());
+        self.fee_overrides =
+            FeeOverrides::new(config.fee_per_weight, config.min_fee, config.max_fee);
+        self.spend_confirmations = config.spend_confirmations();
+        self.max_selectable_outputs = config.max_selectable_outputs();
+        self.pending_slate_ttl_hours = config.pending_slate_ttl_hours();
+        self.restore_max_retries = config.restore_max_retries();
+
+        self.active_node_uri = None;
+        if let Some(backend) = self.backend.clone() {
+            let node_secret = node_secret(config)?;
+            let node_uri = self.select_node_uri(config, &node_secret)?;
+            *backend.lock().w2n_client() =
+                TimeoutNodeClient::new(&node_uri, node_secret, config.timeout());
+        }
+        Ok(())
+    }
+
+    /// The node uri in use for this session, if one has responded yet
+    pub fn active_node_uri(&self) -> Option<String> {
+        self.active_node_uri.clone()
+    }
+
+    /// Tries the configured nodes in order and caches the first one that responds.
+    /// Falls back to the primary node if none of them respond.
+    fn select_node_uri(
+        &mut self,
+        config: &Wallet713Config,
+        node_secret: &Option<String>,
+    ) -> Result<String> {
+        if let Some(ref uri) = self.active_node_uri {
+            return Ok(uri.clone());
+        }
+
+        let uris = config.grin_node_uris();
+        for uri in uris.iter() {
+            let node_client = TimeoutNodeClient::new(uri, node_secret.clone(), config.timeout());
+            if node_client.get_chain_height().is_ok() {
+                self.active_node_uri = Some(uri.clone());
+                return Ok(uri.clone());
+            }
+            if uris.len() > 1 {
+                cli_message!("node [{}] is not responding, trying next node", uri);
+            }
+        }
+        let primary = uris.first().ok_or(ErrorKind::NoNodeUri)?;
+        Ok(primary.clone())
+    }
+
+    fn write_cached_balance(config: &Wallet713Config, cached: &CachedBalance) -> Result<()> {
+        let mut file = File::create(Wallet::cached_balance_path(config)?)?;
+        file.write_all(serde_json::to_string(cached)?.as_bytes())?;
+        Ok(())
+    }
+
+    fn cached_balance_path(config: &Wallet713Config) -> Result<PathBuf> {
+        let mut path = config.get_data_path()?;
+        std::fs::create_dir_all(path.as_path())?;
+        path.push(CACHED_BALANCE_FILENAME);
+        Ok(path)
+    }
+
+    fn write_watch_data(config: &Wallet713Config, watch_data: &WatchData) -> Result<()> {
+        let mut file = File::create(Wallet::watch_data_path(config)?)?;
+        file.write_all(serde_json::to_string(watch_data)?.as_bytes())?;
+        Ok(())
+    }
+
+    fn watch_data_path(config: &Wallet713Config) -> Result<PathBuf> {
+        let mut path = config.get_data_path()?;
+        std::fs::create_dir_all(path.as_path())?;
+        path.push(WATCH_DATA_FILENAME);
+        Ok(path)
+    }
+
+    fn seed_file_exists(wallet_config: &WalletConfig) -> bool {
+        Path::new(&wallet_config.data_file_dir)
+            .join(SEED_FILE)
+            .exists()
+    }
+
+    /// Makes sure an existing seed file can be read, so a damaged one isn't mistaken
+    /// for a wrong passphrase
+    fn check_seed_file(wallet_config: &WalletConfig) -> Result<()> {
+        let path = Path::new(&wallet_config.data_file_dir).join(SEED_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        let corrupt = || ErrorKind::CorruptWallet(path.to_string_lossy().to_string());
+        let mut contents = String::new();
+        File::open(&path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|_| corrupt())?;
+        let seed: SeedFile = serde_json::from_str(&contents).map_err(|_| corrupt())?;
+        let hex_len = |field: &str| from_hex(field.to_string()).map(|bytes| bytes.len()).ok();
+        // an 8 byte salt, a 12 byte nonce and a 16 to 32 byte seed followed by a 16 byte tag
+        let well_formed = hex_len(&seed.salt) == Some(8)
+            && hex_len(&seed.nonce) == Some(12)
+            && hex_len(&seed.encrypted_seed).map_or(false, |len| len >= 32 && len <= 48);
+        if !well_formed {
+            return Err(corrupt())?;
+        }
+        Ok(())
+    }
+
+    /// The seed entropy in bytes for a mnemonic of the given number of words
+    fn seed_length(words: usize) -> Result<usize> {
+        match words {
+            12 => Ok(16),
+            24 => Ok(32),
+            _ => Err(ErrorKind::InvalidSeedWords(words).into()),
+        }
+    }
+
     fn init_seed(
         &self,
         wallet_config: &WalletConfig,
         passphrase: &str,
         create_new: bool,
+        seed_length: usize,
     ) -> Result<WalletSeed> {
         let result = WalletSeed::from_file(&wallet_config, passphrase);
         let seed = match result {
@@ -414,8 +1608,9 @@ impl Wallet {
             Err(_) => {
                 // could not load from file, let's create a new one
                 if create_new {
-                    WalletSeed::init_file(&wallet_config, 32, None, passphrase)?
+                    WalletSeed::init_file(&wallet_config, seed_length, None, passphrase)?
                 } else {
+                    Wallet::check_seed_file(&wallet_config)?;
                     return Err(ErrorKind::WalletSeedCouldNotBeOpened.into());
                 }
             }
@@ -440,28 +1635,179 @@ impl Wallet {
         passphrase: &str,
     ) -> Result<()> {
         let wallet_config = config.as_wallet_config()?;
-        let node_client = HTTPNodeClient::new(
-            &wallet_config.check_node_api_http_addr,
-            config.grin_node_secret().clone(),
-        );
+        let node_secret = node_secret(config)?;
+        let node_uri = self.select_node_uri(config, &node_secret)?;
+        let node_client = TimeoutNodeClient::new(&node_uri, node_secret, config.timeout());
+        Wallet::check_seed_file(&wallet_config)?;
         let _ = WalletSeed::from_file(&wallet_config, passphrase)?;
-        let mut db_wallet = Backend::new(&wallet_config, passphrase, node_client)?;
-        db_wallet.set_parent_key_id_by_name(account)?;
-        self.backend = Some(Arc::new(Mutex::new(db_wallet)));
+        let backend = self.open_backend(config, &wallet_config, passphrase, node_client)?;
+        backend.lock().set_parent_key_id_by_name(account)?;
+        self.backend = Some(backend);
+        self.node_timeout = Some(config.timeout());
+        self.fee_overrides =
+            FeeOverrides::new(config.fee_per_weight, config.min_fee, config.max_fee);
+        self.spend_confirmations = config.spend_confirmations();
+        self.max_selectable_outputs = config.max_selectable_outputs();
+        self.pending_slate_ttl_hours = config.pending_slate_ttl_hours();
+        self.restore_max_retries = config.restore_max_retries();
         Ok(())
     }
 
     fn init_backend(
         &self,
+        config: &Wallet713Config,
         wallet_config: &WalletConfig,
-        wallet713_config: &Wallet713Config,
+        node_uri: &str,
+        node_secret: Option<String>,
         passphrase: &str,
-    ) -> Result<Backend<HTTPNodeClient, ExtKeychain>> {
-        let node_api_client = HTTPNodeClient::new(
-            &wallet_config.check_node_api_http_addr,
-            wallet713_config.grin_node_secret().clone(),
-        );
-        let backend = Backend::new(wallet_config, passphrase, node_api_client)?;
+    ) -> Result<()> {
+        let node_api_client = TimeoutNodeClient::new(node_uri, node_secret, config.timeout());
+        self.open_backend(config, wallet_config, passphrase, node_api_client)?;
+        Ok(())
+    }
+
+    /// Opens the storage backend picked with `storage_backend`. The memory backend
+    /// keeps its data in the wallet's own store, so it survives locking the wallet.
+    fn open_backend(
+        &self,
+        config: &Wallet713Config,
+        wallet_config: &WalletConfig,
+        passphrase: &str,
+        node_client: TimeoutNodeClient,
+    ) -> Result<Arc<Mutex<dyn WalletInst<TimeoutNodeClient, ExtKeychain>>>> {
+        let backend: Arc<Mutex<dyn WalletInst<TimeoutNodeClient, ExtKeychain>>> =
+            match config.storage_backend() {
+                "lmdb" => Arc::new(Mutex::new(Backend::new(
+                    wallet_config,
+                    passphrase,
+                    node_client,
+                )?)),
+                "memory" => Arc::new(Mutex::new(MemoryBackend::new(
+                    wallet_config,
+                    passphrase,
+                    node_client,
+                    self.memory_store.clone(),
+                )?)),
+                other => Err(ErrorKind::InvalidStorageBackend(other.to_string()))?,
+            };
         Ok(backend)
     }
 }
+
+/// The secret for the node api. A `grin_node_secret_file` takes precedence over the
+/// inline `grin_node_secret`, and is read every time a node client is created.
+fn node_secret(config: &Wallet713Config) -> Result<Option<String>> {
+    match config.grin_node_secret_file {
+        Some(ref path) => {
+            let mut content = String::new();
+            File::open(path)
+                .and_then(|mut file| file.read_to_string(&mut content))
+                .map_err(|_| ErrorKind::NodeSecretFile(path.clone()))?;
+            Ok(Some(content.trim().to_string()))
+        }
+        None => Ok(config.grin_node_secret()),
+    }
+}
+
+/// Where and how to reach the node api, copied out of the wallet so a request
+/// doesn't keep the wallet locked while it waits for the node
+pub struct NodeApi {
+    url: String,
+    secret: Option<String>,
+    timeout: Option<u64>,
+}
+
+impl NodeApi {
+    /// Queries the node's api at `/v1/<path>`, `None` on any failure
+    fn get<T>(&self, path: &str) -> Option<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let url = format!("{}/v1/{}", self.url, path);
+        let secret = self.secret.clone();
+        let get = move || client::get::<T>(url.as_str(), secret);
+        match self.timeout {
+            Some(timeout) => with_timeout(timeout, get).ok()?.ok(),
+            None => get().ok(),
+        }
+    }
+
+    /// The chain the node is running, identified by its genesis block. `None` if the
+    /// node can't be reached or runs a chain we don't know
+    pub fn chain_type(&self) -> Option<ChainTypes> {
+        let header: BlockHeaderPrintable = self.get("headers/0")?;
+        if header.hash == genesis::genesis_main().hash().to_hex() {
+            Some(ChainTypes::Mainnet)
+        } else if header.hash == genesis::genesis_floo().hash().to_hex() {
+            Some(ChainTypes::Floonet)
+        } else {
+            None
+        }
+    }
+}
+
+/// Runs `f` on its own thread, failing with `ErrorKind::Timeout` if it does not finish
+/// within `secs` seconds. The thread is left to finish in the background.
+fn with_timeout<T, F>(secs: u64, f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        tx.send(f()).is_ok();
+    });
+    let result = rx
+        .recv_timeout(Duration::from_secs(secs))
+        .map_err(|_| ErrorKind::Timeout(secs))?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    fn wallet_config() -> WalletConfig {
+        let dir = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        WalletConfig {
+            data_file_dir: dir.to_string_lossy().to_string(),
+            ..WalletConfig::default()
+        }
+    }
+
+    #[test]
+    fn accepts_a_seed_file() {
+        let config = wallet_config();
+        WalletSeed::init_file(&config, 32, None, "passphrase").unwrap();
+        assert!(Wallet::check_seed_file(&config).is_ok());
+        fs::remove_dir_all(&config.data_file_dir).unwrap();
+    }
+
+    #[test]
+    fn reports_a_damaged_seed_file() {
+        let config = wallet_config();
+        let path = Path::new(&config.data_file_dir).join(SEED_FILE);
+        let damaged = vec![
+            "\u{0}\u{1}garbage".to_string(),
+            r#"{"encrypted_seed":"zz","salt":"00","nonce":"00"}"#.to_string(),
+            r#"{"encrypted_seed":"00","salt":"0000000000000000","nonce":"000000000000000000000000"}"#
+                .to_string(),
+        ];
+        for contents in damaged {
+            File::create(&path)
+                .unwrap()
+                .write_all(contents.as_bytes())
+                .unwrap();
+            let err = Wallet::check_seed_file(&config).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<ErrorKind>(),
+                Some(&ErrorKind::CorruptWallet(
+                    path.to_string_lossy().to_string()
+                ))
+            );
+        }
+        fs::remove_dir_all(&config.data_file_dir).unwrap();
+    }
+}